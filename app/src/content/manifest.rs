@@ -0,0 +1,312 @@
+use std::{collections::HashMap, path::Path, sync::{OnceLock, RwLock, RwLockReadGuard}};
+
+use serde::{Deserialize, Serialize};
+
+/// One registry entry recorded in the world manifest.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "EntryFile")]
+pub struct Entry {
+    pub name: String,
+    /// Content pack that provided the entry, `None` for the base game
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pack: Option<String>,
+    /// Live voxel kind, only recorded for blocks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub live_voxel: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EntryFile {
+    /// Older worlds stored only the block names
+    Name(String),
+    Entry {
+        name: String,
+        #[serde(default)]
+        pack: Option<String>,
+        #[serde(default)]
+        live_voxel: Option<String>,
+    },
+}
+
+impl From<EntryFile> for Entry {
+    fn from(file: EntryFile) -> Self {
+        match file {
+            EntryFile::Name(name) => Self { name, pack: None, live_voxel: None },
+            EntryFile::Entry { name, pack, live_voxel } => Self { name, pack, live_voxel },
+        }
+    }
+}
+
+impl Entry {
+    pub fn new(name: &str, pack: Option<&str>) -> Self {
+        Self { name: name.to_string(), pack: pack.map(String::from), live_voxel: None }
+    }
+}
+
+/// Item ids of the worlds saved before the manifest recorded items, in the order of the old hardcoded table.
+const LEGACY_ITEMS: [&str; 14] = [
+    "iron_ore", "iron_ingot", "iron_plate", "rock", "furnace", "transport_belt", "drill",
+    "box", "manipulator", "assembling_machine", "glass_classic", "glass_green", "cowboy", "glass_red",
+];
+/// Recipe ids of the same worlds.
+const LEGACY_RECIPES: [&str; 12] = [
+    "iron_plate", "furnace", "iron_ingot", "transport_belt", "drill", "box",
+    "manipulator", "assembling_machine", "glass_classic", "glass_green", "cowboy", "glass_red",
+];
+
+fn legacy_entries(names: &[&str]) -> Vec<Entry> {
+    names.iter().map(|name| Entry::new(name, None)).collect()
+}
+
+fn legacy_items() -> Vec<Entry> {legacy_entries(&LEGACY_ITEMS)}
+fn legacy_recipes() -> Vec<Entry> {legacy_entries(&LEGACY_RECIPES)}
+fn legacy_world() -> bool {true}
+
+/// Every registry of the world, the position of an entry is the id stored in the world files.
+/// Entries are never removed, new content is appended.
+/// New worlds start from [`Manifest::default`], a saved manifest without items or recipes is from an older world.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Ids start after the builtin blocks
+    #[serde(default)]
+    pub blocks: Vec<Entry>,
+    #[serde(default = "legacy_items")]
+    pub items: Vec<Entry>,
+    #[serde(default = "legacy_recipes")]
+    pub recipes: Vec<Entry>,
+    #[serde(default)]
+    pub live_voxels: Vec<Entry>,
+    #[serde(default)]
+    pub fluids: Vec<Entry>,
+    /// The world is older than power, its machines work without a power network
+    #[serde(default = "legacy_world")]
+    pub without_power: bool,
+}
+
+/// Name of the content pack from a `@pack:name` id.
+pub fn pack_name(name: &str) -> Option<&str> {
+    name.strip_prefix('@').and_then(|name| name.split_once(':')).map(|(pack, _)| pack)
+}
+
+/// Maps the ids stored in a world to the ids of the loaded content and back.
+/// Ids that are not in the map are left as is.
+#[derive(Debug, Default)]
+pub struct IdMap {
+    to_runtime: Vec<u32>,
+    to_saved: Vec<u32>,
+}
+
+impl IdMap {
+    pub const fn new() -> Self {
+        Self { to_runtime: Vec::new(), to_saved: Vec::new() }
+    }
+
+    pub fn runtime(&self, saved: u32) -> u32 {
+        self.to_runtime.get(saved as usize).copied().unwrap_or(saved)
+    }
+
+    pub fn saved(&self, runtime: u32) -> u32 {
+        self.to_saved.get(runtime as usize).copied().unwrap_or(runtime)
+    }
+}
+
+/// Merges the saved registry with the loaded one.
+/// Saved entries that are not loaded get placeholder ids after the loaded ones.
+/// Returns the new registry, the id map and the missing entries.
+pub fn remap(saved: &[Entry], loaded: &[Entry]) -> (Vec<Entry>, IdMap, Vec<Entry>) {
+    let runtime_ids: HashMap<&str, u32> = loaded.iter().enumerate()
+        .map(|(id, entry)| (entry.name.as_str(), id as u32))
+        .collect();
+    let mut entries = saved.to_vec();
+    let mut to_runtime = Vec::with_capacity(saved.len());
+    let mut to_saved = vec![None; loaded.len()];
+    let mut placeholders = Vec::new();
+    let mut missing = Vec::new();
+    for (saved_id, entry) in saved.iter().enumerate() {
+        match runtime_ids.get(entry.name.as_str()) {
+            Some(&id) => {
+                to_runtime.push(id);
+                to_saved[id as usize] = Some(saved_id as u32);
+                entries[saved_id] = loaded[id as usize].clone();
+            },
+            None => {
+                to_runtime.push((loaded.len() + placeholders.len()) as u32);
+                placeholders.push(saved_id as u32);
+                missing.push(entry.clone());
+            },
+        }
+    }
+    let mut to_saved: Vec<u32> = to_saved.into_iter().enumerate().map(|(id, saved_id)| {
+        saved_id.unwrap_or_else(|| {
+            entries.push(loaded[id].clone());
+            to_runtime.push(id as u32);
+            (entries.len() - 1) as u32
+        })
+    }).collect();
+    to_saved.extend(placeholders);
+
+    (entries, IdMap { to_runtime, to_saved }, missing)
+}
+
+pub fn report_missing(world: &Path, kind: &str, missing: &[Entry]) {
+    let world = world.file_name().and_then(|w| w.to_str()).unwrap_or("unknown");
+    missing.iter().for_each(|entry| match &entry.pack {
+        Some(pack) => eprintln!("World \"{}\" needs {} \"{}\" from pack \"{}\", a placeholder is used", world, kind, entry.name, pack),
+        None => eprintln!("World \"{}\" needs {} \"{}\" from the base game, a placeholder is used", world, kind, entry.name),
+    });
+}
+
+/// Id maps of the opened world, used when items, recipes and fluids are saved and loaded.
+#[derive(Debug, Default)]
+pub struct IdMaps {
+    pub items: IdMap,
+    pub recipes: IdMap,
+    pub fluids: IdMap,
+}
+
+static ID_MAPS: RwLock<IdMaps> = RwLock::new(IdMaps { items: IdMap::new(), recipes: IdMap::new(), fluids: IdMap::new() });
+
+pub fn set_id_maps(maps: IdMaps) {
+    *ID_MAPS.write().unwrap() = maps;
+}
+
+pub fn id_maps() -> RwLockReadGuard<'static, IdMaps> {
+    ID_MAPS.read().unwrap()
+}
+
+pub mod item_id {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(id: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+        super::id_maps().items.saved(*id).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+        u32::deserialize(deserializer).map(|id| super::id_maps().items.runtime(id))
+    }
+}
+
+/// [`item_id`] for an item that may be absent.
+pub mod option_item_id {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(id: &Option<u32>, serializer: S) -> Result<S::Ok, S::Error> {
+        id.map(|id| super::id_maps().items.saved(id)).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u32>, D::Error> {
+        Option::<u32>::deserialize(deserializer).map(|id| id.map(|id| super::id_maps().items.runtime(id)))
+    }
+}
+
+pub mod recipe_id {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(id: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+        super::id_maps().recipes.saved(*id).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+        u32::deserialize(deserializer).map(|id| super::id_maps().recipes.runtime(id))
+    }
+}
+
+pub mod fluid_id {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(id: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+        super::id_maps().fluids.saved(*id).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+        u32::deserialize(deserializer).map(|id| super::id_maps().fluids.runtime(id))
+    }
+}
+
+pub mod recipe_index {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(index: &usize, serializer: S) -> Result<S::Ok, S::Error> {
+        (super::id_maps().recipes.saved(*index as u32) as usize).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+        usize::deserialize(deserializer).map(|index| super::id_maps().recipes.runtime(index as u32) as usize)
+    }
+}
+
+/// Content packs of the mods that registered items, recipes and live voxels.
+/// Entries that are not here come from the base game.
+#[derive(Debug, Default)]
+pub struct Providers {
+    pub items: HashMap<String, String>,
+    pub recipes: HashMap<String, String>,
+    pub live_voxels: HashMap<String, String>,
+}
+
+static PROVIDERS_CONTAINER: OnceLock<Providers> = OnceLock::new();
+
+pub fn init_providers(providers: Providers) {
+    PROVIDERS_CONTAINER.set(providers).expect("Providers are already initialized");
+}
+
+#[allow(non_snake_case)]
+pub fn PROVIDERS() -> &'static Providers {
+    PROVIDERS_CONTAINER.get_or_init(Providers::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(names: &[&str]) -> Vec<Entry> {
+        legacy_entries(names)
+    }
+
+    fn names(entries: &[Entry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.name.as_str()).collect()
+    }
+
+    #[test]
+    fn new_registry_takes_the_loaded_order() {
+        let (merged, map, missing) = remap(&[], &entries(&["a", "b"]));
+        assert_eq!(names(&merged), ["a", "b"]);
+        assert_eq!((map.runtime(1), map.saved(0)), (1, 0));
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn saved_ids_are_kept_and_new_entries_appended() {
+        let (merged, map, missing) = remap(&entries(&["c", "a"]), &entries(&["a", "b", "c"]));
+        assert_eq!(names(&merged), ["c", "a", "b"]);
+        assert_eq!([map.runtime(0), map.runtime(1), map.runtime(2)], [2, 0, 1]);
+        assert_eq!([map.saved(0), map.saved(1), map.saved(2)], [1, 2, 0]);
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn missing_entries_get_placeholders_after_the_loaded_ones() {
+        let (merged, map, missing) = remap(&entries(&["gone", "a"]), &entries(&["a", "b"]));
+        assert_eq!(names(&merged), ["gone", "a", "b"]);
+        assert_eq!(names(&missing), ["gone"]);
+        assert_eq!((map.runtime(0), map.runtime(1)), (2, 0));
+        assert_eq!((map.saved(2), map.saved(1)), (0, 2));
+    }
+
+    #[test]
+    fn legacy_manifest_uses_the_old_item_ids() {
+        let manifest: Manifest = serde_json::from_str(r#"{"blocks": ["rock"]}"#).unwrap();
+        assert_eq!(names(&manifest.items), LEGACY_ITEMS);
+        assert_eq!(names(&manifest.recipes), LEGACY_RECIPES);
+        assert!(Manifest::default().items.is_empty());
+
+        let mut loaded = LEGACY_ITEMS.to_vec();
+        loaded.sort();
+        let (_, map, missing) = remap(&manifest.items, &entries(&loaded));
+        assert!(missing.is_empty());
+        // The iron ore of an old inventory is still iron ore.
+        assert_eq!(loaded[map.runtime(0) as usize], "iron_ore");
+        assert_eq!(map.saved(loaded.iter().position(|&name| name == "glass_red").unwrap() as u32), 13);
+    }
+}
\ No newline at end of file
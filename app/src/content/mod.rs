@@ -1,63 +1,135 @@
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+use arc_swap::ArcSwap;
 
-use serde::{Deserialize, Serialize};
 use crate::Indices;
+use crate::content_loader::{error::{ContentError, ContentErrors}, indices::{check_duplicate, GamePath}};
 use crate::graphic::complex_object::{load_complex_object, ComplexObject};
-use crate::{voxels::{block::{block_test::{to_block, Block, BlockBase, BlockFile}, block_type::BlockType, functions::{on_multiblock_break}}, live_voxels::{register, LiveVoxelRegistrator}}};
+use crate::recipes::{fluids::FLUIDS, items::ITEMS, recipes::RECIPES};
+use crate::scripting::load_scripts;
+use crate::{voxels::{block::{block_test::{to_block, Block, BlockBase, BlockFile}, block_type::BlockType, functions::{on_multiblock_break, player_add_item}}, live_voxels::{register, LiveVoxelRegistrator}}};
+
+use self::tags::{load_tag_members, Tag, TagSet};
+use self::manifest::{pack_name, remap, report_missing, set_id_maps, Entry, IdMaps, Manifest, PROVIDERS};
+
+pub mod manifest;
+pub mod tags;
+
+pub(crate) fn block_files<'a>(block_paths: &'a [GamePath<PathBuf>], errors: &mut ContentErrors) -> Result<Vec<(String, PathBuf, &'a str)>, ContentError> {
+    let mut seen = HashMap::<String, PathBuf>::new();
+    let mut files = Vec::new();
+    for block_path in block_paths {
+        for file in block_path.files() {
+            let Some((name, file)) = errors.check(file)? else {continue};
+            if errors.check(check_duplicate(&mut seen, "block", &name, &file))?.is_none() {continue};
+            files.push((name, file, block_path.prefix()));
+        }
+    }
+    Ok(files)
+}
+
+/// Tags the tag files give to blocks, by block name.
+pub(crate) fn block_tags(content: &[GamePath<PathBuf>], files: &[(String, PathBuf, &str)], errors: &mut ContentErrors) -> Result<HashMap<String, TagSet>, ContentError> {
+    let tag_paths = content.iter().map(|c| c.join("tags/")).collect::<Vec<_>>();
+    let names = files.iter().map(|(name, _, _)| name.as_str()).collect::<HashSet<_>>();
+    let mut tags = HashMap::<String, TagSet>::new();
+    for member in load_tag_members(&tag_paths, errors)?.blocks {
+        let prefixed = format!("{}{}", member.prefix, member.name);
+        let name = if names.contains(prefixed.as_str()) {prefixed} else {member.name.clone()};
+        if !names.contains(name.as_str()) {
+            errors.report(ContentError::missing(&member.path, member.field, "block", member.name))?;
+            continue;
+        }
+        tags.entry(name).or_default().insert(member.tag);
+    }
+    Ok(tags)
+}
+
+pub(crate) fn load_block_file(path: &Path) -> Result<BlockFile, ContentError> {
+    let data = std::fs::read(path).map_err(|err| ContentError::io(path, err))?;
+    serde_json::from_slice(&data).map_err(|err| ContentError::json(path, err))
+}
+
+pub type ComplexObjects = (HashMap::<String, u32>, Box<[ComplexObject]>);
 
 pub fn load_complex_objects(
-    complex_objects_path: impl AsRef<Path>,
-    tmp_indices: &Indices
-) -> (HashMap::<String, u32>, Box<[ComplexObject]>) {
-    let files = walkdir::WalkDir::new(complex_objects_path)
-        .into_iter()
-        .filter_map(|f| f.ok())
-        .filter(|f| f.file_type().is_file())
-        .enumerate();
+    complex_objects_paths: &[GamePath<PathBuf>],
+    tmp_indices: &Indices,
+    errors: &mut ContentErrors,
+) -> Result<ComplexObjects, ContentError> {
+    let mut seen = HashMap::<String, PathBuf>::new();
     let mut indices = HashMap::<String, u32>::new();
-    let complex_objects: Box<[ComplexObject]> = files.map(|(index, file)| {
-        let file_name = file.file_name().to_str().unwrap();
-        let dot_index = file_name.rfind('.').unwrap();
-        let name = file_name[..dot_index].to_string();
-        let model = load_complex_object(file.path(), tmp_indices);
-        indices.insert(name, index as u32);
-        model
-    }).collect();
-
-    (indices, complex_objects)
+    let mut complex_objects = Vec::new();
+    for path in complex_objects_paths {
+        for file in path.files() {
+            let Some((name, file)) = errors.check(file)? else {continue};
+            if errors.check(check_duplicate(&mut seen, "complex object", &name, &file))?.is_none() {continue};
+            let Some(model) = errors.check(load_complex_object(&file, tmp_indices, path.prefix()))? else {continue};
+            indices.insert(name, complex_objects.len() as u32);
+            complex_objects.push(model);
+        }
+    }
+
+    Ok((indices, complex_objects.into_boxed_slice()))
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ContentIndices {
-    pub blocks: Vec<String>,
+/// Stands in for a saved block whose provider is gone, so its id and live voxel are kept.
+fn placeholder_block(id: u32, live_voxel: Option<String>) -> Block {
+    Block {
+        base: BlockBase {
+            id,
+            item_id: None,
+            emission: [0, 0, 0],
+            block_type: BlockType::None,
+            width: 1,
+            height: 1,
+            depth: 1,
+            is_light_passing: true,
+            live_voxel,
+            tags: TagSet::default(),
+            item_filter: None,
+            drops: Box::new([]),
+        },
+        on_block_break: Box::new([]),
+        on_block_set: Box::new([]),
+        script: None,
+    }
 }
 
 #[derive(Debug)]
 pub struct Content {
     pub block_indexes: HashMap<String, u32>,
     pub co_indices: HashMap<String, u32>,
-    pub blocks: Vec<Block>,
+    /// Swappable so that blocks can be reloaded while the world is open
+    pub blocks: Box<[ArcSwap<Block>]>,
     pub complex_objects: Box<[ComplexObject]>,
 
     pub live_voxel: LiveVoxelRegistrator,
+    /// Machines without a power network work at full speed, see [`Manifest::without_power`]
+    pub without_power: bool,
 }
 
 impl Content {
-    pub fn new(indices: &Indices, path: impl AsRef<Path>) -> Self {
-        let path = path.as_ref().join("indices.json");
-        let content_indices = if let Ok(bytes) = std::fs::read(&path) {
-            Some(serde_json::from_slice::<ContentIndices>(&bytes).unwrap())
-        } else {
-            None
+    pub fn new(indices: &Indices, path: impl AsRef<Path>) -> Result<Self, ContentError> {
+        let world = path.as_ref().to_path_buf();
+        let path = world.join("indices.json");
+        let manifest = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice::<Manifest>(&bytes)
+                .map_err(|err| ContentError::json(&path, err))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Manifest::default(),
+            Err(err) => return Err(ContentError::io(&path, err)),
         };
         let mut block_indexes = HashMap::<String, u32>::new();
-        let (co_indices, complex_objects) = load_complex_objects("./res/game/complex_objects", indices);
-        let files = walkdir::WalkDir::new("./res/game/blocks/")
-            .into_iter()
-            .filter_map(|f| f.ok())
-            .filter(|f| f.file_type().is_file());
+        let co_paths = indices.content.iter().map(|c| c.join("complex_objects/")).collect::<Vec<_>>();
+        let errors = &mut ContentErrors::fail_fast();
+        let (co_indices, complex_objects) = load_complex_objects(&co_paths, indices, errors)?;
+        let block_paths = indices.content.iter().map(|c| c.join("blocks/")).collect::<Vec<_>>();
+        let files = block_files(&block_paths, errors)?;
+        let tags = block_tags(&indices.content, &files, errors)?;
+        let script_paths = indices.content.iter().map(|c| c.join("scripts/")).collect::<Vec<_>>();
+        let scripts = load_scripts(&script_paths, errors)?;
 
         let mut blocks = vec![
             Block {
@@ -71,11 +143,13 @@ impl Content {
                     depth: 1,
                     is_light_passing: true,
                     live_voxel: None,
-                    is_glass: false,
-                    is_ore: false
+                    tags: TagSet::default(),
+                    item_filter: None,
+                    drops: Box::new([]),
                 },
                 on_block_break: Box::new([]),
                 on_block_set: Box::new([]),
+                script: None,
             },
             Block {
                 base: BlockBase {
@@ -88,61 +162,159 @@ impl Content {
                     depth: 1,
                     is_light_passing: true,
                     live_voxel: None,
-                    is_glass: false,
-                    is_ore: false
+                    tags: TagSet::default(),
+                    item_filter: None,
+                    drops: Box::new([]),
                 },
-                on_block_break: Box::new([&on_multiblock_break]),
+                // Parts of a multiblock drop the items of the whole structure.
+                on_block_break: Box::new([&player_add_item, &on_multiblock_break]),
                 on_block_set: Box::new([]),
+                script: None,
             },
         ];
         let blocks_init_len = blocks.len();
-        if let Some(content) = content_indices {
-            let mut all_count = 0;
-            let mut id = blocks.len() as u32 + content.blocks.len() as u32;
-            files.for_each(|file| {
-                let file_name = file.file_name().to_str().unwrap();
-                let dot_index = file_name.rfind('.').unwrap();
-                let name = file_name[0..dot_index].to_string();
-                let data = std::fs::read(file.path()).unwrap();
-                let block_file: BlockFile = serde_json::from_slice(&data).unwrap();
-
-                if let Some(position) = content.blocks.iter().position(|s| s == &name) {
-                    all_count += 1;
-                    block_indexes.insert(name, position as u32 + blocks_init_len as u32);
-                    blocks.push(to_block(block_file, indices, &co_indices, position as u32 + blocks_init_len as u32));
-                } else {
-                    block_indexes.insert(name, id);
-                    blocks.push(to_block(block_file, indices, &co_indices, id));
-                    id += 1;
-                };
-            });
-            if all_count < content.blocks.len() {
-                panic!("Missing contents");
-            }
-        } else {
-            let mut id = blocks.len() as u32;
-            files.for_each(|file| {
-                let name = file.file_name().to_str().unwrap();
-                let dot_index = name.rfind('.').unwrap();
-                block_indexes.insert(name[0..dot_index].to_string(), id);
-
-                let data = std::fs::read(file.path()).unwrap();
-                let block_file: BlockFile = serde_json::from_slice(&data).unwrap();
-
-                blocks.push(to_block(block_file, indices, &co_indices, id));
-                id += 1;
+        // Blocks saved in the world keep their ids, new ones are added after them.
+        let mut block_entries = manifest.blocks.clone();
+        for (name, file, prefix) in files {
+            let block_file = load_block_file(&file)?;
+
+            let position = block_entries.iter().position(|e| e.name == name).unwrap_or_else(|| {
+                block_entries.push(Entry::default());
+                block_entries.len() - 1
             });
+            let block_id = (position + blocks_init_len) as u32;
+            block_entries[position] = Entry {
+                live_voxel: block_file.live_voxel.clone(),
+                ..Entry::new(&name, pack_name(&name))
+            };
+            let mut block = to_block(block_file, indices, &co_indices, &scripts, prefix, block_id, &file)?;
+            block.base.tags.extend(tags.get(&name).into_iter().flat_map(TagSet::iter));
+            blocks.push(block);
+            block_indexes.insert(name, block_id);
+        }
+        let missing_blocks: Vec<Entry> = manifest.blocks.iter().enumerate()
+            .filter(|(_, entry)| !block_indexes.contains_key(&entry.name))
+            .map(|(position, entry)| {
+                blocks.push(placeholder_block((position + blocks_init_len) as u32, entry.live_voxel.clone()));
+                entry.clone()
+            }).collect();
+        blocks.sort_by_key(|block| block.id());
+
+        let item_entries: Vec<Entry> = ITEMS().all.iter()
+            .map(|item| Entry::new(&item.name, PROVIDERS().items.get(&item.name).map(String::as_str).or(pack_name(&item.name))))
+            .collect();
+        let (item_entries, items, missing_items) = remap(&manifest.items, &item_entries);
+        let recipe_entries: Vec<Entry> = RECIPES().all.iter()
+            .map(|recipe| Entry::new(&recipe.name, PROVIDERS().recipes.get(&recipe.name).map(String::as_str).or(pack_name(&recipe.name))))
+            .collect();
+        let (recipe_entries, recipes, missing_recipes) = remap(&manifest.recipes, &recipe_entries);
+        let fluid_entries: Vec<Entry> = FLUIDS().all.iter()
+            .map(|fluid| Entry::new(&fluid.name, pack_name(&fluid.name)))
+            .collect();
+        let (fluid_entries, fluids, missing_fluids) = remap(&manifest.fluids, &fluid_entries);
+        set_id_maps(IdMaps { items, recipes, fluids });
+
+        let live_voxel = register();
+        let mut live_voxel_entries: Vec<Entry> = live_voxel.new.keys()
+            .map(|name| Entry::new(name, PROVIDERS().live_voxels.get(name).map(String::as_str)))
+            .collect();
+        live_voxel_entries.sort_by(|a, b| a.name.cmp(&b.name));
+        let (live_voxel_entries, _, missing_live_voxels) = remap(&manifest.live_voxels, &live_voxel_entries);
+
+        report_missing(&world, "block", &missing_blocks);
+        report_missing(&world, "item", &missing_items);
+        report_missing(&world, "recipe", &missing_recipes);
+        report_missing(&world, "live voxel", &missing_live_voxels);
+        report_missing(&world, "fluid", &missing_fluids);
+
+        let manifest = Manifest {
+            blocks: block_entries,
+            items: item_entries,
+            recipes: recipe_entries,
+            live_voxels: live_voxel_entries,
+            fluids: fluid_entries,
+            without_power: manifest.without_power,
+        };
+        let data = serde_json::to_vec_pretty(&manifest).unwrap();
+        std::fs::write(&path, data).map_err(|err| ContentError::io(&path, err))?;
+
+        let blocks = blocks.into_iter().map(ArcSwap::from_pointee).collect();
+        Ok(Self { blocks, block_indexes, live_voxel, co_indices, complex_objects, without_power: manifest.without_power })
+    }
+
+    /// Ids of the blocks with the tag, use [`Block::has_tag`] to check a single block.
+    pub fn blocks_with_tag(&self, tag: Tag) -> Vec<u32> {
+        self.blocks.iter().map(|block| block.load())
+            .filter(|block| block.has_tag(tag))
+            .map(|block| block.id())
+            .collect()
+    }
+
+    /// Rebuilds the blocks whose files pass the filter and returns their ids.
+    /// Scripts are compiled again, so blocks with changed scripts have to pass the filter too.
+    /// Block files that were added or removed are picked up only when the world is opened again.
+    pub fn reload_blocks(&self, indices: &Indices, filter: impl Fn(&Path) -> bool) -> Result<Vec<u32>, ContentError> {
+        let block_paths = indices.content.iter().map(|c| c.join("blocks/")).collect::<Vec<_>>();
+        let script_paths = indices.content.iter().map(|c| c.join("scripts/")).collect::<Vec<_>>();
+        let errors = &mut ContentErrors::fail_fast();
+        let scripts = load_scripts(&script_paths, errors)?;
+        let files = block_files(&block_paths, errors)?;
+        let tags = block_tags(&indices.content, &files, errors)?;
+        let mut reloaded = Vec::new();
+        for (name, file, prefix) in files {
+            let Some(&id) = self.block_indexes.get(&name).filter(|_| filter(&file)) else {continue};
+            let mut block = to_block(load_block_file(&file)?, indices, &self.co_indices, &scripts, prefix, id, &file)?;
+            block.base.tags.extend(tags.get(&name).into_iter().flat_map(TagSet::iter));
+            self.blocks[id as usize].store(Arc::new(block));
+            reloaded.push(id);
         }
-        
-        let mut content_blocks = vec![String::new(); block_indexes.len()];
-        block_indexes.iter().for_each(|(s, i)| {
-            content_blocks[*i as usize - blocks_init_len] = s.clone();
-        });
-        println!("{:?} {:?}", content_blocks, block_indexes);
-        let content_indices = ContentIndices {blocks: content_blocks};
-        let data = serde_json::to_vec_pretty(&content_indices).unwrap();
-        std::fs::write(path, data).unwrap();
-
-        Self { blocks, block_indexes, live_voxel: register(), co_indices, complex_objects }
+        Ok(reloaded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::content_loader::test_content;
+    use super::*;
+
+    fn write_block(game: &Path, emission: u8, texture: &str, tags: &str) {
+        let block = format!(r#"{{"id": "lamp", "block_type": {{"block": {{"faces": "{}"}}}}, "emission": [{}, 0, 0], "tags": [{}]}}"#,
+            texture, emission, tags);
+        std::fs::write(game.join("blocks/lamp.json"), block).unwrap();
+    }
+
+    #[test]
+    fn reload_picks_up_changed_blocks() {
+        // Items and recipes come from the game content of the workspace.
+        test_content();
+        let root = tempfile::tempdir().unwrap();
+        let (game, world) = (root.path().join("game"), root.path().join("world"));
+        std::fs::create_dir_all(game.join("blocks")).unwrap();
+        std::fs::create_dir_all(&world).unwrap();
+        write_block(&game, 0, "dark", "");
+        let indices = Indices {
+            block: HashMap::from([(String::from("dark"), 1), (String::from("bright"), 2)]),
+            models: HashMap::new(),
+            animated_models: HashMap::new(),
+            content: vec![GamePath { path: game.clone(), prefix: None }],
+        };
+
+        let content = Content::new(&indices, &world).unwrap();
+        let id = content.block_indexes["lamp"];
+        let block = content.blocks[id as usize].load();
+        assert_eq!(block.base.emission, [0, 0, 0]);
+        assert!(matches!(block.base.block_type, BlockType::Block { faces: [1, 1, 1, 1, 1, 1] }));
+
+        write_block(&game, 15, "bright", r#""glowing""#);
+        let unchanged = content.reload_blocks(&indices, |_| false).unwrap();
+        assert!(unchanged.is_empty());
+        assert_eq!(content.blocks[id as usize].load().base.emission, [0, 0, 0]);
+
+        assert_eq!(content.reload_blocks(&indices, |path| path.ends_with("lamp.json")).unwrap(), [id]);
+        let block = content.blocks[id as usize].load();
+        assert_eq!(block.base.emission, [15, 0, 0]);
+        assert!(matches!(block.base.block_type, BlockType::Block { faces: [2, 2, 2, 2, 2, 2] }));
+        assert!(block.has_tag(Tag::new("glowing")));
+        assert_eq!(content.block_indexes["lamp"], id);
     }
 }
\ No newline at end of file
@@ -0,0 +1,236 @@
+use std::{collections::HashMap, path::{Path, PathBuf}, sync::{OnceLock, RwLock}};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::content_loader::{error::{ContentError, ContentErrors}, indices::GamePath};
+
+/// Tag of blocks and items, such as `#ore`, `#glass` or `#fuel`.
+/// Tag names are interned, so tags are compared and looked up by index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Tag(u32);
+
+#[derive(Debug)]
+struct TagNames {
+    names: Vec<String>,
+    indices: HashMap<String, u32>,
+}
+
+static TAG_NAMES: OnceLock<RwLock<TagNames>> = OnceLock::new();
+
+fn tag_names() -> &'static RwLock<TagNames> {
+    TAG_NAMES.get_or_init(|| {
+        let names: Vec<String> = Tag::BUILTIN.iter().map(|name| name.to_string()).collect();
+        let indices = names.iter().enumerate().map(|(i, name)| (name.clone(), i as u32)).collect();
+        RwLock::new(TagNames { names, indices })
+    })
+}
+
+impl Tag {
+    /// Blocks rendered as glass, the faces between two glass blocks of the same kind are hidden
+    pub const GLASS: Tag = Tag(0);
+    /// Blocks that drills mine into their item
+    pub const ORE: Tag = Tag(1);
+    const BUILTIN: [&'static str; 2] = ["glass", "ore"];
+
+    /// The `#` in front of the name is optional.
+    pub fn new(name: &str) -> Self {
+        let name = name.strip_prefix('#').unwrap_or(name);
+        if let Some(tag) = Self::get(name) {return tag};
+        let mut tag_names = tag_names().write().unwrap();
+        if let Some(&index) = tag_names.indices.get(name) {return Tag(index)};
+        let index = tag_names.names.len() as u32;
+        tag_names.names.push(name.to_string());
+        tag_names.indices.insert(name.to_string(), index);
+        Tag(index)
+    }
+
+    /// Returns `None` if no content has used the tag.
+    pub fn get(name: &str) -> Option<Self> {
+        let name = name.strip_prefix('#').unwrap_or(name);
+        tag_names().read().unwrap().indices.get(name).map(|&index| Tag(index))
+    }
+
+    pub fn name(&self) -> String {
+        tag_names().read().unwrap().names[self.0 as usize].clone()
+    }
+}
+
+impl Serialize for Tag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Tag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|name| Tag::new(&name))
+    }
+}
+
+/// Tags of one block or item, a bitset indexed by tag.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagSet(Vec<u64>);
+
+impl TagSet {
+    pub fn insert(&mut self, tag: Tag) {
+        let (word, bit) = (tag.0 as usize / 64, tag.0 % 64);
+        if self.0.len() <= word {self.0.resize(word + 1, 0)};
+        self.0[word] |= 1 << bit;
+    }
+
+    #[inline]
+    pub fn contains(&self, tag: Tag) -> bool {
+        self.0.get(tag.0 as usize / 64).is_some_and(|word| word & (1 << (tag.0 % 64)) != 0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|word| *word == 0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Tag> + '_ {
+        self.0.iter().enumerate().flat_map(|(word, bits)| (0..64)
+            .filter(move |bit| bits & (1 << bit) != 0)
+            .map(move |bit| Tag((word * 64 + bit) as u32)))
+    }
+}
+
+impl FromIterator<Tag> for TagSet {
+    fn from_iter<I: IntoIterator<Item = Tag>>(iter: I) -> Self {
+        let mut set = TagSet::default();
+        iter.into_iter().for_each(|tag| set.insert(tag));
+        set
+    }
+}
+
+impl Extend<Tag> for TagSet {
+    fn extend<I: IntoIterator<Item = Tag>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|tag| self.insert(tag));
+    }
+}
+
+impl Serialize for TagSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for TagSet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<Tag>::deserialize(deserializer).map(TagSet::from_iter)
+    }
+}
+
+/// `tags/<tag>.json`, adds the tag to blocks and items declared elsewhere.
+/// Files of the same tag in the base game and in content packs are merged.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct TagFile {
+    #[serde(default)]
+    pub blocks: Vec<String>,
+    #[serde(default)]
+    pub items: Vec<String>,
+}
+
+/// A block or an item named in a tag file, `prefix` is the pack of the file.
+#[derive(Debug, Clone)]
+pub struct TagMember {
+    pub tag: Tag,
+    pub prefix: String,
+    pub name: String,
+    pub path: PathBuf,
+    /// Field of the name in the file, only used for errors
+    pub field: String,
+}
+
+#[derive(Debug, Default)]
+pub struct TagMembers {
+    pub blocks: Vec<TagMember>,
+    pub items: Vec<TagMember>,
+}
+
+pub fn load_tag_file(path: &Path) -> Result<TagFile, ContentError> {
+    let data = std::fs::read(path).map_err(|err| ContentError::io(path, err))?;
+    serde_json::from_slice(&data).map_err(|err| ContentError::json(path, err))
+}
+
+/// Reads the tag files of every content root, the tag is the file name without the pack prefix.
+pub fn load_tag_members(paths: &[GamePath<PathBuf>], errors: &mut ContentErrors) -> Result<TagMembers, ContentError> {
+    let mut members = TagMembers::default();
+    for tags_path in paths {
+        for file in tags_path.files() {
+            let Some((name, path)) = errors.check(file)? else {continue};
+            let tag = Tag::new(name.strip_prefix(tags_path.prefix()).unwrap_or(&name));
+            let Some(tag_file) = errors.check(load_tag_file(&path))? else {continue};
+            let member = |(i, name): (usize, String), field: &str| TagMember {
+                tag,
+                prefix: tags_path.prefix().to_string(),
+                name,
+                path: path.clone(),
+                field: format!("{}[{}]", field, i),
+            };
+            members.blocks.extend(tag_file.blocks.into_iter().enumerate().map(|n| member(n, "blocks")));
+            members.items.extend(tag_file.items.into_iter().enumerate().map(|n| member(n, "items")));
+        }
+    }
+    Ok(members)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{content_loader::test_content, recipes::{items::{ItemFile, Items, ITEMS}, recipes::{expand_tags, RecipeFile}}};
+
+    use super::*;
+
+    fn item(prefix: &str, id: &str, tags: &[&str]) -> (String, PathBuf, ItemFile) {
+        let file = ItemFile { id: id.to_string(), stack_size: 50, icon: 0, block: None, tags: tags.iter().map(|t| t.to_string()).collect(), fuel: None };
+        (prefix.to_string(), PathBuf::from(format!("{}.json", id)), file)
+    }
+
+    #[test]
+    fn tag_files_tag_the_items_of_their_pack() {
+        let root = tempfile::tempdir().unwrap();
+        let (game, pack) = (root.path().join("game"), root.path().join("pack"));
+        std::fs::create_dir_all(&game).unwrap();
+        std::fs::create_dir_all(&pack).unwrap();
+        std::fs::write(game.join("lens.json"), r#"{"items": ["window", "@other:lens"]}"#).unwrap();
+        std::fs::write(pack.join("lens.json"), r#"{"items": ["window", "missing"]}"#).unwrap();
+        let paths = [GamePath { path: game, prefix: None }, GamePath { path: pack, prefix: Some("@pack:".to_string()) }];
+
+        let mut errors = ContentErrors::collect_all();
+        let members = load_tag_members(&paths, &mut errors).unwrap();
+        let files = vec![
+            item("", "window", &[]),
+            item("", "door", &["#lens"]),
+            item("", "wall", &[]),
+            item("@pack:", "@pack:window", &[]),
+            item("@other:", "@other:lens", &[]),
+        ];
+        let items = Items::new(files, &members.items, &mut errors).unwrap();
+
+        let tagged: Vec<&str> = items.with_tag(Tag::new("lens")).iter().map(|&id| items.all[id as usize].name.as_str()).collect();
+        assert_eq!(tagged, ["@other:lens", "@pack:window", "door", "window"]);
+        let errors = errors.into_errors();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(&errors[0], ContentError::MissingReference { field, .. } if field == "items[1]"));
+    }
+
+    #[test]
+    fn tag_ingredients_expand_to_the_tagged_items() {
+        test_content();
+        let recipe = |ingredient: &str| -> RecipeFile { serde_json::from_str(&format!(
+            r#"{{"id": "smelt", "duration": 1, "crafter": ["furnace"], "ingredients": [{{"item": "{}", "count": 2}}, {{"item": "rock"}}], "result": {{"item": "iron_ingot"}}}}"#,
+            ingredient)).unwrap() };
+        let ores: Vec<&str> = ITEMS().with_tag(Tag::ORE).iter().map(|&id| ITEMS().all[id as usize].name.as_str()).collect();
+        assert_eq!(ores, ["iron_ore"]);
+
+        let variants = expand_tags(String::new(), PathBuf::new(), recipe("#ore")).unwrap();
+        assert_eq!(variants.len(), 1);
+        let variant = &variants[0].2;
+        assert_eq!(variant.id, "smelt/iron_ore");
+        assert_eq!((variant.ingredients[0].item.as_str(), variant.ingredients[0].count), ("iron_ore", 2));
+        assert_eq!(variant.ingredients[1].item, "rock");
+
+        assert_eq!(expand_tags(String::new(), PathBuf::new(), recipe("iron_ore")).unwrap()[0].2.id, "smelt");
+        let err = expand_tags(String::new(), PathBuf::new(), recipe("#no_such_tag")).unwrap_err();
+        assert!(matches!(err, ContentError::MissingReference { field, .. } if field == "ingredients[0].item"));
+    }
+}
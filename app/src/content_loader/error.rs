@@ -0,0 +1,113 @@
+use std::{fmt::Display, path::{Path, PathBuf}};
+
+#[derive(Debug)]
+pub enum ContentError {
+    Io { path: PathBuf, cause: std::io::Error },
+    Json { path: PathBuf, cause: serde_json::Error },
+    Toml { path: PathBuf, cause: toml::de::Error },
+    Image { path: PathBuf, cause: image::ImageError },
+    Model { path: PathBuf, cause: String },
+    /// Content is named by the file stem, so every file needs an extension
+    FileName { path: PathBuf },
+    Duplicate { kind: &'static str, name: String, first: PathBuf, second: PathBuf },
+    /// A file refers to a texture, model, complex object or function that is not loaded
+    MissingReference { path: PathBuf, field: String, kind: &'static str, name: String },
+    /// A field has a value the game can't use
+    Invalid { path: PathBuf, field: String, message: String },
+    /// A script that does not compile
+    Script { path: PathBuf, line: Option<usize>, message: String },
+}
+
+impl ContentError {
+    pub fn io(path: impl AsRef<Path>, cause: std::io::Error) -> Self {
+        Self::Io { path: path.as_ref().to_path_buf(), cause }
+    }
+
+    pub fn json(path: impl AsRef<Path>, cause: serde_json::Error) -> Self {
+        Self::Json { path: path.as_ref().to_path_buf(), cause }
+    }
+
+    pub fn toml(path: impl AsRef<Path>, cause: toml::de::Error) -> Self {
+        Self::Toml { path: path.as_ref().to_path_buf(), cause }
+    }
+
+    pub fn missing(path: impl AsRef<Path>, field: impl Into<String>, kind: &'static str, name: impl Into<String>) -> Self {
+        Self::MissingReference { path: path.as_ref().to_path_buf(), field: field.into(), kind, name: name.into() }
+    }
+
+    pub fn invalid(path: impl AsRef<Path>, field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::Invalid { path: path.as_ref().to_path_buf(), field: field.into(), message: message.into() }
+    }
+}
+
+impl Display for ContentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io { path, cause } => write!(f, "Failed to read {:?}: {}", path, cause),
+            Self::Json { path, cause } => write!(f, "Failed to parse {:?}: {}", path, cause),
+            Self::Toml { path, cause } => write!(f, "Failed to parse {:?}: {}", path, cause),
+            Self::Image { path, cause } => write!(f, "Failed to open image {:?}: {}", path, cause),
+            Self::Model { path, cause } => write!(f, "Failed to load model {:?}: {}", path, cause),
+            Self::FileName { path } => write!(f, "File name has no extension: {:?}", path),
+            Self::Duplicate { kind, name, first, second } =>
+                write!(f, "Duplicate {} \"{}\" on paths: {:?} and {:?}", kind, name, first, second),
+            Self::MissingReference { path, field, kind, name } =>
+                write!(f, "{:?}: {}: unknown {} \"{}\"", path, field, kind, name),
+            Self::Invalid { path, field, message } => write!(f, "{:?}: {}: {}", path, field, message),
+            Self::Script { path, line: Some(line), message } => write!(f, "{:?}:{}: {}", path, line, message),
+            Self::Script { path, line: None, message } => write!(f, "{:?}: {}", path, message),
+        }
+    }
+}
+
+impl std::error::Error for ContentError {}
+
+impl ContentError {
+    /// File the error is about, mod content is named by a path like `mod <pack>`.
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Io { path, .. } | Self::Json { path, .. } | Self::Toml { path, .. } | Self::Image { path, .. }
+            | Self::Model { path, .. } | Self::FileName { path } | Self::MissingReference { path, .. }
+            | Self::Invalid { path, .. } | Self::Script { path, .. } => path,
+            Self::Duplicate { second, .. } => second,
+        }
+    }
+}
+
+/// Decides what the loaders do with an error of one file or one entry.
+/// The game stops at the first one, `validate-content` keeps all of them and skips the broken entries,
+/// so that the rest of the content is still loaded and checked.
+#[derive(Debug, Default)]
+pub struct ContentErrors {
+    collect: bool,
+    errors: Vec<ContentError>,
+}
+
+impl ContentErrors {
+    /// Fails on the first error.
+    pub fn fail_fast() -> Self {Self::default()}
+
+    /// Keeps every error, the loaders skip what is broken.
+    pub fn collect_all() -> Self {Self { collect: true, errors: Vec::new() }}
+
+    /// Returns the value, `None` if the error was kept, or the error when the loading has to stop.
+    pub fn check<T>(&mut self, result: Result<T, ContentError>) -> Result<Option<T>, ContentError> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(err) if self.collect => {
+                self.errors.push(err);
+                Ok(None)
+            },
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Same as [`ContentErrors::check`] for errors found outside of a `Result`.
+    pub fn report(&mut self, err: ContentError) -> Result<(), ContentError> {
+        self.check(Err::<(), _>(err)).map(|_| ())
+    }
+
+    pub fn into_errors(self) -> Vec<ContentError> {
+        self.errors
+    }
+}
\ No newline at end of file
@@ -0,0 +1,117 @@
+use std::{path::PathBuf, sync::mpsc::{channel, Receiver}};
+
+use graphics_engine::state::State;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::level::Level;
+
+use super::indices::{load_blocks_textures, GamePath, Indices};
+
+/// Files changed in the watched content folders.
+#[derive(Debug, Default)]
+pub struct ContentChanges {
+    pub blocks: Vec<PathBuf>,
+    pub textures: bool,
+    pub scripts: bool,
+    /// Only block tags are reloaded, item tags need a restart
+    pub tags: bool,
+}
+
+impl ContentChanges {
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty() && !self.textures && !self.scripts && !self.tags
+    }
+}
+
+/// Watches the block files, block textures, scripts and tag files of the base game and of the active content packs.
+pub struct ContentWatcher {
+    _watcher: RecommendedWatcher,
+    receiver: Receiver<notify::Result<Event>>,
+    blocks: Vec<PathBuf>,
+    textures: Vec<PathBuf>,
+    scripts: Vec<PathBuf>,
+    tags: Vec<PathBuf>,
+}
+
+impl ContentWatcher {
+    pub fn new(content: &[GamePath<PathBuf>]) -> notify::Result<Self> {
+        let (sender, receiver) = channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        // Events come with absolute paths, so the folders are compared in the same form.
+        let mut watch = |folder: &str| -> notify::Result<Vec<PathBuf>> {
+            let mut paths = Vec::new();
+            for root in content {
+                let Ok(path) = root.path.join(folder).canonicalize() else {continue};
+                watcher.watch(&path, RecursiveMode::Recursive)?;
+                paths.push(path);
+            }
+            Ok(paths)
+        };
+        let blocks = watch("blocks/")?;
+        let textures = watch("assets/blocks/")?;
+        let scripts = watch("scripts/")?;
+        let tags = watch("tags/")?;
+
+        Ok(Self { _watcher: watcher, receiver, blocks, textures, scripts, tags })
+    }
+
+    /// Collects the changes since the last call without blocking.
+    pub fn changes(&self) -> ContentChanges {
+        let mut changes = ContentChanges::default();
+        for event in self.receiver.try_iter() {
+            let event = match event {
+                Ok(event) => event,
+                Err(err) => {
+                    eprintln!("Content watcher error: {}", err);
+                    continue;
+                }
+            };
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) {continue};
+            for path in event.paths {
+                if self.textures.iter().any(|folder| path.starts_with(folder)) {
+                    changes.textures = true;
+                } else if self.scripts.iter().any(|folder| path.starts_with(folder)) {
+                    changes.scripts = true;
+                } else if self.tags.iter().any(|folder| path.starts_with(folder)) {
+                    changes.tags = true;
+                } else if self.blocks.iter().any(|folder| path.starts_with(folder)) && !changes.blocks.contains(&path) {
+                    changes.blocks.push(path);
+                }
+            }
+        }
+        changes
+    }
+}
+
+/// Rebuilds the block texture array and the changed blocks of the opened world, then re-meshes the chunks with them.
+/// Changed scripts and tag files rebuild every block, live voxels pick up the new scripts with their blocks.
+/// Complex objects and light are updated when the world is opened again.
+pub fn reload(changes: &ContentChanges, indices: &mut Indices, state: &mut State, level: Option<&Level>) {
+    let mut all_blocks = changes.scripts || changes.tags;
+    if changes.textures {
+        let paths = indices.content.iter().map(|c| c.join("assets/blocks/")).collect::<Vec<_>>();
+        match load_blocks_textures(&paths) {
+            Ok((block, textures, len)) => {
+                state.set_block_textures(textures, len);
+                // Added or removed textures move the layers of the others.
+                all_blocks |= block != indices.block;
+                indices.block = block;
+                println!("Block textures reloaded");
+            },
+            Err(err) => eprintln!("Failed to reload block textures: {}", err),
+        }
+    }
+
+    let Some(level) = level else {return};
+    let reloaded = level.content.reload_blocks(indices, |path| {
+        all_blocks || path.canonicalize().is_ok_and(|path| changes.blocks.contains(&path))
+    });
+    match reloaded {
+        Ok(ids) if !ids.is_empty() => {
+            level.world.chunks.modify_with_blocks(&ids);
+            println!("Blocks reloaded: {:?}", ids);
+        },
+        Ok(_) => {},
+        Err(err) => eprintln!("Failed to reload blocks: {}", err),
+    }
+}
\ No newline at end of file
@@ -5,34 +5,110 @@ use image::{imageops::FilterType, DynamicImage};
 use itertools::Itertools;
 use resources::texture::{load_texture, ModelTexture};
 
+use super::error::{ContentError, ContentErrors};
+
 #[derive(Debug, Clone)]
 pub struct GamePath<T: AsRef<Path>> {
     pub path: T,
     pub prefix: Option<String>,
 }
 
+impl<T: AsRef<Path>> GamePath<T> {
+    pub fn prefix(&self) -> &str {
+        self.prefix.as_deref().unwrap_or("")
+    }
+
+    pub fn join(&self, path: impl AsRef<Path>) -> GamePath<PathBuf> {
+        GamePath { path: self.path.as_ref().join(path), prefix: self.prefix.clone() }
+    }
+
+    /// Namespaces an id declared inside a file of this path, like the names of the files.
+    pub fn qualify(&self, id: &str) -> String {
+        format!("{}{}", self.prefix(), id)
+    }
+
+    /// Walks every file under the path and names it by its prefixed file stem.
+    pub fn files(&self) -> impl Iterator<Item = Result<(String, PathBuf), ContentError>> + '_ {
+        walkdir::WalkDir::new(&self.path)
+            .into_iter()
+            .filter_map(|f| f.ok())
+            .filter(|f| f.file_type().is_file())
+            .map(|file| {
+                let Some(file_name) = file.file_name().to_str() else {
+                    return Err(ContentError::FileName { path: file.into_path() });
+                };
+                let Some(dot_index) = file_name.rfind('.') else {
+                    return Err(ContentError::FileName { path: file.into_path() });
+                };
+                Ok((format!("{}{}", self.prefix(), &file_name[..dot_index]), file.into_path()))
+            })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Indices {
     pub block: HashMap<String, u32>,
     pub models: HashMap<String, u32>,
     pub animated_models: HashMap<String, u32>,
+    /// Root folders of the base game and of every active content pack.
+    pub content: Vec<GamePath<PathBuf>>,
 }
 
-pub fn load_blocks_textures(paths: &[GamePath<PathBuf>]) -> (HashMap::<String, u32>, Vec<Vec<u8>>, u32) {
-    let (names, images): (Vec<String>, Vec<DynamicImage>) = paths.iter().flat_map(|path| {
-            let prefix = path.prefix.as_ref().map(|s| s.as_str()).unwrap_or("");
-            let files = walkdir::WalkDir::new(&path.path)
-                .into_iter()
-                .filter_map(|f| f.ok())
-                .filter(|f| f.file_type().is_file());
-
-            files.map(move |file| {
-                let name = file.file_name().to_str().unwrap();
-                let dot_index = name.rfind('.').unwrap();
-                (format!("{}{}", &prefix, &name[..dot_index]), image::open(file.path())
-                    .unwrap_or_else(|_| panic!("Failed to open image on path: {:?}", file.path())))
-            })
-        }).unzip();
+/// Data loaded together with [`Indices`] that is handed over to the renderer.
+pub struct Assets {
+    pub blocks: Vec<Vec<u8>>,
+    pub blocks_len: u32,
+    pub models: Vec<resources::model::Model>,
+    pub animated_models: Vec<resources::animated_model::AnimatedModel>,
+}
+
+/// Looks up a name referenced by content with the given prefix.
+/// Names starting with `@` are already namespaced, others are searched in the owner pack first
+/// and then in the base game.
+pub fn resolve<'a, V>(map: &'a HashMap<String, V>, prefix: &str, name: &str) -> Option<&'a V> {
+    if prefix.is_empty() || name.starts_with('@') {return map.get(name)};
+    map.get(&format!("{}{}", prefix, name)).or_else(|| map.get(name))
+}
+
+/// Remembers where every name came from and fails if two files claim the same one.
+pub fn check_duplicate(seen: &mut HashMap<String, PathBuf>, kind: &'static str, name: &str, path: &Path) -> Result<(), ContentError> {
+    if let Some(first) = seen.insert(name.to_string(), path.to_path_buf()) {
+        return Err(ContentError::Duplicate { kind, name: name.to_string(), first, second: path.to_path_buf() });
+    }
+    Ok(())
+}
+
+/// Sorts the entries by name and keeps the first entry of every name, the others are reported as duplicates.
+/// `key` gives the name and the file of an entry.
+pub fn sort_unique<T>(mut entries: Vec<T>, kind: &'static str, key: impl Fn(&T) -> (&str, &Path), errors: &mut ContentErrors) -> Result<Vec<T>, ContentError> {
+    entries.sort_by(|a, b| key(a).0.cmp(key(b).0));
+    let mut unique: Vec<T> = Vec::with_capacity(entries.len());
+    for entry in entries {
+        if let Some(first) = unique.last().filter(|first| key(first).0 == key(&entry).0) {
+            let (name, second) = key(&entry);
+            errors.report(ContentError::Duplicate { kind, name: name.to_string(), first: key(first).1.to_path_buf(), second: second.to_path_buf() })?;
+            continue;
+        }
+        unique.push(entry);
+    }
+    Ok(unique)
+}
+
+/// Texture indices, texture data and the texture count
+pub type BlockTextures = (HashMap::<String, u32>, Vec<Vec<u8>>, u32);
+
+pub fn load_blocks_textures(paths: &[GamePath<PathBuf>]) -> Result<BlockTextures, ContentError> {
+    let mut seen = HashMap::<String, PathBuf>::new();
+    let (names, images): (Vec<String>, Vec<DynamicImage>) = paths.iter()
+        .flat_map(|path| path.files())
+        .map(|file| {
+            let (name, path) = file?;
+            check_duplicate(&mut seen, "block texture", &name, &path)?;
+            let image = image::open(&path)
+                .map_err(|cause| ContentError::Image { path, cause })?;
+            Ok((name, image))
+        }).collect::<Result<Vec<_>, ContentError>>()?
+        .into_iter().unzip();
 
     let data = (0..BLOCK_MIPMAP_COUNT).map(|mipmap| {
         let size = BLOCK_TEXTURE_SIZE / 2u32.pow(mipmap as u32);
@@ -45,58 +121,55 @@ pub fn load_blocks_textures(paths: &[GamePath<PathBuf>]) -> (HashMap::<String, u
     let indices: HashMap<String, u32> = names.into_iter().enumerate()
         .map(|(i, n)| (n, i as u32)).collect();
 
-    (indices, data, images.len() as u32)
+    Ok((indices, data, images.len() as u32))
 }
 
 pub fn load_animated_models(
-    model_paths: &[impl AsRef<Path>],
-    texture_paths: &[impl AsRef<Path>],
-) -> (HashMap::<String, u32>, Vec<resources::animated_model::AnimatedModel>) {
-    let load = |p: &Path, m: ModelTexture| {resources::animated_model::load_animated_model(p, m)};
+    model_paths: &[GamePath<PathBuf>],
+    texture_paths: &[GamePath<PathBuf>],
+) -> Result<(HashMap::<String, u32>, Vec<resources::animated_model::AnimatedModel>), ContentError> {
+    let load = |p: &Path, m: ModelTexture| {Ok(resources::animated_model::load_animated_model(p, m))};
     load_with_texture(model_paths, texture_paths, &load)
 }
 
 pub fn load_models(
-    model_paths: &[impl AsRef<Path>],
-    texture_paths: &[impl AsRef<Path>], 
-) -> (HashMap::<String, u32>, Vec<resources::model::Model>) {
-    let load = |p: &Path, m: ModelTexture| {resources::model::load_model(p, m)};
-    let (indices, models) = load_with_texture(model_paths, texture_paths, &load);
-    (indices, models.into_iter().map(|m| m.unwrap()).collect())
+    model_paths: &[GamePath<PathBuf>],
+    texture_paths: &[GamePath<PathBuf>],
+) -> Result<(HashMap::<String, u32>, Vec<resources::model::Model>), ContentError> {
+    let load = |p: &Path, m: ModelTexture| {
+        resources::model::load_model(p, m)
+            .map_err(|err| ContentError::Model { path: p.to_path_buf(), cause: err.to_string() })
+    };
+    load_with_texture(model_paths, texture_paths, &load)
 }
 
 pub fn load_with_texture<T>(
-    paths: &[impl AsRef<Path>],
-    texture_paths: &[impl AsRef<Path>],
-    load: &dyn for<'a> Fn(&'a Path, ModelTexture) -> T
-) -> (HashMap::<String, u32>, Vec<T>) {
-    let textures_files = texture_paths.iter().flat_map(|path| walkdir::WalkDir::new(path)
-        .into_iter()
-        .filter_map(|f| f.ok())
-        .filter(|f| f.file_type().is_file()));
-
-    let mut textures: HashMap<String, ModelTexture> = textures_files.map(|file| {
-        let file_name = file.file_name().to_str().unwrap();
-        let dot_index = file_name.rfind('.').unwrap();
-        let name = file_name[..dot_index].to_string();
-        load_texture(file.path()).ok().map(|t| (name, t))
-    }).flatten().collect();
-
-    let files = paths.iter().flat_map(|path| walkdir::WalkDir::new(path)
-        .into_iter()
-        .filter_map(|f| f.ok())
-        .filter(|f| f.file_type().is_file())
-        .enumerate());
+    paths: &[GamePath<PathBuf>],
+    texture_paths: &[GamePath<PathBuf>],
+    load: &dyn for<'a> Fn(&'a Path, ModelTexture) -> Result<T, ContentError>
+) -> Result<(HashMap::<String, u32>, Vec<T>), ContentError> {
+    let mut textures: HashMap<String, ModelTexture> = texture_paths.iter()
+        .flat_map(|path| path.files())
+        .map(|file| {
+            let (name, path) = file?;
+            let texture = load_texture(&path).map_err(|cause| ContentError::Image { path, cause })?;
+            Ok((name, texture))
+        }).collect::<Result<_, ContentError>>()?;
 
+    let mut seen = HashMap::<String, PathBuf>::new();
     let mut indices = HashMap::<String, u32>::new();
-    let datas: Vec<T> = files.map(|(index, file)| {
-        let file_name = file.file_name().to_str().unwrap();
-        let dot_index = file_name.rfind('.').unwrap();
-        let name = file_name[..dot_index].to_string();
-        let data = load(file.path(), textures.remove(&name).unwrap());
-        indices.insert(name, index as u32);
-        data
-    }).collect();
-
-    (indices, datas)
+    let datas: Vec<T> = paths.iter()
+        .flat_map(|path| path.files())
+        .enumerate()
+        .map(|(index, file)| {
+            let (name, path) = file?;
+            check_duplicate(&mut seen, "model", &name, &path)?;
+            let texture = textures.remove(&name)
+                .ok_or_else(|| ContentError::missing(&path, "texture", "model texture", &name))?;
+            let data = load(&path, texture)?;
+            indices.insert(name, index as u32);
+            Ok(data)
+        }).collect::<Result<_, ContentError>>()?;
+
+    Ok((indices, datas))
 }
\ No newline at end of file
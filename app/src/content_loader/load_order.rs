@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+
+use super::ContentDetails;
+
+fn deactivate(details: &mut HashMap<String, ContentDetails>, name: &str, reason: String) {
+    let Some(detail) = details.get_mut(name) else {return};
+    detail.active = false;
+    eprintln!("The content pack \"{}:{}\" will not be activated!", name, detail.info.version());
+    eprintln!("Reason: {}", reason);
+}
+
+/// Returns the reason why the active pack cannot be loaded, whatever other packs are deactivated.
+fn check_dependencies(details: &HashMap<String, ContentDetails>, detail: &ContentDetails) -> Option<String> {
+    for (name, req) in detail.info.dependencies() {
+        let Some(dependency) = details.get(name) else {
+            return Some(format!("missing dependency \"{}\" ({})", name, req));
+        };
+        if !req.matches(dependency.info.version()) {
+            return Some(format!("dependency \"{}\" requires version {}, found {}",
+                name, req, dependency.info.version()));
+        }
+        if !dependency.active {
+            return Some(format!("dependency \"{}\" is not active", name));
+        }
+    }
+    None
+}
+
+/// Returns the reason why the active pack cannot be loaded together with another active pack.
+fn check_conflicts(details: &HashMap<String, ContentDetails>, detail: &ContentDetails) -> Option<String> {
+    for (name, req) in detail.info.optional_dependencies() {
+        let Some(dependency) = details.get(name).filter(|d| d.active) else {continue};
+        if !req.matches(dependency.info.version()) {
+            return Some(format!("optional dependency \"{}\" requires version {}, found {}",
+                name, req, dependency.info.version()));
+        }
+    }
+    for (name, req) in detail.info.conflicts() {
+        let Some(conflict) = details.get(name).filter(|d| d.active) else {continue};
+        if req.matches(conflict.info.version()) {
+            return Some(format!("conflicts with \"{}:{}\"", name, conflict.info.version()));
+        }
+    }
+    None
+}
+
+/// Active packs that fail the check and the reason, sorted by name.
+fn broken(details: &HashMap<String, ContentDetails>, check: fn(&HashMap<String, ContentDetails>, &ContentDetails) -> Option<String>) -> Vec<(String, String)> {
+    let mut broken: Vec<(String, String)> = details.values()
+        .filter(|d| d.active)
+        .filter_map(|d| check(details, d).map(|reason| (d.info.name().to_string(), reason)))
+        .collect();
+    broken.sort();
+    broken
+}
+
+fn active_names<'a>(details: &HashMap<String, ContentDetails>, names: impl Iterator<Item = &'a String>) -> Vec<&'a str> {
+    names.filter(|name| details.get(*name).is_some_and(|d| d.active)).map(|name| name.as_str()).collect()
+}
+
+/// The first pack by name whose dependencies are ordered, `None` if every pack waits.
+fn first_ready<'a>(waiting: &HashMap<&'a str, (Vec<&'a str>, Vec<&'a str>)>, with_optional: bool) -> Option<&'a str> {
+    waiting.iter()
+        .filter(|(_, (required, optional))| required.is_empty() && (!with_optional || optional.is_empty()))
+        .map(|(name, _)| *name)
+        .min()
+}
+
+/// Deactivates packs whose requirements are not satisfied and returns the names of the remaining
+/// active packs so that every pack comes after its dependencies. Ties are ordered by name.
+pub fn load_order(details: &mut HashMap<String, ContentDetails>) -> Vec<String> {
+    // Deactivating one pack can break the packs that depend on it, so repeat until nothing changes.
+    loop {
+        let missing = broken(details, check_dependencies);
+        if !missing.is_empty() {
+            missing.into_iter().for_each(|(name, reason)| deactivate(details, &name, reason));
+            continue;
+        }
+        // Deactivating one of two conflicting packs is enough, the one that sorts last goes.
+        let Some((name, reason)) = broken(details, check_conflicts).pop() else {break};
+        deactivate(details, &name, reason);
+    }
+
+    let mut waiting: HashMap<&str, (Vec<&str>, Vec<&str>)> = details.values()
+        .filter(|d| d.active)
+        .map(|d| {
+            let required = active_names(details, d.info.dependencies().keys());
+            let optional = active_names(details, d.info.optional_dependencies().keys());
+            (d.info.name(), (required, optional))
+        }).collect();
+
+    let mut order = Vec::<String>::new();
+    // Optional dependencies only order the packs, they are dropped when they are all that is left waiting.
+    while let Some(next) = first_ready(&waiting, true).or_else(|| first_ready(&waiting, false)) {
+        waiting.remove(next);
+        waiting.values_mut().for_each(|(required, optional)| {
+            required.retain(|d| *d != next);
+            optional.retain(|d| *d != next);
+        });
+        order.push(next.to_string());
+    }
+
+    // Whatever is left is either in a dependency cycle or requires a pack in one.
+    let mut cycle: Vec<String> = waiting.into_keys().map(String::from).collect();
+    cycle.sort();
+    for name in &cycle {
+        deactivate(details, name, format!("circular dependency between {:?}", cycle));
+    }
+
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::content_loader::ContentInfo;
+
+    use super::*;
+
+    fn pack(info: &str) -> (String, ContentDetails) {
+        let info: ContentInfo = toml::from_str(info).unwrap();
+        (info.name().to_string(), ContentDetails::new(true, PathBuf::new(), info))
+    }
+
+    fn packs(infos: &[&str]) -> HashMap<String, ContentDetails> {
+        infos.iter().map(|info| pack(info)).collect()
+    }
+
+    fn is_active(details: &HashMap<String, ContentDetails>, name: &str) -> bool {
+        details[name].active()
+    }
+
+    #[test]
+    fn dependencies_come_first() {
+        let mut details = packs(&[
+            "name = \"c\"\nversion = \"1.0.0\"\ndependencies = { b = \"1\" }",
+            "name = \"b\"\nversion = \"1.2.0\"\ndependencies = { a = \">=0.5\" }",
+            "name = \"a\"\nversion = \"0.5.0\"",
+            "name = \"d\"\nversion = \"1.0.0\"",
+        ]);
+        assert_eq!(load_order(&mut details), ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn optional_dependencies_order_only_when_active() {
+        let mut details = packs(&[
+            "name = \"a\"\nversion = \"1.0.0\"\noptional_dependencies = { b = \"1\", missing = \"1\" }",
+            "name = \"b\"\nversion = \"1.0.0\"",
+        ]);
+        assert_eq!(load_order(&mut details), ["b", "a"]);
+    }
+
+    #[test]
+    fn broken_dependencies_deactivate_transitively() {
+        let mut details = packs(&[
+            "name = \"a\"\nversion = \"1.0.0\"\ndependencies = { missing = \"1\" }",
+            "name = \"b\"\nversion = \"1.0.0\"\ndependencies = { a = \"1\" }",
+            "name = \"c\"\nversion = \"1.0.0\"\ndependencies = { d = \"2\" }",
+            "name = \"d\"\nversion = \"1.0.0\"",
+        ]);
+        assert_eq!(load_order(&mut details), ["d"]);
+        assert!(!is_active(&details, "a") && !is_active(&details, "b") && !is_active(&details, "c"));
+    }
+
+    #[test]
+    fn conflicts_and_cycles_are_deactivated() {
+        let mut details = packs(&[
+            "name = \"a\"\nversion = \"1.0.0\"\nconflicts = { b = \"1\" }",
+            "name = \"b\"\nversion = \"1.0.0\"",
+            "name = \"c\"\nversion = \"1.0.0\"\ndependencies = { d = \"1\" }",
+            "name = \"d\"\nversion = \"1.0.0\"\ndependencies = { c = \"1\" }",
+        ]);
+        assert_eq!(load_order(&mut details), ["b"]);
+        assert!(!is_active(&details, "a") && !is_active(&details, "c") && !is_active(&details, "d"));
+    }
+
+    #[test]
+    fn one_of_two_conflicting_packs_is_kept() {
+        let mut details = packs(&[
+            "name = \"a\"\nversion = \"1.0.0\"\nconflicts = { b = \"1\" }",
+            "name = \"b\"\nversion = \"1.0.0\"\nconflicts = { a = \"1\" }",
+            "name = \"c\"\nversion = \"1.0.0\"\ndependencies = { missing = \"1\" }\nconflicts = { a = \"1\" }",
+        ]);
+        assert_eq!(load_order(&mut details), ["a"]);
+        assert!(!is_active(&details, "b") && !is_active(&details, "c"));
+    }
+
+    #[test]
+    fn optional_dependencies_on_a_cycle_are_not_circular() {
+        let mut details = packs(&[
+            "name = \"a\"\nversion = \"1.0.0\"\noptional_dependencies = { c = \"1\" }",
+            "name = \"b\"\nversion = \"1.0.0\"\ndependencies = { c = \"1\" }",
+            "name = \"c\"\nversion = \"1.0.0\"\ndependencies = { d = \"1\" }",
+            "name = \"d\"\nversion = \"1.0.0\"\ndependencies = { c = \"1\" }",
+            "name = \"e\"\nversion = \"1.0.0\"\noptional_dependencies = { f = \"1\" }",
+            "name = \"f\"\nversion = \"1.0.0\"\noptional_dependencies = { e = \"1\" }",
+        ]);
+        assert_eq!(load_order(&mut details), ["a", "e", "f"]);
+        assert!(!is_active(&details, "b") && !is_active(&details, "c") && !is_active(&details, "d"));
+    }
+}
@@ -1,17 +1,22 @@
 use std::{collections::{HashMap, HashSet}, fs::DirEntry, path::{Path, PathBuf}};
 
+use semver::{Version, VersionReq};
 use serde::{Deserialize, Serialize};
 
-use crate::content_loader::indices::{load_blocks_textures, GamePath};
+use crate::content_loader::{error::ContentError, load_order::load_order, indices::{load_animated_models, load_blocks_textures, load_models, Assets, GamePath}};
 
 use self::indices::Indices;
 
+pub mod error;
+pub mod hot_reload;
 pub mod indices;
+pub mod load_order;
+pub mod validate;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ContentInfo {
     name: String,
-    version: String,
+    version: Version,
 
     #[serde(default)]
     authors: Option<Vec<String>>,
@@ -19,11 +24,24 @@ pub struct ContentInfo {
     description: Option<String>,
     #[serde(default)]
     category: Option<String>,
+
+    /// Packs that must be active, loaded before this one
+    #[serde(default)]
+    dependencies: HashMap<String, VersionReq>,
+    /// Packs that are loaded before this one if they are active
+    #[serde(default)]
+    optional_dependencies: HashMap<String, VersionReq>,
+    /// Packs that cannot be active together with this one
+    #[serde(default)]
+    conflicts: HashMap<String, VersionReq>,
 }
 
 impl ContentInfo {
     pub fn name(&self) -> &str { &self.name }
-    pub fn version(&self) -> &str { &self.name }
+    pub fn version(&self) -> &Version { &self.version }
+    pub fn dependencies(&self) -> &HashMap<String, VersionReq> { &self.dependencies }
+    pub fn optional_dependencies(&self) -> &HashMap<String, VersionReq> { &self.optional_dependencies }
+    pub fn conflicts(&self) -> &HashMap<String, VersionReq> { &self.conflicts }
 }
 
 #[derive(Debug, Clone)]
@@ -44,6 +62,7 @@ impl ContentDetails {
 
     pub fn active(&self) -> bool {self.active}
     pub fn path(&self) -> &Path {&self.path}
+    pub fn info(&self) -> &ContentInfo {&self.info}
 }
 
 pub fn load_info(entry: &DirEntry) -> Option<ContentInfo> {
@@ -51,11 +70,14 @@ pub fn load_info(entry: &DirEntry) -> Option<ContentInfo> {
     path.push("info.toml");
     let file = std::fs::read(path).ok()?;
     let s = &String::from_utf8(file).ok()?;
-    toml::from_str(s).ok()
+    toml::from_str(s).map_err(|err| {
+        eprintln!("Failed to parse content pack info on path: {:?}, {}", entry.path(), err);
+    }).ok()
 }
 
 pub struct ContentLoader {
     details: HashMap::<String, ContentDetails>,
+    order: Vec<String>,
 }
 
 impl ContentLoader {
@@ -84,31 +106,66 @@ impl ContentLoader {
             }
         }
 
-
-        Self { details }
+        let order = load_order(&mut details);
+        Self { details, order }
     }
 
-    pub fn load_indices(&self, game: impl AsRef<Path>) -> Indices {
-        println!("{:?}", game.as_ref());
-        let block_paths: Vec<GamePath<PathBuf>> = self.details().values().filter(|d| d.active)
-            .map(|d| {
-                GamePath {
-                    path: d.path.join("assets/blocks/"),
-                    prefix: Some(format!("@{}:", d.info.name)),
-                }
-            })
-            .chain(std::iter::once(GamePath {
-                path: game.as_ref().join("assets/blocks/"),
-                prefix: None,
+    /// Content roots of the base game followed by every active pack, namespaced as `@pack:`.
+    pub fn content_paths(&self, game: impl AsRef<Path>) -> Vec<GamePath<PathBuf>> {
+        std::iter::once(GamePath { path: game.as_ref().to_path_buf(), prefix: None })
+            .chain(self.load_order().map(|d| GamePath {
+                path: d.path.clone(),
+                prefix: Some(format!("@{}:", d.info.name)),
             }))
-            .collect();
+            .collect()
+    }
 
-        let (indices, blocks, count) = load_blocks_textures(&block_paths);
-        println!("{:?} {:?}", indices, block_paths);
-        todo!();
+    pub fn load_indices(&self, game: impl AsRef<Path>) -> Result<(Indices, Assets), ContentError> {
+        let content = self.content_paths(game);
+        let join = |folder: &str| content.iter().map(|c| c.join(folder)).collect::<Vec<_>>();
+
+        let (block, blocks, blocks_len) = load_blocks_textures(&join("assets/blocks/"))?;
+        let model_textures = join("assets/models/");
+        let (models_indices, models) = load_models(&join("models/"), &model_textures)?;
+        let (animated_models_indices, animated_models) =
+            load_animated_models(&join("animated_models/"), &model_textures)?;
+
+        let indices = Indices {
+            block,
+            models: models_indices,
+            animated_models: animated_models_indices,
+            content,
+        };
+        Ok((indices, Assets { blocks, blocks_len, models, animated_models }))
     }
 
     pub fn details(&self) -> &HashMap<String, ContentDetails> {
         &self.details
     }
+
+    /// Active content packs, every pack comes after its dependencies.
+    pub fn load_order(&self) -> impl Iterator<Item = &ContentDetails> {
+        self.order.iter().map(|name| &self.details[name])
+    }
+}
+
+/// Content of the base game
+pub const GAME_PATH: &str = "./res/game/";
+/// Folder with the content packs
+pub const CONTENT_PACKS_PATH: &str = "./res/content/";
+
+/// The base game and the active packs at the default locations.
+/// Registries load it when they are used before [`crate::Registrator::install`].
+pub fn default_content() -> Vec<GamePath<PathBuf>> {
+    ContentLoader::new(CONTENT_PACKS_PATH).content_paths(GAME_PATH)
+}
+
+/// The base game of the workspace, installed once for all tests of the crate.
+/// The path doesn't depend on the working directory, which the tests share.
+#[cfg(test)]
+pub(crate) fn test_content() -> Vec<GamePath<PathBuf>> {
+    static INSTALLED: std::sync::Once = std::sync::Once::new();
+    let content = vec![GamePath { path: Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join(GAME_PATH), prefix: None }];
+    INSTALLED.call_once(|| crate::Registrator::new().install(&content).unwrap());
+    content
 }
\ No newline at end of file
@@ -0,0 +1,127 @@
+use std::{collections::HashMap, path::{Path, PathBuf}};
+
+use graphics_engine::constants::BLOCK_TEXTURE_SIZE;
+
+use crate::{content::{block_files, block_tags, load_block_file, load_complex_objects}, recipes::{item_filter::ItemFilter, items::{load_item_files, ITEMS}}, scripting::load_scripts, voxels::{block::block_test::{to_block, BlockFile}, live_voxels::{register, LiveVoxelRegistrator}}, Indices, Registrator};
+
+use super::{error::{ContentError, ContentErrors}, indices::{check_duplicate, resolve, GamePath}, ContentLoader};
+
+/// Checks the base game and every content pack, active or not, without loading models or starting the game.
+/// The content is loaded by the loaders of the game, which go on after an error, so everything that is broken
+/// is reported, one error for every broken entry. The mods' content is installed on the way,
+/// the game can't be started after it.
+pub fn validate_content(content_loader: &ContentLoader, game: impl AsRef<Path>, registrator: Registrator) -> Vec<ContentError> {
+    let mut packs = content_loader.details().values().collect::<Vec<_>>();
+    packs.sort_by(|a, b| a.info.name().cmp(b.info.name()));
+    let content: Vec<GamePath<PathBuf>> = std::iter::once(GamePath { path: game.as_ref().to_path_buf(), prefix: None })
+        .chain(packs.into_iter().map(|d| GamePath { path: d.path.clone(), prefix: Some(format!("@{}:", d.info.name())) }))
+        .collect();
+
+    let mut errors = ContentErrors::collect_all();
+    // Collected errors are not returned, this one stopped the loading.
+    let stopped = load_content(&content, registrator, &mut errors).err();
+    let mut problems = errors.into_errors();
+    problems.extend(stopped);
+    problems.sort_by(|a, b| a.path().cmp(b.path()));
+    problems
+}
+
+fn load_content(content: &[GamePath<PathBuf>], registrator: Registrator, errors: &mut ContentErrors) -> Result<(), ContentError> {
+    let join = |folder: &str| content.iter().map(|c| c.join(folder)).collect::<Vec<_>>();
+
+    let textures = names(&join("assets/blocks/"), "block texture", errors)?;
+    for path in textures.values() {
+        match image::image_dimensions(path) {
+            Ok((width, height)) if width != BLOCK_TEXTURE_SIZE || height != BLOCK_TEXTURE_SIZE =>
+                errors.report(ContentError::invalid(path, "", format!("block texture is {}x{}, expected {}x{}",
+                    width, height, BLOCK_TEXTURE_SIZE, BLOCK_TEXTURE_SIZE)))?,
+            Err(cause) => errors.report(ContentError::Image { path: path.clone(), cause })?,
+            _ => {},
+        }
+    }
+    let model_textures = names(&join("assets/models/"), "model texture", errors)?;
+    let models = names(&join("models/"), "model", errors)?;
+    let animated_models = names(&join("animated_models/"), "animated model", errors)?;
+    for (name, path) in models.iter().chain(animated_models.iter()) {
+        if !model_textures.contains_key(name) {
+            errors.report(ContentError::missing(path, "texture", "model texture", name))?;
+        }
+    }
+    let indices = Indices {
+        block: numbered(textures),
+        models: numbered(models),
+        animated_models: numbered(animated_models),
+        content: content.to_vec(),
+    };
+
+    let mut item_paths: HashMap<String, PathBuf> = registrator.item_files().into_iter()
+        .map(|(_, path, file)| (file.id, path))
+        .collect();
+    registrator.install_with(content, errors)?;
+    // Read again only for the paths, their errors are already reported.
+    item_paths.extend(load_item_files(content, &mut ContentErrors::collect_all())?.into_iter()
+        .map(|(_, path, file)| (file.id, path)));
+
+    let (co_indices, _) = load_complex_objects(&join("complex_objects/"), &indices, errors)?;
+    let scripts = load_scripts(&join("scripts/"), errors)?;
+    let block_paths = join("blocks/");
+    let files = block_files(&block_paths, errors)?;
+    block_tags(content, &files, errors)?;
+    let live_voxels = register();
+    let mut blocks = HashMap::<String, u32>::new();
+    for (id, (name, path, prefix)) in files.iter().enumerate() {
+        let Some(file) = errors.check(load_block_file(path))? else {continue};
+        check_block_file(&file, path, &live_voxels, errors)?;
+        let Some(block) = errors.check(to_block(file, &indices, &co_indices, &scripts, prefix, id as u32, path))? else {continue};
+        if let Some(ItemFilter::Tag(tag)) = block.base.item_filter {
+            if ITEMS().with_tag(tag).is_empty() {
+                errors.report(ContentError::invalid(path, "item_filter", format!("no item has the tag \"{}\"", tag.name())))?;
+            }
+        }
+        blocks.insert(name.clone(), id as u32);
+    }
+
+    // Items look up their block only when they are placed.
+    for item in &ITEMS().all {
+        let Some(block) = &item.block else {continue};
+        if resolve(&blocks, &item.prefix, block).is_none() {
+            let path = item_paths.get(&item.name).cloned().unwrap_or_default();
+            errors.report(ContentError::missing(path, "block", "block", block))?;
+        }
+    }
+    Ok(())
+}
+
+/// Fields the block loader accepts, but that don't work in the game or are deprecated.
+fn check_block_file(file: &BlockFile, path: &Path, live_voxels: &LiveVoxelRegistrator, errors: &mut ContentErrors) -> Result<(), ContentError> {
+    if let Some(name) = &file.live_voxel {
+        if !live_voxels.new.contains_key(name) || !live_voxels.deserialize.contains_key(name) {
+            errors.report(ContentError::missing(path, "live_voxel", "live voxel", name))?;
+        }
+        if name == "script" && file.script.is_none() {
+            errors.report(ContentError::invalid(path, "live_voxel", "scripted live voxel without a script"))?;
+        }
+    }
+    if file.is_glass {errors.report(ContentError::invalid(path, "is_glass", "deprecated, use the \"glass\" tag"))?};
+    if file.is_ore {errors.report(ContentError::invalid(path, "is_ore", "deprecated, use the \"ore\" tag"))?};
+    if file.on_break.iter().any(|name| name == "player_add_item") && file.drops.is_empty() {
+        errors.report(ContentError::invalid(path, "drops", "player_add_item without drops"))?;
+    }
+    Ok(())
+}
+
+/// Names the files like the loaders do, without opening them.
+fn names(paths: &[GamePath<PathBuf>], kind: &'static str, errors: &mut ContentErrors) -> Result<HashMap<String, PathBuf>, ContentError> {
+    let mut seen = HashMap::<String, PathBuf>::new();
+    for path in paths {
+        for file in path.files() {
+            let Some((name, file)) = errors.check(file)? else {continue};
+            errors.check(check_duplicate(&mut seen, kind, &name, &file))?;
+        }
+    }
+    Ok(seen)
+}
+
+fn numbered(names: HashMap<String, PathBuf>) -> HashMap<String, u32> {
+    names.into_keys().enumerate().map(|(i, name)| (name, i as u32)).collect()
+}
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use crate::bytes::AsFromBytes;
 
 #[repr(C)]
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
 pub struct GlobalCoord {
     pub x: i32,
     pub y: i32,
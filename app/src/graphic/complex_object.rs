@@ -3,7 +3,7 @@ use std::path::Path;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
-use crate::{Indices};
+use crate::{content_loader::{error::ContentError, indices::resolve}, Indices};
 #[derive(Deserialize, Serialize, Debug, Clone)]
 /// 0: position, 1: uv
 pub struct ComplexObjectVertex(pub [f32; 3], pub [f32; 2]);
@@ -108,11 +108,11 @@ pub struct ComplexObjectSideFile {
 }
 
 impl ComplexObjectSideFile {
-    pub fn to_complex_object_side(self, indices: &Indices) -> ComplexObjectSide {
-        ComplexObjectSide {
-            texture_layer: *indices.block.get(&self.texture_layer).unwrap(),
+    pub fn to_complex_object_side(self, indices: &Indices, prefix: &str) -> Result<ComplexObjectSide, String> {
+        Ok(ComplexObjectSide {
+            texture_layer: *resolve(&indices.block, prefix, &self.texture_layer).ok_or(self.texture_layer)?,
             vertex_group: self.vertex_group,
-        }
+        })
     }
 }
 
@@ -125,28 +125,35 @@ pub struct ComplexObjectFile {
 }
 
 impl ComplexObjectFile {
-    pub fn to_sides(array: [Vec<ComplexObjectSideFile>; 6], indices: &Indices) -> [Vec<ComplexObjectSide>; 6] {
-        array.map(|sides| {
-            sides.into_iter()
-                .map(|side| side.to_complex_object_side(indices))
-                .collect_vec()
-        })
+    pub fn to_sides(array: [Vec<ComplexObjectSideFile>; 6], indices: &Indices, prefix: &str, path: &Path, field: &str) -> Result<[Vec<ComplexObjectSide>; 6], ContentError> {
+        let mut result: [Vec<ComplexObjectSide>; 6] = Default::default();
+        for (i, sides) in array.into_iter().enumerate() {
+            result[i] = sides.into_iter()
+                .map(|side| side.to_complex_object_side(indices, prefix)
+                    .map_err(|name| ContentError::missing(path, format!("{}[{}].texture_layer", field, i), "block texture", name)))
+                .collect::<Result<_, ContentError>>()?;
+        }
+        Ok(result)
     }
 
-    pub fn to_complex_object(self, indices: &Indices) -> ComplexObject {
-        ComplexObject {
-            block: Self::to_sides(self.block, indices),
-            transport_belt: Self::to_sides(self.transport_belt, indices),
-            models: self.models.into_iter()
-                .map(|s| *indices.models.get(&s).unwrap()).collect_vec(),
-            animated_models: self.animated_models.into_iter()
-                .map(|s| *indices.animated_models.get(&s).unwrap()).collect_vec()
-        }
+    pub fn to_complex_object(self, indices: &Indices, prefix: &str, path: &Path) -> Result<ComplexObject, ContentError> {
+        let find = |map, kind, field: &str, names: Vec<String>| names.into_iter().enumerate()
+            .map(|(i, s)| resolve(map, prefix, &s).copied()
+                .ok_or_else(|| ContentError::missing(path, format!("{}[{}]", field, i), kind, s)))
+            .collect::<Result<Vec<u32>, ContentError>>();
+        Ok(ComplexObject {
+            block: Self::to_sides(self.block, indices, prefix, path, "block")?,
+            transport_belt: Self::to_sides(self.transport_belt, indices, prefix, path, "transport_belt")?,
+            models: find(&indices.models, "model", "models", self.models)?,
+            animated_models: find(&indices.animated_models, "animated model", "animated_models", self.animated_models)?,
+        })
     }
 }
 
-pub fn load_complex_object(path: impl AsRef<Path>, indices: &Indices) -> ComplexObject {
-    let data = std::fs::read(path).unwrap();
-    let complex_object_file: ComplexObjectFile = serde_json::from_slice(&data).unwrap();
-    complex_object_file.to_complex_object(indices)
+pub fn load_complex_object(path: impl AsRef<Path>, indices: &Indices, prefix: &str) -> Result<ComplexObject, ContentError> {
+    let path = path.as_ref();
+    let data = std::fs::read(path).map_err(|err| ContentError::io(path, err))?;
+    let complex_object_file: ComplexObjectFile = serde_json::from_slice(&data)
+        .map_err(|err| ContentError::json(path, err))?;
+    complex_object_file.to_complex_object(indices, prefix, path)
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use crate::{content::Content, voxels::{block::{block_test::BlockBase}, chunks::Chunks, chunk::Chunk}};
+use crate::{content::{tags::Tag, Content}, voxels::{block::{block_test::BlockBase}, chunks::Chunks, chunk::Chunk}};
 use crate::light::light_map::Light;
 use super::block_managers::BlockManagers;
 
@@ -120,8 +120,9 @@ pub fn render_block(
 #[inline]
 fn is_blocked(x: i32, y: i32, z: i32, chunks: &Chunks, block: &BlockBase, content: &Content) -> bool {
     let Some(voxel) = chunks.voxel_global((x, y, z).into()) else {return false};
-    let nblock = &content.blocks[voxel.id as usize].base;
-    if block.is_glass && nblock.is_glass {
+    let nblock = content.blocks[voxel.id as usize].load();
+    let nblock = &nblock.base;
+    if block.tags.contains(Tag::GLASS) && nblock.tags.contains(Tag::GLASS) {
         return block.id == nblock.id;
     }
     !nblock.is_light_passing
@@ -85,7 +85,7 @@ pub fn render(chunk_index: usize, chunks: &Chunks, content: &Content) -> Option<
     for (ly, lz, lx) in iproduct!(0..CHUNK_SIZE, 0..CHUNK_SIZE, 0..CHUNK_SIZE) {
         let id = unsafe {chunk.get_unchecked_voxel((lx, ly, lz).into()).id};
         if id == 0 { continue };
-        let block = &content.blocks[id as usize];
+        let block = content.blocks[id as usize].load();
         match block.block_type() {
             BlockType::Block {faces} => {
                 if block.is_glass() {
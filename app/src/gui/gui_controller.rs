@@ -4,8 +4,8 @@ use egui::{epaint::Shadow, vec2, Align, Align2, Color32, Context, Margin, RichTe
 use winit::{window::Window, dpi::PhysicalPosition, event_loop::{EventLoopWindowTarget}};
 use graphics_engine::{texture::TextureAtlas};
 
-use crate::{level::Level, player::player::Player, recipes::{recipes::RECIPES, storage::Storage}, save_load::SettingSave, setting::Setting, world::loader::WorldLoader, Indices};
-use super::{my_widgets::{inventory_slot::inventory_slot, category_change_button::category_change_button, container::container, recipe::recipe, hotbar_slot::hotbar_slot, active_recipe::active_recipe}, theme::DEFAULT_THEME, main_screen::{MainScreen, in_game_menu::draw_in_game_menu}, setting::draw_setting};
+use crate::{level::Level, player::player::Player, recipes::{recipe::Recipe, recipes::RECIPES, research::is_recipe_unlocked, storage::Storage}, save_load::SettingSave, setting::Setting, world::loader::WorldLoader, Indices};
+use super::{my_widgets::{inventory_slot::inventory_slot, category_change_button::category_change_button, container::container, recipe::recipe, hotbar_slot::hotbar_slot, active_recipe::active_recipe}, theme::DEFAULT_THEME, main_screen::{MainScreen, in_game_menu::draw_in_game_menu}, ratio::{draw_ratio, RatioPanel}, setting::draw_setting, simulation::simulation_controls};
 
 enum Task {
     Hotbar(usize),
@@ -21,6 +21,8 @@ pub struct GuiController {
     is_cursor: bool,
     main_screen: MainScreen,
     is_setting: bool,
+    is_ratio: bool,
+    ratio: RatioPanel,
 }
 
 
@@ -32,6 +34,8 @@ impl GuiController {
             is_ui: true,
             is_menu: false,
             is_setting: false,
+            is_ratio: false,
+            ratio: RatioPanel::new(),
             is_cursor: true,
             main_screen: MainScreen::new(),
         }
@@ -45,6 +49,12 @@ impl GuiController {
     pub fn toggle_menu(&mut self) {
         self.is_menu = !self.is_menu;
     }
+    pub fn is_ratio(&self) -> bool {
+        self.is_ratio
+    }
+    pub fn toggle_ratio(&mut self) {
+        self.is_ratio = !self.is_ratio;
+    }
 
     pub fn update_cursor_lock(&mut self) {
         if !self.is_cursor && self.window.has_focus() {
@@ -83,6 +93,12 @@ impl GuiController {
         self
     }
 
+    pub fn draw_ratio(&mut self, ctx: &Context) -> &mut Self {
+        if !self.is_ui || self.is_menu || !self.is_ratio {return self}
+        draw_ratio(ctx, &mut self.ratio);
+        self
+    }
+
     pub fn draw_in_game_menu(&mut self, ctx: &Context, exit_level: &mut bool) -> &mut Self {
         draw_in_game_menu(ctx, exit_level, &mut self.is_setting, &mut self.is_menu);
         self
@@ -155,10 +171,11 @@ impl GuiController {
                                         ..Default::default()
                                     };
                                     ui.set_style(style);
+                                    let recipes: Vec<&Recipe> = RECIPES().all.iter().filter(|r| is_recipe_unlocked(r.id)).collect();
                                     ui.vertical(|ui| {
-                                        for i in 0..=(RECIPES().all.len()/5) {
+                                        for i in 0..=(recipes.len()/5) {
                                             ui.horizontal(|ui| {
-                                                for i in RECIPES().all.iter().skip(5*i).take(5) {
+                                                for i in recipes.iter().skip(5*i).take(5) {
                                                     if ui.add(recipe(&self.items_atlas, i)).drag_started() {
                                                         player.inventory().lock().unwrap().start_recipe(i);
                                                     };
@@ -221,6 +238,8 @@ impl GuiController {
                         *debug_block_id = Some(0);
                     }
                 }
+                ui.separator();
+                simulation_controls(ui);
             });
         self
     }
@@ -236,7 +255,7 @@ impl GuiController {
             .show(ctx, |ui| {
                 ui.set_visible(self.is_ui);
                 ui.horizontal(|ui| {
-                    active.iter().enumerate().for_each(|(i, recipe)| {
+                    active.enumerate().for_each(|(i, recipe)| {
                         ui.add_space(5.0);
                         if ui.add(active_recipe(&self.items_atlas, recipe)).drag_started() {
                             cancel_index = Some(i);
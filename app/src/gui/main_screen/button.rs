@@ -1,13 +1,13 @@
 use egui::{Stroke, Color32};
 
-use crate::gui::theme::DEFAULT_THEME;
+use crate::{gui::theme::DEFAULT_THEME, lang::tr};
 
 const WIDTH: f32 = 250.0;
 const HEIGHT: f32 = 40.0;
 const FONT_SIZE: f32 = 20.0;
 
 pub fn continue_button<'a>() -> egui::Button<'a> {
-    let text = egui::RichText::new("Continue")
+    let text = egui::RichText::new(tr("ui.menu.continue"))
         .color(DEFAULT_THEME.on_green)
         .size(FONT_SIZE);
     egui::Button::new(text)
@@ -25,7 +25,7 @@ pub fn button(text: &str) -> egui::Button {
 }
 
 pub fn exit<'a>() -> egui::Button<'a> {
-    let text = egui::RichText::new("Exit")
+    let text = egui::RichText::new(tr("ui.menu.exit"))
         .color(DEFAULT_THEME.on_red)
         .size(FONT_SIZE);
     egui::Button::new(text)
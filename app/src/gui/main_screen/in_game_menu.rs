@@ -1,5 +1,5 @@
 use egui::{Context, Align2, vec2};
-use crate::gui::main_screen::button;
+use crate::{gui::main_screen::button, lang::tr};
 
 
 pub fn draw_in_game_menu(ctx: &Context, exit_level: &mut bool, is_setting: &mut bool, is_menu: &mut bool) {
@@ -12,7 +12,7 @@ pub fn draw_in_game_menu(ctx: &Context, exit_level: &mut bool, is_setting: &mut
             if ui.add(button::continue_button()).clicked() {
                 *is_menu = false;
             };
-            if ui.add(button::button("Setting")).clicked() {
+            if ui.add(button::button(&tr("ui.menu.setting"))).clicked() {
                 *is_setting = !*is_setting;
             };
             if ui.add(button::exit()).clicked() {
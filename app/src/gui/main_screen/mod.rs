@@ -1,8 +1,8 @@
 
 
-use egui::{vec2, Align2};
+use egui::{vec2, Align2, Color32, RichText};
 use winit::event_loop::{EventLoopWindowTarget};
-use crate::{world::loader::{WorldLoader}, setting::Setting, level::Level};
+use crate::{world::loader::{WorldLoader}, setting::Setting, level::Level, lang::tr};
 use crate::Indices;
 use self::worlds::{draw_world_display, WorldCreator};
 
@@ -16,6 +16,8 @@ pub mod in_game_menu;
 pub struct MainScreen {
     is_worlds: bool,
     world_creator: WorldCreator,
+    /// Why the last world failed to open
+    error: Option<String>,
 }
 
 impl MainScreen {
@@ -46,10 +48,10 @@ impl MainScreen {
                 // if ui.add(self::button::continue_button()).clicked() {
                 //     println!("Clicked");
                 // };
-                if ui.add(self::button::button("Play")).clicked() {
+                if ui.add(self::button::button(&tr("ui.menu.play"))).clicked() {
                     self.is_worlds = true;
                 };
-                if ui.add(self::button::button("Setting")).clicked() {
+                if ui.add(self::button::button(&tr("ui.menu.setting"))).clicked() {
                     *is_setting = !*is_setting;
                 };
                 if ui.add(self::button::exit()).clicked() {
@@ -61,7 +63,9 @@ impl MainScreen {
 
     fn draw_worlds(&mut self, ctx: &egui::Context, world_loader: &mut WorldLoader, level: &mut Option<Level>, setting: &Setting, indices: &Indices) {
         let mut remove_world = None;
-        egui::Window::new("Worlds")
+        let error = &mut self.error;
+        egui::Window::new(tr("ui.worlds.title"))
+            .id(egui::Id::new("worlds"))
             .open(&mut self.is_worlds)
             .movable(false)
             .collapsible(false)
@@ -70,11 +74,14 @@ impl MainScreen {
             .show(ctx, |ui| {
                 ui.spacing_mut().item_spacing.y = 7.0;
                 self.world_creator.draw(ui, level, world_loader);
+                if let Some(error) = error.as_ref() {
+                    ui.label(RichText::new(error).color(Color32::RED).size(17.0));
+                }
                 egui::ScrollArea::vertical()
                     .show(ui, |ui| {
                         world_loader.worlds.iter().for_each(|world| {
                             ui.horizontal_top(|ui| {
-                                draw_world_display(ui, world, level, setting, &mut remove_world, indices);
+                                draw_world_display(ui, world, level, setting, &mut remove_world, error, indices);
                             });
                         });
                     });
@@ -90,6 +97,7 @@ impl Default for MainScreen {
         Self {
             is_worlds: false,
             world_creator: WorldCreator::new(),
+            error: None,
         }
     }
 }
\ No newline at end of file
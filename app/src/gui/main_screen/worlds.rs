@@ -3,7 +3,7 @@ use std::{collections::{hash_map::DefaultHasher}, hash::{Hash, Hasher}};
 use chrono::{Utc, TimeZone};
 use egui::{vec2, Color32, RichText, Stroke, Ui};
 use crate::Indices;
-use crate::{gui::theme::DEFAULT_THEME, level::Level, setting::Setting, world::loader::{WorldData, WorldLoader}};
+use crate::{gui::theme::DEFAULT_THEME, lang::tr, level::Level, setting::Setting, world::loader::{WorldData, WorldLoader}};
 
 #[derive(Default, Debug, Clone)]
 pub struct WorldCreator {
@@ -23,18 +23,18 @@ impl WorldCreator {
                 .show(ui, |ui| {
                     ui.horizontal_top(|ui| {
                         ui.style_mut().override_text_style = Some(egui::TextStyle::Heading);
-                        ui.label(RichText::new("World name: ").size(21.0));
+                        ui.label(RichText::new(tr("ui.worlds.world_name")).size(21.0));
                         let world_edit = egui::TextEdit::singleline(&mut self.world_name)
                             .text_color(Color32::BLACK).frame(false);
                         ui.add_sized(vec2(121.0, 20.0), world_edit);
-                        ui.label(RichText::new("Seed: ").size(21.0));
+                        ui.label(RichText::new(tr("ui.worlds.seed")).size(21.0));
                         let seed_edit = egui::TextEdit::singleline(&mut self.seed)
                             .text_color(Color32::BLACK).frame(false);
                         ui.add_sized(vec2(121.0, 20.0), seed_edit);
                     });
                 });
             ui.add_space(5.0);
-            if ui.button(RichText::new("Create").size(24.0)).clicked() && self.world_name.len() > 0
+            if ui.button(RichText::new(tr("ui.worlds.create")).size(24.0)).clicked() && self.world_name.len() > 0
               && self.world_name.len() < 50 {
                 let seed = if !self.seed.is_empty() {
                     let mut hasher = DefaultHasher::new();
@@ -54,7 +54,7 @@ impl WorldCreator {
 }
 
 
-pub(crate) fn draw_world_display(ui: &mut Ui, world: &WorldData, level: &mut Option<Level>, setting: &Setting, remove_world: &mut Option<String>, indices: &Indices) {
+pub(crate) fn draw_world_display(ui: &mut Ui, world: &WorldData, level: &mut Option<Level>, setting: &Setting, remove_world: &mut Option<String>, error: &mut Option<String>, indices: &Indices) {
     egui::Frame::none()
         .fill(Color32::WHITE)
         .outer_margin(vec2(0.0, 0.0))
@@ -71,7 +71,7 @@ pub(crate) fn draw_world_display(ui: &mut Ui, world: &WorldData, level: &mut Opt
                             ui.heading(name);
                             ui.horizontal(|ui| {
                                 ui.horizontal(|ui| {
-                                    ui.label("Seed: ");
+                                    ui.label(tr("ui.worlds.seed"));
                                     ui.label(world.seed.to_string());
                                 });
                                 ui.add_space(ui.available_width());
@@ -96,7 +96,16 @@ pub(crate) fn draw_world_display(ui: &mut Ui, world: &WorldData, level: &mut Opt
             .fill(DEFAULT_THEME.green)
             .stroke(Stroke::NONE);
         if ui.add(button).clicked() {
-            *level = Some(Level::new(&world.name, world.seed, setting, indices));
+            match Level::new(&world.name, world.seed, setting, indices) {
+                Ok(new_level) => {
+                    *level = Some(new_level);
+                    *error = None;
+                },
+                Err(err) => {
+                    eprintln!("{}", err);
+                    *error = Some(err.to_string());
+                },
+            }
         }
         ui.add_space(5.0);
         let text = egui::RichText::new("🗑")
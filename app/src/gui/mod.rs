@@ -3,4 +3,6 @@ pub mod theme;
 pub mod my_widgets;
 pub mod draw;
 pub mod main_screen;
-pub mod setting;
\ No newline at end of file
+pub mod setting;
+pub mod ratio;
+pub mod simulation;
\ No newline at end of file
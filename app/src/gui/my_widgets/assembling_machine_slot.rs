@@ -1,6 +1,6 @@
 use egui::{Rect, RichText, vec2, Stroke, pos2, Color32};
 use graphics_engine::texture::TextureAtlas;
-use crate::{gui::theme::DEFAULT_THEME, recipes::{item::PossibleItem, recipe::Recipe}};
+use crate::{gui::theme::DEFAULT_THEME, recipes::{item::PossibleItem, items::ITEMS, recipe::Recipe}};
 
 const WIDTH: f32 = 50.0;
 const HEIGHT: f32 = 50.0;
@@ -50,12 +50,14 @@ fn assembling_machine_slot_ui(ui: &mut egui::Ui, texture_atlas: &TextureAtlas, i
                         .get(slot_id)
                         .map(|i| i.id())
                 } else {
-                    Some(recipe.result.id())
+                    recipe.results
+                        .get(slot_id)
+                        .map(|r| r.item.id())
                 },
                 |i| Some(i.id()));
         if let Some(item_id) = item_id {
             // Paint image
-            let uv_rect = texture_atlas.uv_rect(item_id);
+            let uv_rect = texture_atlas.uv_rect(ITEMS().icon(item_id));
             let mut image = egui::Image::new(egui::load::SizedTexture::new(texture_atlas.texture_id, vec2(WIDTH, HEIGHT)))
                 .uv(Rect::from_min_max(pos2(uv_rect.0, uv_rect.1), pos2(uv_rect.2, uv_rect.3)));
             if is_not_item {image = image.tint(GREY)}
@@ -0,0 +1,13 @@
+use egui::Color32;
+
+use crate::{lang::{fluid_name, tr}, recipes::{fluid::FluidBox, fluids::FLUIDS}};
+
+/// Fill level of a fluid box in the color of its fluid.
+pub fn fluid_bar(fluid_box: &FluidBox) -> impl egui::Widget {
+    let text = match fluid_box.fluid {
+        Some(fluid) => format!("{} {:.0}/{:.0}", fluid_name(fluid.id()), fluid.amount, fluid_box.capacity),
+        None => format!("{} 0/{:.0}", tr("ui.fluid.empty"), fluid_box.capacity),
+    };
+    let [r, g, b] = fluid_box.fluid_id().map_or([0, 0, 0], |id| FLUIDS().color(id));
+    egui::ProgressBar::new(fluid_box.level()).text(text).fill(Color32::from_rgb(r, g, b))
+}
\ No newline at end of file
@@ -1,6 +1,7 @@
 use egui::{Rect, RichText, vec2, Stroke, pos2};
 use graphics_engine::texture::TextureAtlas;
 use crate::{gui::theme::DEFAULT_THEME, recipes::item::PossibleItem};
+use super::item_tooltip::item_tooltip;
 
 const WIDTH: f32 = 50.0;
 const HEIGHT: f32 = 50.0;
@@ -23,7 +24,7 @@ fn hotbar_slot_ui(ui: &mut egui::Ui, texture_atlas: &TextureAtlas, item: &Possib
 
         if let Some(item) = item.0 {
             // Paint image
-            let uv_rect = texture_atlas.uv_rect(item.id());
+            let uv_rect = texture_atlas.uv_rect(item.icon());
             let image = egui::Image::new(egui::load::SizedTexture::new(texture_atlas.texture_id, vec2(WIDTH, HEIGHT)))
                 .uv(Rect::from_min_max(pos2(uv_rect.0, uv_rect.1), pos2(uv_rect.2, uv_rect.3)));
             
@@ -40,7 +41,10 @@ fn hotbar_slot_ui(ui: &mut egui::Ui, texture_atlas: &TextureAtlas, item: &Possib
             }
         } 
     }
-    response
+    match item.0 {
+        Some(item) => response.on_hover_ui(|ui| item_tooltip(ui, item.id())),
+        None => response,
+    }
 }
 
 
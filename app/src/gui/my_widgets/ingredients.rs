@@ -29,7 +29,7 @@ pub fn ingredients(ui: &mut egui::Ui, texture_atlas: &TextureAtlas, recipe: &Rec
                 );
 
                 // Paint image
-                let uv_rect = texture_atlas.uv_rect(item.id());
+                let uv_rect = texture_atlas.uv_rect(item.icon());
                 let image = egui::Image::new(egui::load::SizedTexture::new(texture_atlas.texture_id, vec2(WIDTH, HEIGHT)))
                     .uv(Rect::from_min_max(pos2(uv_rect.0, uv_rect.1), pos2(uv_rect.2, uv_rect.3)));
                 ui.put(inventory_slot_rect, image);
@@ -1,6 +1,8 @@
 use egui::{Rect, RichText, vec2, Stroke, pos2};
 
 use crate::{gui::theme::DEFAULT_THEME, recipes::item::PossibleItem};
+
+use super::item_tooltip::item_tooltip;
 use graphics_engine::texture::TextureAtlas;
 
 const WIDTH: f32 = 50.0;
@@ -38,7 +40,7 @@ fn inventory_slot_ui(ui: &mut egui::Ui, texture_atlas: &TextureAtlas, item: &Pos
 
         if let Some(item) = item.0 {
             // Paint image
-            let uv_rect = texture_atlas.uv_rect(item.id());
+            let uv_rect = texture_atlas.uv_rect(item.icon());
             let image = egui::Image::new(egui::load::SizedTexture::new(texture_atlas.texture_id, vec2(WIDTH, HEIGHT)))
                 .uv(Rect::from_min_max(pos2(uv_rect.0, uv_rect.1), pos2(uv_rect.2, uv_rect.3)));
             ui.put(inventory_slot_rect, image);
@@ -54,7 +56,10 @@ fn inventory_slot_ui(ui: &mut egui::Ui, texture_atlas: &TextureAtlas, item: &Pos
             }
         } 
     }
-    response
+    match item.0 {
+        Some(item) => response.on_hover_ui(|ui| item_tooltip(ui, item.id())),
+        None => response,
+    }
 }
 
 
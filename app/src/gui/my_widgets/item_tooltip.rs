@@ -0,0 +1,11 @@
+use egui::RichText;
+
+use crate::lang::{item_description, item_name};
+
+/// Name and description of the item, shown on hover over a slot.
+pub fn item_tooltip(ui: &mut egui::Ui, id: u32) {
+    ui.label(RichText::new(item_name(id)).size(20.0).strong());
+    if let Some(description) = item_description(id) {
+        ui.label(description);
+    }
+}
\ No newline at end of file
@@ -36,7 +36,7 @@ fn recipe_ui(ui: &mut egui::Ui, texture_atlas: &TextureAtlas, recipe: &Recipe) -
         // Paint image
         recipe_rect.min = pos2(recipe_rect.min.x+PADDING, recipe_rect.min.y+PADDING);
         recipe_rect.max = pos2(recipe_rect.max.x-PADDING, recipe_rect.max.y-PADDING);
-        let uv_rect = texture_atlas.uv_rect(recipe.result.id());
+        let uv_rect = texture_atlas.uv_rect(recipe.result().icon());
         let image = egui::Image::new(egui::load::SizedTexture::new(texture_atlas.texture_id, vec2(IMAGE_WIDTH, IMAGE_HEIGHT)))
             .uv(Rect::from_min_max(pos2(uv_rect.0, uv_rect.1), pos2(uv_rect.2, uv_rect.3)));
         ui.put(recipe_rect, image);
@@ -0,0 +1,143 @@
+use egui::{vec2, Context, RichText};
+
+use crate::{lang::{item_name, tr, tr_opt}, recipes::{items::ITEMS, recipes::RECIPES, ratio::{crafter_name, RatioNote, RatioSolver}, recipe::RecipeCrafter}};
+
+/// Items per minute.
+fn rate_text(rate: f32) -> String {
+    format!("{:.1}/{}", rate * 60.0, tr("ui.ratio.minute"))
+}
+
+fn crafter_text(crafter: RecipeCrafter) -> String {
+    let name = crafter_name(crafter);
+    tr_opt(&format!("ui.ratio.crafter.{}", name)).unwrap_or(name)
+}
+
+/// State of the production ratio calculator, kept between frames.
+pub struct RatioPanel {
+    item: Option<u32>,
+    /// Items per minute
+    rate: f32,
+    by_hand: bool,
+    /// Built on the first draw, the recipes are not loaded yet when the panel is created
+    solver: Option<RatioSolver>,
+}
+
+impl RatioPanel {
+    pub fn new() -> Self {
+        Self { item: None, rate: 60.0, by_hand: false, solver: None }
+    }
+
+    fn solver(by_hand: bool) -> RatioSolver {
+        let crafters: Vec<RecipeCrafter> = RatioSolver::machines().into_iter()
+            .filter(|crafter| by_hand || !crafter.contains(RecipeCrafter::PLAYER))
+            .collect();
+        RatioSolver::new(&crafters)
+    }
+}
+
+impl Default for RatioPanel {
+    fn default() -> Self {Self::new()}
+}
+
+/// Opened and closed with F2, together with the cursor.
+pub fn draw_ratio(ctx: &Context, panel: &mut RatioPanel) {
+    egui::Window::new(tr("ui.ratio.title"))
+        .id(egui::Id::new("ratio"))
+        .movable(true)
+        .resizable(false)
+        .collapsible(false)
+        .default_pos([0.0, 0.0])
+        .show(ctx, |ui| {
+            let by_hand = panel.by_hand;
+            let solver = panel.solver.get_or_insert_with(|| RatioPanel::solver(by_hand));
+            ui.spacing_mut().item_spacing = vec2(4.0, 2.0);
+            ui.horizontal(|ui| {
+                ui.label(tr("ui.ratio.item"));
+                egui::ComboBox::from_id_source("ratio_item")
+                    .selected_text(panel.item.map(item_name).unwrap_or_default())
+                    .show_ui(ui, |ui| {
+                        for item in ITEMS().all.iter().filter(|item| !solver.recipes(item.id).is_empty()) {
+                            ui.selectable_value(&mut panel.item, Some(item.id), item_name(item.id));
+                        }
+                    });
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("ui.ratio.rate"));
+                ui.add(egui::DragValue::new(&mut panel.rate).clamp_range(0.0..=100000.0).speed(1.0));
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("ui.ratio.by_hand"));
+                if ui.checkbox(&mut panel.by_hand, "").changed() {
+                    *solver = RatioPanel::solver(panel.by_hand);
+                }
+            });
+
+            let Some(item) = panel.item else {return};
+            let plan = solver.solve(item, panel.rate / 60.0);
+
+            ui.add_space(10.0);
+            ui.heading(RichText::new(tr("ui.ratio.machines")).size(20.0));
+            egui::Grid::new("ratio_steps").striped(true).show(ui, |ui| {
+                for step in &plan.steps {
+                    ui.label(item_name(step.item));
+                    ui.label(crafter_text(step.crafter));
+                    ui.label(format!("{} ({:.2})", step.machines.ceil(), step.machines));
+                    ui.label(rate_text(step.crafts * step.recipe.expected_count(step.item)));
+                    ui.end_row();
+                }
+            });
+
+            let mut choice = None;
+            for note in &plan.notes {
+                match note {
+                    RatioNote::Alternatives { item, recipes, chosen } => {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{} {}", tr("ui.ratio.recipe"), item_name(*item)));
+                            let name = |id: u32| solver.recipes(*item).iter()
+                                .find(|(recipe, _)| recipe.id == id)
+                                .map(|(recipe, crafter)| format!("{} ({})", recipe.name, crafter_text(*crafter)))
+                                .unwrap_or_default();
+                            egui::ComboBox::from_id_source(("ratio_recipe", *item))
+                                .selected_text(chosen.map_or_else(|| tr("ui.ratio.raw_input"), name))
+                                .show_ui(ui, |ui| {
+                                    for &id in recipes {
+                                        if ui.selectable_label(Some(id) == *chosen, name(id)).clicked() {
+                                            choice = Some((*item, id));
+                                        }
+                                    }
+                                });
+                        });
+                    },
+                    RatioNote::Cycle { item, recipe } => {
+                        let recipe = &RECIPES().all[*recipe as usize].name;
+                        ui.label(format!("{} {} ({})", tr("ui.ratio.cycle"), item_name(*item), recipe));
+                    },
+                }
+            }
+            if let Some((item, recipe)) = choice {
+                solver.set_recipe(item, recipe);
+            }
+
+            if !plan.flows.is_empty() {
+                ui.add_space(10.0);
+                ui.heading(RichText::new(tr("ui.ratio.intermediate")).size(20.0));
+                for (item, rate) in &plan.flows {
+                    ui.label(format!("{}: {}", item_name(*item), rate_text(*rate)));
+                }
+            }
+
+            ui.add_space(10.0);
+            ui.heading(RichText::new(tr("ui.ratio.raw")).size(20.0));
+            for (item, rate) in &plan.raw {
+                ui.label(format!("{}: {}", item_name(*item), rate_text(*rate)));
+            }
+
+            if !plan.byproducts.is_empty() {
+                ui.add_space(10.0);
+                ui.heading(RichText::new(tr("ui.ratio.byproducts")).size(20.0));
+                for (item, rate) in &plan.byproducts {
+                    ui.label(format!("{}: {}", item_name(*item), rate_text(*rate)));
+                }
+            }
+        });
+}
\ No newline at end of file
@@ -1,5 +1,5 @@
 use egui::{Context, vec2, RichText};
-use crate::{setting::Setting, save_load::SettingSave};
+use crate::{lang::{locales, set_locale, tr}, setting::Setting, save_load::SettingSave, threads::scheduler};
 
 
 pub fn draw_setting(ctx: &Context, open: &mut bool, setting: &mut Setting, save: &SettingSave) {
@@ -7,7 +7,8 @@ pub fn draw_setting(ctx: &Context, open: &mut bool, setting: &mut Setting, save:
     let device_type: &mut Option<wgpu::DeviceType> = &mut setting.graphic.device_type;
     let backends: &mut Option<wgpu::Backends> = &mut setting.graphic.backends;
     let sample_count: &mut u32 = &mut setting.graphic.sample_count;
-    egui::Window::new("Setting")
+    egui::Window::new(tr("ui.setting.title"))
+        .id(egui::Id::new("setting"))
         .open(open)
         .movable(true)
         .resizable(false)
@@ -17,7 +18,7 @@ pub fn draw_setting(ctx: &Context, open: &mut bool, setting: &mut Setting, save:
             ui.style_mut().override_text_style = Some(egui::TextStyle::Heading);
             ui.spacing_mut().item_spacing = vec2(4.0, 2.0);
             ui.horizontal(|ui| {
-                ui.label("Render radius:");
+                ui.label(tr("ui.setting.render_radius"));
                 ui.spacing_mut().slider_width = 180.0;
                 ui.add(
                     egui::Slider::new(&mut setting.render_radius, 3..=100)
@@ -26,37 +27,55 @@ pub fn draw_setting(ctx: &Context, open: &mut bool, setting: &mut Setting, save:
                 ui.label(&format!(" {}", setting.render_radius));
             });
             ui.horizontal(|ui| {
-                ui.label("Greedy meshing:");
+                ui.label(tr("ui.setting.greedy_meshing"));
                 ui.checkbox(&mut setting.is_greedy_meshing, "");
             });
 
             ui.horizontal(|ui| {
-                ui.label("Fullscreen:");
+                ui.label(tr("ui.setting.language"));
+                for (locale, name) in locales() {
+                    if ui.selectable_value(&mut setting.language, locale.clone(), name).changed() {
+                        set_locale(&locale);
+                    }
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label(tr("ui.setting.ups"));
+                ui.spacing_mut().slider_width = 180.0;
+                if ui.add(egui::Slider::new(&mut setting.ups, 5..=60).show_value(false)).changed() {
+                    scheduler::set_ups_target(setting.ups);
+                }
+                ui.label(format!(" {}", setting.ups));
+            });
+
+            ui.horizontal(|ui| {
+                ui.label(tr("ui.setting.fullscreen"));
                 ui.checkbox(&mut true, "");
             });
 
             ui.add_space(10.0);
-            ui.heading(RichText::new("Graphics Settings (Restart required)").size(20.0));
+            ui.heading(RichText::new(tr("ui.setting.graphics")).size(20.0));
             ui.add_space(4.0);
             ui.horizontal(|ui| {
-                ui.label("Vsync:");
+                ui.label(tr("ui.setting.vsync"));
                 ui.checkbox(&mut setting.graphic.vsync, "");
             });
             ui.horizontal(|ui| {
-                ui.label("Backend:");
-                ui.selectable_value(backends, None, "Auto");
+                ui.label(tr("ui.setting.backend"));
+                ui.selectable_value(backends, None, tr("ui.setting.auto"));
                 ui.selectable_value(backends, Some(wgpu::Backends::VULKAN), "Vulkan");
                 ui.selectable_value(backends, Some(wgpu::Backends::DX12), "Dx12");
                 ui.selectable_value(backends, Some(wgpu::Backends::METAL), "Metal");
             });
             ui.horizontal(|ui| {
-                ui.label("Device:");
-                ui.selectable_value(device_type, None, "Auto");
+                ui.label(tr("ui.setting.device"));
+                ui.selectable_value(device_type, None, tr("ui.setting.auto"));
                 ui.selectable_value(device_type, Some(wgpu::DeviceType::DiscreteGpu), "DiscreteGpu");
                 ui.selectable_value(device_type, Some(wgpu::DeviceType::IntegratedGpu), "IntegratedGpu");
             });
             ui.horizontal(|ui| {
-                ui.label("Sample count:");
+                ui.label(tr("ui.setting.sample_count"));
                 ui.selectable_value(sample_count, 1, "X1");
                 ui.selectable_value(sample_count, 2, "X2");
                 ui.selectable_value(sample_count, 4, "X4");
@@ -64,7 +83,7 @@ pub fn draw_setting(ctx: &Context, open: &mut bool, setting: &mut Setting, save:
                 ui.selectable_value(sample_count, 16, "X16");
             });
 
-            if ui.button("Save setting").clicked() {
+            if ui.button(tr("ui.setting.save")).clicked() {
                 save.save(setting_save);
             };
         });
@@ -0,0 +1,44 @@
+use egui::{RichText, Ui};
+
+use crate::{lang::tr, threads::scheduler::{self, SPEEDS}, world::clock};
+
+use super::theme::DEFAULT_THEME;
+
+fn button(ui: &mut Ui, text: String) -> bool {
+    ui.add(egui::Button::new(RichText::new(text).color(DEFAULT_THEME.on_primary)).fill(DEFAULT_THEME.primary)).clicked()
+}
+
+/// Pause, single step and speed of the simulation with its measured UPS.
+pub fn simulation_controls(ui: &mut Ui) {
+    let stats = scheduler::stats();
+    ui.colored_label(DEFAULT_THEME.on_background, format!(
+        "{} {:.1} / {:.1}\n{} {:.2} ms ({:.2} ms)\n{} {}",
+        tr("ui.simulation.ups"), stats.ups, scheduler::target_ups(),
+        tr("ui.simulation.tick_time"), stats.tick_time.as_secs_f32() * 1000.0, stats.max_tick_time.as_secs_f32() * 1000.0,
+        tr("ui.simulation.tick"), clock::now(),
+    ));
+    if stats.dropped > 0 {
+        ui.colored_label(DEFAULT_THEME.on_background, format!("{} {}", tr("ui.simulation.dropped"), stats.dropped));
+    }
+
+    ui.horizontal(|ui| {
+        let paused = scheduler::is_paused();
+        if button(ui, tr(if paused {"ui.simulation.resume"} else {"ui.simulation.pause"})) {
+            scheduler::set_paused(!paused);
+        }
+        ui.add_enabled_ui(paused, |ui| {
+            if button(ui, tr("ui.simulation.step")) {
+                scheduler::step(1);
+            }
+        });
+    });
+
+    ui.horizontal(|ui| {
+        let speed = scheduler::speed();
+        for option in SPEEDS {
+            if ui.selectable_label(speed == option, format!("{}x", option)).clicked() {
+                scheduler::set_speed(option);
+            }
+        }
+    });
+}
\ No newline at end of file
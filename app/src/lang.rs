@@ -0,0 +1,187 @@
+use std::{collections::HashMap, path::PathBuf, sync::RwLock};
+
+use crate::{content_loader::indices::GamePath, recipes::{fluids::FLUIDS, item_type::ItemType, items::ITEMS, technology::TECHNOLOGIES}};
+
+/// Used for the keys the selected language does not have.
+pub const FALLBACK_LOCALE: &str = "en";
+
+/// Texts from `lang/<locale>.toml` of the base game and of the active content packs.
+///
+/// Nested tables make dotted keys, `[block.furnace] name = "Furnace"` is `block.furnace.name`.
+/// Files of a content pack override the earlier ones. Block ids in the `block.` keys of a pack
+/// get the pack prefix, like the ids of its block files.
+#[derive(Debug, Default)]
+pub struct Lang {
+    pub locale: String,
+    texts: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+    content: Vec<GamePath<PathBuf>>,
+    /// Found once, the lang files are not watched
+    locales: Vec<(String, String)>,
+}
+
+fn flatten(table: toml::Table, prefix: &str, texts: &mut HashMap<String, String>) {
+    for (key, value) in table {
+        let key = if prefix.is_empty() {key} else {format!("{}.{}", prefix, key)};
+        match value {
+            toml::Value::String(text) => {texts.insert(key, text);},
+            toml::Value::Table(table) => flatten(table, &key, texts),
+            _ => eprintln!("Translation \"{}\" is not a string", key),
+        }
+    }
+}
+
+fn load_locale(content: &[GamePath<PathBuf>], locale: &str) -> HashMap<String, String> {
+    let mut texts = HashMap::new();
+    for root in content {
+        let path = root.path.join("lang").join(format!("{}.toml", locale));
+        let Ok(data) = std::fs::read_to_string(&path) else {continue};
+        let table = match data.parse::<toml::Table>() {
+            Ok(table) => table,
+            Err(err) => {
+                eprintln!("Failed to parse translations {:?}: {}", path, err);
+                continue;
+            }
+        };
+        let mut file = HashMap::new();
+        flatten(table, "", &mut file);
+        texts.extend(file.into_iter().map(|(key, text)| match key.strip_prefix("block.") {
+            Some(block) if !block.starts_with('@') => (format!("block.{}{}", root.prefix(), block), text),
+            _ => (key, text),
+        }));
+    }
+    texts
+}
+
+/// Locales that have a file in the base game or in any active content pack, with their own names.
+fn find_locales(content: &[GamePath<PathBuf>]) -> Vec<(String, String)> {
+    let mut locales: Vec<String> = content.iter()
+        .filter_map(|root| std::fs::read_dir(root.path.join("lang")).ok())
+        .flat_map(|dir| dir.flatten())
+        .filter_map(|entry| entry.path().file_stem().and_then(|s| s.to_str()).map(String::from))
+        .collect();
+    locales.sort();
+    locales.dedup();
+    locales.into_iter().map(|locale| {
+        let name = load_locale(content, &locale).remove("language.name").unwrap_or_else(|| locale.clone());
+        (locale, name)
+    }).collect()
+}
+
+impl Lang {
+    pub fn new(content: &[GamePath<PathBuf>], locale: &str) -> Self {
+        Self {
+            locale: locale.to_string(),
+            texts: load_locale(content, locale),
+            fallback: load_locale(content, FALLBACK_LOCALE),
+            content: content.to_vec(),
+            locales: find_locales(content),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.texts.get(key).or_else(|| self.fallback.get(key)).map(String::as_str)
+    }
+
+    /// Locales that have a file in the base game or in any active content pack, with their own names.
+    pub fn locales(&self) -> &[(String, String)] {
+        &self.locales
+    }
+}
+
+static LANG: RwLock<Option<Lang>> = RwLock::new(None);
+
+pub fn init_lang(content: &[GamePath<PathBuf>], locale: &str) {
+    *LANG.write().unwrap() = Some(Lang::new(content, locale));
+}
+
+/// Switches the language, the texts are read again.
+pub fn set_locale(locale: &str) {
+    let mut lang = LANG.write().unwrap();
+    match lang.as_mut() {
+        Some(lang) => {
+            lang.texts = load_locale(&lang.content, locale);
+            lang.locale = locale.to_string();
+        },
+        None => *lang = Some(Lang::new(&[], locale)),
+    }
+}
+
+/// Doesn't read the lang files, so it can be called every frame.
+pub fn locales() -> Vec<(String, String)> {
+    LANG.read().unwrap().as_ref().map(|lang| lang.locales().to_vec()).unwrap_or_default()
+}
+
+pub fn tr_opt(key: &str) -> Option<String> {
+    LANG.read().unwrap().as_ref().and_then(|lang| lang.get(key)).map(String::from)
+}
+
+/// Text of the key in the selected language, in English or the key itself.
+pub fn tr(key: &str) -> String {
+    tr_opt(key).unwrap_or_else(|| key.to_string())
+}
+
+pub fn block_name(block: &str) -> String {
+    tr_opt(&format!("block.{}.name", block)).unwrap_or_else(|| block.to_string())
+}
+
+/// Items without a name are named after the block they place or by their id.
+pub fn item_name(id: u32) -> String {
+    let Some(item) = ITEMS().get(id) else {return id.to_string()};
+    tr_opt(&format!("item.{}.name", item.name))
+        .or_else(|| block_text(item, "name"))
+        .or_else(|| item.block.clone())
+        .unwrap_or_else(|| item.name.clone())
+}
+
+/// Text of the block an item places, the block is looked up in the pack of the item first.
+fn block_text(item: &ItemType, text: &str) -> Option<String> {
+    let block = item.block.as_ref()?;
+    let qualified = (!item.prefix.is_empty() && !block.starts_with('@')).then(|| format!("{}{}", item.prefix, block));
+    qualified.iter().chain(std::iter::once(block))
+        .find_map(|block| tr_opt(&format!("block.{}.{}", block, text)))
+}
+
+pub fn technology_name(id: u32) -> String {
+    let Some(technology) = TECHNOLOGIES().get(id) else {return id.to_string()};
+    tr_opt(&format!("technology.{}.name", technology.name)).unwrap_or_else(|| technology.name.clone())
+}
+
+pub fn fluid_name(id: u32) -> String {
+    let Some(fluid) = FLUIDS().get(id) else {return id.to_string()};
+    tr_opt(&format!("fluid.{}.name", fluid.name)).unwrap_or_else(|| fluid.name.clone())
+}
+
+pub fn item_description(id: u32) -> Option<String> {
+    let item = ITEMS().get(id)?;
+    tr_opt(&format!("item.{}.description", item.name))
+        .or_else(|| block_text(item, "description"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &std::path::Path, locale: &str, texts: &str) {
+        std::fs::create_dir_all(root.join("lang")).unwrap();
+        std::fs::write(root.join("lang").join(format!("{}.toml", locale)), texts).unwrap();
+    }
+
+    #[test]
+    fn missing_keys_fall_back_to_english() {
+        let root = tempfile::tempdir().unwrap();
+        let (game, pack) = (root.path().join("game"), root.path().join("pack"));
+        write(&game, "en", "[block.furnace]\nname = \"Furnace\"\ndescription = \"Smelts ore\"\n[ui]\nquit = \"Quit\"");
+        write(&game, "de", "language.name = \"Deutsch\"\n[block.furnace]\nname = \"Ofen\"");
+        write(&pack, "en", "[block.lamp]\nname = \"Lamp\"\n[ui]\nquit = \"Leave\"");
+        let content = [GamePath { path: game, prefix: None }, GamePath { path: pack, prefix: Some("@pack:".to_string()) }];
+
+        let lang = Lang::new(&content, "de");
+        assert_eq!(lang.get("block.furnace.name"), Some("Ofen"));
+        assert_eq!(lang.get("block.furnace.description"), Some("Smelts ore"));
+        assert_eq!(lang.get("ui.quit"), Some("Leave"));
+        assert_eq!(lang.get("block.@pack:lamp.name"), Some("Lamp"));
+        assert_eq!(lang.get("block.lamp.name"), None);
+        assert_eq!(lang.locales(), [("de".to_string(), "Deutsch".to_string()), ("en".to_string(), "en".to_string())]);
+    }
+}
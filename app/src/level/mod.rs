@@ -1,7 +1,7 @@
 use std::{path::PathBuf, sync::{Arc, Mutex, Condvar, mpsc::{Sender, Receiver}}};
 use graphics_engine::{mesh::Mesh, state::{State}};
-use crate::Indices;
-use crate::{camera, content::Content, coords::{chunk_coord::ChunkCoord, global_coord::GlobalCoord}, direction::Direction, frustum, graphic::{render::RenderResult, render_selection::render_selection}, gui::gui_controller::GuiController, input_event::{input_service::{InputService, Mouse}, KeypressState}, meshes::{Meshes, MeshesRenderInput}, my_time::Time, nalgebra_converter::Conventer, player::player::Player, recipes::{item::Item, storage::Storage}, save_load::WorldSaver, setting::Setting, threads::{save::SaveState, Threads}, unsafe_mutex::UnsafeMutex, voxels::{chunks::WORLD_HEIGHT, ray_cast::ray_cast}, world::{sun::{Color, Sun}, World}, CAMERA_FAR, CAMERA_FOV, CAMERA_NEAR};
+use crate::{content_loader::error::ContentError, Indices};
+use crate::{camera, content::Content, coords::{chunk_coord::ChunkCoord, global_coord::GlobalCoord}, direction::Direction, frustum, graphic::{render::RenderResult, render_selection::render_selection}, gui::gui_controller::GuiController, input_event::{input_service::{InputService, Mouse}, KeypressState}, meshes::{Meshes, MeshesRenderInput}, my_time::Time, nalgebra_converter::Conventer, player::player::Player, recipes::{items::ITEMS, research::{load_research, unload_research}, storage::Storage}, save_load::WorldSaver, setting::Setting, threads::{save::SaveState, Threads}, unsafe_mutex::UnsafeMutex, voxels::{chunks::WORLD_HEIGHT, ray_cast::ray_cast}, world::{clock::{load_clock, unload_clock}, sun::{Color, Sun}, World}, CAMERA_FAR, CAMERA_FOV, CAMERA_NEAR};
 use nalgebra_glm as glm;
 
 pub struct Level {
@@ -18,12 +18,14 @@ pub struct Level {
 }
 
 impl Level {
-    pub fn new(world_name: &str, seed: u64, setting: &Setting, indices: &Indices) -> Self {
+    pub fn new(world_name: &str, seed: u64, setting: &Setting, indices: &Indices) -> Result<Self, ContentError> {
         let (render_sender, render_recv) = std::sync::mpsc::channel::<RenderResult>();
         let (indices_sender, indices_recv) = std::sync::mpsc::channel::<Vec<(usize, usize)>>();
         let mut path = PathBuf::from("./data/worlds/");
         path.push(world_name);
-        let content = Arc::new(Content::new(indices, path.as_path()));
+        let content = Arc::new(Content::new(indices, path.as_path())?);
+        load_research(&path);
+        load_clock(&path);
         let world_saver = Arc::new(WorldSaver::new(path));
         let player = match world_saver.player.lock().unwrap().load_player() {
             Some(player) => player,
@@ -33,10 +35,10 @@ impl Level {
                 let mut player = Player::new(camera, glm::vec3(0.0, 20.0, 0.0));
                 let binding = player.inventory();
                 let mut inventory = binding.lock().unwrap();
-                _ = inventory.add_by_index(&Item::new(0, 100), 10);
-                _ = inventory.add_by_index(&Item::new(1, 100), 11);
-                _ = inventory.add_by_index(&Item::new(2, 100), 12);
-                _ = inventory.add_by_index(&Item::new(3, 100), 13);
+                ["iron_ore", "iron_ingot", "iron_plate", "rock"].iter().enumerate().for_each(|(i, name)| {
+                    let Some(item) = ITEMS().item(name, 100) else {return};
+                    _ = inventory.add_by_index(&item, 10 + i);
+                });
                 player
             }
         };
@@ -72,7 +74,7 @@ impl Level {
              Color(0.0, 0.0, 0.0), Color(0.0, 0.0, 0.0),
              Color(1.0, 0.301, 0.0)]);
 
-        Self {
+        Ok(Self {
             meshes: Meshes::new(Arc::clone(&content)),
             content,
             player,
@@ -82,7 +84,7 @@ impl Level {
             render_recv,
             indices_sender,
             indices_recv
-        }
+        })
     }
 
     pub fn update(
@@ -127,8 +129,8 @@ impl Level {
             let voxel_id = voxel.map_or(0, |v| v.id) as usize;
 
             if voxel_id != 0 {
-                let min = self.content.blocks[voxel_id].min_point() + global.into();
-                let max = self.content.blocks[voxel_id].max_point() + global.into();
+                let min = self.content.blocks[voxel_id].load().min_point() + global.into();
+                let max = self.content.blocks[voxel_id].load().max_point() + global.into();
                 state.selection_vertex_buffer =
                     Some(render_selection(
                         state.device(),
@@ -141,7 +143,7 @@ impl Level {
             let direction = Direction::new(front.x, front.y, front.z);
 
             if input.is_mouse(&Mouse::Left, KeypressState::AnyPress) && !is_cursor {
-                self.content.blocks[voxel_id].on_block_break(&self.world, &mut player, &global, &direction);
+                self.content.blocks[voxel_id].load().on_block_break(&self.world, &mut player, &global, &direction);
             } else if input.is_mouse(&Mouse::Right, KeypressState::AnyJustPress) && !is_cursor {
                 let gxyz = global + norm.tuple().into();
                 let storage = self.world.chunks.master_live_voxel(global).and_then(|vd| vd.live_voxel.player_unlockable());
@@ -151,7 +153,7 @@ impl Level {
                     gui_controller.set_cursor_lock(player.is_inventory);
                     state.set_ui_interaction(player.is_inventory);
                 } else if let Some(block_id) = debug_block_id {
-                    self.content.blocks[*block_id as usize].on_block_set(
+                    self.content.blocks[*block_id as usize].load().on_block_set(
                         &self.world, &mut player, &gxyz, &direction);
                 } else {
                     player.on_right_click(&self.world, &gxyz, &direction, &self.content);
@@ -191,6 +193,8 @@ impl Drop for Level {
     fn drop(&mut self) {
         let Some(threads) = self.threads.take() else {return};
         threads.finalize();
+        unload_research();
+        unload_clock();
         println!("All saved!");
     }
 }
\ No newline at end of file
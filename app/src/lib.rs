@@ -1,4 +1,4 @@
-use std::{collections::{HashMap, VecDeque}, future::IntoFuture, hash::Hash, path::Path, sync::{atomic::AtomicBool, Arc}, time::{Duration, Instant}};
+use std::{collections::VecDeque, future::IntoFuture, hash::Hash, path::Path, sync::{atomic::AtomicBool, Arc}, time::{Duration, Instant}};
 use camera::frustum::Frustum;
 
 use coords::chunk_coord::ChunkCoord;
@@ -11,8 +11,8 @@ use level::Level;
 
 use unsafe_mutex::UnsafeMutex;
 use world::{loader::WorldLoader};
-use crate::{content_loader::{indices::{load_animated_models, load_blocks_textures, load_models, GamePath, Indices}, ContentLoader}, save_load::Save, voxels::{block::block_test::test_serde_block, chunk::HALF_CHUNK_SIZE}};
-use voxels::{chunk::CHUNK_SIZE, chunks::Chunks, live_voxels::{BoxDesiarializeLiveVoxel, BoxNewLiveVoxel, DesiarializeLiveVoxel, NewLiveVoxel}};
+use crate::{content_loader::{hot_reload::{self, ContentWatcher}, indices::Indices, ContentLoader, CONTENT_PACKS_PATH, GAME_PATH}, save_load::Save, voxels::{block::block_test::test_serde_block, chunk::HALF_CHUNK_SIZE}};
+use voxels::{chunk::CHUNK_SIZE, chunks::Chunks, live_voxels::{BoxDesiarializeLiveVoxel, BoxNewLiveVoxel}};
 
 use winit::{
     dpi::PhysicalSize, event::*, event_loop::{EventLoop, EventLoopWindowTarget}, window::{Fullscreen, WindowBuilder}
@@ -47,13 +47,16 @@ pub mod level;
 pub mod nalgebra_converter;
 pub mod content;
 pub mod server;
+pub mod plugin;
+pub mod registrator;
+pub mod scripting;
+pub mod wasm_plugin;
+pub mod lang;
+pub mod power;
 
-const _GAME_VERSION: u32 = 1;
+pub use registrator::Registrator;
 
-pub struct Registrator {
-    pub c: HashMap<String, NewLiveVoxel>,
-    pub from_bytes: HashMap<String, DesiarializeLiveVoxel>,
-}
+const _GAME_VERSION: u32 = 1;
 
 const CAMERA_FOV: f32 = 1.2;
 const CAMERA_NEAR: f32 = 0.1;
@@ -78,15 +81,20 @@ pub fn frustum(chunks: &Chunks, frustum: &Frustum) -> Vec<usize> {
 
 #[no_mangle]
 pub extern "C" fn run() {
+    run_with(ContentLoader::new(CONTENT_PACKS_PATH));
+}
+
+/// Same as [`run`], but with content packs that were already discovered, for example to load their mods first.
+pub fn run_with(content_loader: ContentLoader) {
     let rt = tokio::runtime::Builder::new_current_thread()
         .enable_all()
         .build()
         .unwrap();
 
-    rt.block_on(run_async());
+    rt.block_on(run_async(content_loader));
 }
 
-pub async fn run_async() {
+pub async fn run_async(content_loader: ContentLoader) {
     println!("{:?}", Path::new("./data/").canonicalize());
     let mut world_loader = WorldLoader::new(Path::new("./data/worlds/"));
     
@@ -97,28 +105,24 @@ pub async fn run_async() {
     //let source = Decoder::new(file).unwrap();
     // Play the sound directly on the device
     //let _ = stream_handle.play_raw(source.convert_samples());
-    let (blocks_indices, blocks, blocks_len) = load_blocks_textures(&[GamePath {
-        path: "./res/game/assets/blocks/".into(),
-        prefix: None
-    }]);
-    let (models_indices, models) = load_models(&["./res/game/models"], &["./res/game/assets/models"]);
-    let (animated_models_indices, animated_models) = load_animated_models(&["./res/game/animated_models"],
-        &["./res/game/assets/models"]);
+    let (mut indices, assets) = match content_loader.load_indices(GAME_PATH) {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            eprintln!("Failed to load content: {}", err);
+            return;
+        }
+    };
 
     let img = image::open("./res/game/assets/items/items.png").expect("./res/game/assets/items/items.png");
     let (width, height) = (img.width(), img.height());
     if width != height { panic!("Use square textures") }
 
 
-    let indices = Indices {
-        block: blocks_indices,
-        models: models_indices,
-        animated_models: animated_models_indices,
-    };
-
     let save = Save::new("./data/worlds/debug/", "./data/");
     let mut setting = save.setting.load().unwrap_or_default();
     save.setting.save(&setting);
+    lang::init_lang(&indices.content, &setting.language);
+    threads::scheduler::set_ups_target(setting.ups);
 
     let mut debug_block_id = None;
 
@@ -132,15 +136,22 @@ pub async fn run_async() {
     let mut input = input_event::input_service::InputService::new();
     let mut time = my_time::Time::new();
 
+    // Development mode, block files and textures are reloaded when they change.
+    let watcher = if std::env::args().any(|arg| arg == "hot-reload") {
+        ContentWatcher::new(&indices.content).map_err(|err| eprintln!("Failed to watch content: {}", err)).ok()
+    } else {
+        None
+    };
+
     let mut level: Option<Level> = None;
     let mut exit_level = false;
     let mut state = state::State::new(
         window.clone(),
         &setting.graphic,
-        blocks,
-        blocks_len,
-        models,
-        animated_models,
+        assets.blocks,
+        assets.blocks_len,
+        assets.models,
+        assets.animated_models,
         img.as_bytes(),
         width,
         &[[1.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]]).await;
@@ -155,6 +166,10 @@ pub async fn run_async() {
             exit_level = false;
         };
 
+        if let Some(changes) = watcher.as_ref().map(ContentWatcher::changes).filter(|c| !c.is_empty()) {
+            hot_reload::reload(&changes, &mut indices, state, level.as_ref());
+        }
+
         let mut debug_data = String::new();
         let mesh_vec = if let Some(level) = &mut level {
             let result = level.update(
@@ -172,9 +187,13 @@ pub async fn run_async() {
             state.set_sun_color(sun.into());
             state.set_clear_color(sky.into());
 
-            if input.is_key(&Key::KeyE, KeypressState::AnyJustPress) {
-                gui_controller.set_cursor_lock(player.is_inventory);
-                state.set_ui_interaction(player.is_inventory);
+            if input.is_key(&Key::F2, KeypressState::AnyJustPress) {
+                gui_controller.toggle_ratio();
+            }
+            if input.is_key(&Key::KeyE, KeypressState::AnyJustPress) || input.is_key(&Key::F2, KeypressState::AnyJustPress) {
+                let is_cursor = player.is_inventory || gui_controller.is_ratio();
+                gui_controller.set_cursor_lock(is_cursor);
+                state.set_ui_interaction(is_cursor);
             }
             result
         } else {vec![]};
@@ -216,7 +235,8 @@ pub async fn run_async() {
                 gui_controller
                     .draw_inventory(ctx, &mut player)
                     .draw_debug(ctx, &debug_data, &mut debug_block_id)
-                    .draw_active_recieps(ctx, &mut player);
+                    .draw_active_recieps(ctx, &mut player)
+                    .draw_ratio(ctx);
 
                 drop(player);
                 gui_controller.draw_in_game_menu(ctx, &mut exit_level);
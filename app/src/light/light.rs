@@ -54,7 +54,7 @@ impl LightSolvers {
 
         for (ly, lz, lx) in iproduct!((0..(CHUNK_SIZE-1) as u8).rev(), 0..CHUNK_SIZE as u8, 0..CHUNK_SIZE as u8) {
             let id = chunk.voxel((lx, ly, lz).into()).id as usize;
-            if chunk.lightmap.get((lx, (ly+1), lz).into()).get_sun() == 15 && self.content.blocks[id].is_light_passing() {
+            if chunk.lightmap.get((lx, (ly+1), lz).into()).get_sun() == 15 && self.content.blocks[id].load().is_light_passing() {
                 chunk.lightmap.get((lx, ly, lz).into()).set_sun(15);
                 let global = ChunkCoord::new(cx, cy, cz).to_global((lx, ly, lz).into());
                 self.solver_sun.add(chunks, global.x, global.y, global.z);
@@ -68,9 +68,9 @@ impl LightSolvers {
         for (ly, lz, lx) in iproduct!(0..CHUNK_SIZE, 0..CHUNK_SIZE, 0..CHUNK_SIZE) {
             let xyz = ChunkCoord::new(cx, cy, cz).to_global((lx as u8, ly as u8, lz as u8).into());
             let id = chunks.voxel_global(xyz).map_or(0, |v| v.id as usize);
-            let emission = self.content.blocks[id].emission();
+            let emission = *self.content.blocks[id].load().emission();
             if emission.iter().any(|e| *e > 0) {
-                self.add_with_emission_rgb(chunks, xyz.x, xyz.y, xyz.z, emission);
+                self.add_with_emission_rgb(chunks, xyz.x, xyz.y, xyz.z, &emission);
             }
         }
         self.solve_rgb(chunks);
@@ -113,7 +113,7 @@ impl LightSolvers {
 
 
     pub fn on_block_set(&self, chunks: &Chunks, x: i32, y: i32, z: i32, id: u32) {
-        let emission = self.content.blocks[id as usize].emission();
+        let emission = *self.content.blocks[id as usize].load().emission();
         self.remove_rgbs(chunks, x, y, z);
         self.solver_sun.solve(chunks, &self.content);
 
@@ -124,7 +124,7 @@ impl LightSolvers {
         }
 
         if emission.iter().any(|e| *e > 0) {
-            self.add_with_emission_rgb(chunks, x, y, z, emission);
+            self.add_with_emission_rgb(chunks, x, y, z, &emission);
         }
         self.solve_rgb(chunks);
     }
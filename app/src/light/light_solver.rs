@@ -175,7 +175,7 @@ impl LightSolver {
                     .get_unchecked_channel(self.channel)};
                 let id = unsafe {chunk.voxels.0.get_unchecked(index).id()};
 
-                if content.blocks[id as usize].is_light_passing() && (light+2) <= prev_light {
+                if content.blocks[id as usize].load().is_light_passing() && (light+2) <= prev_light {
                     self.add_queue.push(entry);
                     unsafe {chunk.lightmap.0.get_unchecked(index)
                         .set_unchecked_channel(entry.light, self.channel)};
@@ -47,6 +47,9 @@ macro_rules! player_unlockable {
 ///     }
 /// }
 /// ```
+///
+/// With more types the saves of older layouts are decoded as those types, newest first, and converted with `From`.
+/// Bytes of no layout keep the live voxel as it was saved instead of failing the load.
 #[macro_export]
 macro_rules! live_voxel_default_deserialize {
     ( $type:ty ) => {
@@ -55,4 +58,17 @@ macro_rules! live_voxel_default_deserialize {
                 .expect(concat!("Deserialization error on type: ", stringify!($type))))
         }
     };
+    ( $type:ty $(, $legacy:ty)+ ) => {
+        fn from_bytes(bytes: &[u8]) -> Box<dyn LiveVoxelBehavior> {
+            let decoded = $crate::save_load::decode_exact::<Self>(bytes)
+                $(.or_else(|_| $crate::save_load::decode_exact::<$legacy>(bytes).map(Self::from)))+;
+            match decoded {
+                Ok(live_voxel) => Box::new(live_voxel),
+                Err(err) => {
+                    eprintln!(concat!("Deserialization error on type: ", stringify!($type), ", {}"), err);
+                    Box::new($crate::voxels::live_voxels::missing::MissingLiveVoxel(bytes.to_vec()))
+                },
+            }
+        }
+    };
 }
\ No newline at end of file
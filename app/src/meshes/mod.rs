@@ -60,7 +60,8 @@ impl Meshes {
     
             chunk.live_voxels.0.read().unwrap().iter().sorted_by_key(|data| {data.0}).for_each(|data| {
                 let progress = data.1.live_voxel.animation_progress();
-                let block_type = &self.content.blocks[data.1.id as usize].block_type();
+                let block = self.content.blocks[data.1.id as usize].load();
+                let block_type = block.block_type();
                 if let BlockType::AnimatedModel {id} = block_type {
                     animated_models.entry(*id)
                         .and_modify(|models| models.push(progress))
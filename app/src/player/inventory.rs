@@ -1,48 +1,197 @@
-use std::ops::Range;
+use std::{collections::HashMap, ops::Range};
 
-use crate::{recipes::{recipe::{Recipe, ActiveRecipe, RecipeCrafter}, item::PossibleItem, storage::Storage, recipes::RECIPES}, bytes::{BytesCoder, cast_bytes_from_slice, AsFromBytes, cast_vec_from_bytes}};
+use crate::{recipes::{recipe::{Recipe, ActiveRecipe, RecipeCrafter}, item::{Item, PossibleItem}, research::is_recipe_unlocked, storage::Storage, recipes::RECIPES}, bytes::{BytesCoder, cast_bytes_from_slice, AsFromBytes, cast_vec_from_bytes}, content::manifest::id_maps, world::clock::Stopwatch};
 
+/// Crafts queued by one click at most, a deeper chain of intermediates is refused.
+const MAX_CRAFTS: usize = 64;
+
+
+/// A recipe crafted by the player. The intermediates crafted for it are queued before it
+/// and give their result to it instead of the inventory.
+#[derive(Debug)]
+pub struct PlayerRecipe {
+    id: u32,
+    active: ActiveRecipe,
+    /// Ingredients taken from the inventory or given by finished intermediates, refunded on cancel
+    reserved: Vec<Item>,
+    /// The craft that waits for the result
+    parent: Option<u32>,
+    /// Part of the result given to the parent, the rest goes to the inventory
+    deliver: Option<Item>,
+}
 
 #[derive(Debug)]
-pub struct ActiveRecipes(pub Vec<ActiveRecipe>);
+pub struct ActiveRecipes(pub Vec<PlayerRecipe>);
+
+impl ActiveRecipes {
+    fn get_mut(&mut self, id: u32) -> Option<&mut PlayerRecipe> {
+        self.0.iter_mut().find(|craft| craft.id == id)
+    }
+
+    /// Whether some intermediate of the craft is not finished yet.
+    fn is_waiting(&self, id: u32) -> bool {
+        self.0.iter().any(|craft| craft.parent == Some(id))
+    }
+
+    /// The craft the player clicked for, the one at the end of the chain.
+    fn root(&self, mut id: u32) -> u32 {
+        while let Some(parent) = self.0.iter().find(|craft| craft.id == id).and_then(|craft| craft.parent) {
+            id = parent;
+        }
+        id
+    }
+}
+
+/// Craft of a hand-crafting plan, `parent` is an index into the plan.
+struct PlannedRecipe<'a> {
+    recipe: &'a Recipe,
+    reserved: Vec<Item>,
+    parent: Option<usize>,
+    deliver: Option<Item>,
+}
 
 
 #[derive(Debug)]
 pub struct PlayerInventory {
     storage: [PossibleItem; 50],
-    active_recipes: ActiveRecipes
+    active_recipes: ActiveRecipes,
+    next_craft_id: u32,
 }
 
 
 impl PlayerInventory {
     pub fn new() -> Self {
-        Self { storage: [PossibleItem::new_none(); 50], active_recipes: ActiveRecipes(vec![]) }
+        Self { storage: [PossibleItem::new_none(); 50], active_recipes: ActiveRecipes(vec![]), next_craft_id: 0 }
     }
 
-    pub fn active_recipe(&self) -> &Vec<ActiveRecipe> {
-        &self.active_recipes.0
+    pub fn active_recipe(&self) -> impl Iterator<Item = &ActiveRecipe> {
+        self.active_recipes.0.iter().map(|craft| &craft.active)
     }
 
+    /// Missing ingredients are crafted first from what is in the inventory.
+    /// Every ingredient the chain needs is reserved at once, nothing is started if something is missing.
     pub fn start_recipe(&mut self, recipe: &Recipe) -> bool {
-        if !recipe.crafter.intersects(RecipeCrafter::PLAYER) {return false};
-        let Some(active_recipe) = recipe.start(self) else {return false};
-        self.active_recipes.0.push(active_recipe);
+        if !Self::is_craftable(recipe) {return false};
+        let mut available = self.counts();
+        let mut plan = vec![];
+        if !Self::plan(recipe, None, None, &mut available, &mut vec![recipe.id], &mut plan) {return false};
+
+        // The plan has every craft before its intermediates, the queue has it after them.
+        let first = self.next_craft_id;
+        self.next_craft_id += plan.len() as u32;
+        for (index, planned) in plan.into_iter().enumerate().rev() {
+            self.remove_items(&planned.reserved);
+            self.active_recipes.0.push(PlayerRecipe {
+                id: first + index as u32,
+                active: planned.recipe.start_absolute(),
+                reserved: planned.reserved,
+                parent: planned.parent.map(|parent| first + parent as u32),
+                deliver: planned.deliver,
+            });
+        }
         true
     }
 
-    pub fn update_recipe(&mut self) {
-        let self_ptr = self as *mut Self;
-        self.active_recipes.0.retain(|ar| !ar.update(unsafe {self_ptr.as_mut().unwrap()}));
+    fn is_craftable(recipe: &Recipe) -> bool {
+        recipe.crafter.intersects(RecipeCrafter::PLAYER) && is_recipe_unlocked(recipe.id)
     }
 
+    fn counts(&self) -> HashMap<u32, u32> {
+        let mut counts = HashMap::new();
+        self.storage.iter().filter_map(|possible_item| possible_item.0).for_each(|item| {
+            *counts.entry(item.id()).or_default() += item.count;
+        });
+        counts
+    }
 
-    pub fn cancel_active_recipe(&mut self, index: usize) -> bool {
-        let Some(recipe) = self.active_recipes.0.get(index).map(|ar| ar.recipe.clone()) else {return false};
-        if self.cancel_recipe(&recipe) {
-            self.active_recipes.0.remove(index);
-            return true;
+    /// Hand-crafted recipe of a missing ingredient, one with it as the main product if there is.
+    /// Recipes already in the chain are skipped, so that a cycle is never followed.
+    fn intermediate(item_id: u32, chain: &[u32]) -> Option<&'static Recipe> {
+        RECIPES().player.get_by_result(item_id)?.iter()
+            .copied()
+            .filter(|recipe| Self::is_craftable(recipe) && !chain.contains(&recipe.id))
+            .filter(|recipe| recipe.guaranteed_count(item_id) > 0)
+            .min_by_key(|recipe| recipe.result().id() != item_id)
+    }
+
+    /// Reserves the ingredients of the recipe found in `available` and plans crafts for the rest.
+    /// The surplus of an intermediate goes to the inventory, it is not used for other ingredients.
+    fn plan<'a>(
+        recipe: &'a Recipe,
+        parent: Option<usize>,
+        deliver: Option<Item>,
+        available: &mut HashMap<u32, u32>,
+        chain: &mut Vec<u32>,
+        plan: &mut Vec<PlannedRecipe<'a>>,
+    ) -> bool {
+        if plan.len() >= MAX_CRAFTS {return false};
+        let index = plan.len();
+        plan.push(PlannedRecipe { recipe, reserved: vec![], parent, deliver });
+        for ingredient in &recipe.ingredients {
+            let have = available.entry(ingredient.id()).or_default();
+            let taken = (*have).min(ingredient.count);
+            *have -= taken;
+            if taken > 0 {plan[index].reserved.push(Item::new(ingredient.id(), taken))};
+
+            let mut missing = ingredient.count - taken;
+            if missing == 0 {continue};
+            let Some(intermediate) = Self::intermediate(ingredient.id(), chain) else {return false};
+            let count = intermediate.guaranteed_count(ingredient.id());
+            chain.push(intermediate.id);
+            while missing > 0 {
+                let delivered = Item::new(ingredient.id(), count.min(missing));
+                missing -= delivered.count;
+                if !Self::plan(intermediate, Some(index), Some(delivered), available, chain, plan) {return false};
+            }
+            chain.pop();
         }
-        false
+        true
+    }
+
+    /// Finishes the crafts whose time is up.
+    /// A craft waiting for its intermediates is started once the last of them is finished.
+    pub fn update_recipe(&mut self) {
+        let mut index = 0;
+        while index < self.active_recipes.0.len() {
+            let craft = &self.active_recipes.0[index];
+            if self.active_recipes.is_waiting(craft.id) {
+                self.active_recipes.0[index].active.restart();
+                index += 1;
+                continue;
+            }
+            let results = subtract(craft.active.recipe.max_results(), craft.deliver);
+            if !craft.active.is_finished() || !self.is_spaces_exist(&results) {
+                index += 1;
+                continue;
+            }
+
+            let craft = self.active_recipes.0.remove(index);
+            let mut results = craft.active.recipe.roll_results(&mut craft.active.recipe.rng(&[craft.id as u64]));
+            let parent = craft.parent.and_then(|parent| self.active_recipes.get_mut(parent));
+            if let (Some(parent), Some(deliver)) = (parent, craft.deliver) {
+                parent.reserved.push(deliver);
+                results = subtract(results, Some(deliver));
+            }
+            self.add_items(&results);
+        }
+    }
+
+    /// Cancels the whole chain the craft belongs to and refunds everything it reserved.
+    pub fn cancel_active_recipe(&mut self, index: usize) -> bool {
+        let Some(craft) = self.active_recipes.0.get(index) else {return false};
+        let root = self.active_recipes.root(craft.id);
+        let chain: Vec<u32> = self.active_recipes.0.iter()
+            .filter(|craft| self.active_recipes.root(craft.id) == root)
+            .map(|craft| craft.id)
+            .collect();
+        let refund = merge(self.active_recipes.0.iter()
+            .filter(|craft| chain.contains(&craft.id))
+            .flat_map(|craft| craft.reserved.iter().copied()));
+        if !self.is_spaces_exist(&refund) {return false};
+
+        self.active_recipes.0.retain(|craft| !chain.contains(&craft.id));
+        self.add_items(&refund);
+        true
     }
 
 
@@ -73,8 +222,33 @@ impl PlayerInventory {
 
 impl Default for PlayerInventory {
     fn default() -> Self {
-        Self { storage: [PossibleItem::new_none(); 50], active_recipes: ActiveRecipes(vec![]) }
+        Self::new()
+    }
+}
+
+/// `items` without `item`, taken from the first stacks of its id.
+fn subtract(mut items: Vec<Item>, item: Option<Item>) -> Vec<Item> {
+    let Some(item) = item else {return items};
+    let mut count = item.count;
+    for stack in items.iter_mut().filter(|stack| stack.id() == item.id()) {
+        let taken = stack.count.min(count);
+        stack.count -= taken;
+        count -= taken;
+    }
+    items.retain(|stack| stack.count > 0);
+    items
+}
+
+/// Stacks of the same item joined, so that the space for them is counted once.
+fn merge(items: impl Iterator<Item = Item>) -> Vec<Item> {
+    let mut merged: Vec<Item> = vec![];
+    for item in items {
+        match merged.iter_mut().find(|stack| stack.id() == item.id()) {
+            Some(stack) => stack.count += item.count,
+            None => merged.push(item),
+        }
     }
+    merged
 }
 
 
@@ -89,20 +263,46 @@ impl Storage for PlayerInventory {
 }
 
 
+/// Saved after the storage for every active recipe, old saves end before it.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct CraftLink {
+    /// Index of the parent among the saved recipes, `u32::MAX` without one
+    parent: u32,
+    item: u32,
+    count: u32,
+}
+impl AsFromBytes for CraftLink {}
+
+
 impl BytesCoder for PlayerInventory {
     fn encode_bytes(&self) -> Box<[u8]> {
         let mut bytes = Vec::new();
-        let recipies: Vec<u32> = self.active_recipes.0.iter().map(|ar| ar.recipe.id).collect();
+        let crafts = &self.active_recipes.0;
+        let recipies: Vec<u32> = crafts.iter().map(|craft| id_maps().recipes.saved(craft.active.recipe.id)).collect();
         let recipies_bytes = cast_bytes_from_slice(&recipies);
         let recipies_len = recipies_bytes.len();
 
         let storage = self.storage.encode_bytes();
         let storage_len = storage.len();
 
+        let links: Vec<CraftLink> = crafts.iter().map(|craft| {
+            let parent = craft.parent.and_then(|parent| crafts.iter().position(|c| c.id == parent));
+            let deliver = craft.deliver.unwrap_or(Item::new(0, 0));
+            CraftLink {
+                parent: parent.map_or(u32::MAX, |parent| parent as u32),
+                item: id_maps().items.saved(deliver.id()),
+                count: deliver.count,
+            }
+        }).collect();
+        let times: Vec<Stopwatch> = crafts.iter().map(|craft| craft.active.start_time()).collect();
+
         bytes.extend((recipies_len as u32).as_bytes());
         bytes.extend((storage_len as u32).as_bytes());
         bytes.extend(recipies_bytes);
         bytes.extend(storage.as_ref());
+        bytes.extend(cast_bytes_from_slice(&links));
+        bytes.extend(bincode::serialize(&times).unwrap());
         bytes.into()
     }
 
@@ -112,10 +312,161 @@ impl BytesCoder for PlayerInventory {
 
         let recipies_id = cast_vec_from_bytes::<u32>(&bytes[8..recipe_end]);
         let storage = <[PossibleItem; 50]>::decode_bytes(&bytes[recipe_end..storage_end]);
-        let active_recipes = ActiveRecipes(recipies_id.iter()
-            .map(|id| RECIPES().all[*id as usize].start_absolute())
-            .collect::<Vec<ActiveRecipe>>());
+        let links_end = (storage_end + recipies_id.len() * std::mem::size_of::<CraftLink>()).min(bytes.len());
+        let links = cast_vec_from_bytes::<CraftLink>(&bytes[storage_end..links_end]);
+        // Saves from before the world clock end before the start times, their crafts start again.
+        let times: Vec<Stopwatch> = bincode::deserialize(&bytes[links_end..]).unwrap_or_default();
+
+        // Recipes of missing content get placeholder ids and are dropped, with the links to them.
+        let recipes: Vec<(u32, &Recipe)> = recipies_id.iter().enumerate()
+            .filter_map(|(index, id)| RECIPES().all.get(id_maps().recipes.runtime(*id) as usize).map(|recipe| (index as u32, recipe)))
+            .collect();
+        let mut crafts: Vec<PlayerRecipe> = recipes.iter().map(|&(id, recipe)| {
+            let link = links.get(id as usize)
+                .filter(|link| recipes.iter().any(|(index, _)| *index == link.parent));
+            PlayerRecipe {
+                id,
+                active: times.get(id as usize).map_or_else(|| recipe.start_absolute(), |&time| ActiveRecipe::new(time, recipe.clone())),
+                reserved: vec![],
+                parent: link.map(|link| link.parent),
+                deliver: link.map(|link| Item::new(id_maps().items.runtime(link.item), link.count)),
+            }
+        }).collect();
+        // Whatever the unfinished intermediates do not give was reserved.
+        for index in 0..crafts.len() {
+            let reserved = crafts.iter()
+                .filter(|craft| craft.parent == Some(crafts[index].id))
+                .fold(crafts[index].active.recipe.ingredients.clone(), |items, craft| subtract(items, craft.deliver));
+            crafts[index].reserved = reserved;
+        }
+
+        Self { storage, active_recipes: ActiveRecipes(crafts), next_craft_id: recipies_id.len() as u32 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{content_loader::test_content, recipes::items::ITEMS, world::clock};
+
+    use super::*;
+
+    fn id(item: &str) -> u32 {
+        ITEMS().id(item).unwrap()
+    }
 
-        Self { storage, active_recipes }
+    fn recipe(name: &str) -> &'static Recipe {
+        RECIPES().all.iter().find(|recipe| recipe.name == name).unwrap()
     }
-}
\ No newline at end of file
+
+    fn inventory(items: &[(&str, u32)]) -> PlayerInventory {
+        test_content();
+        let mut inventory = PlayerInventory::new();
+        inventory.add_items(&items.iter().map(|&(item, count)| Item::new(id(item), count)).collect::<Vec<_>>());
+        inventory
+    }
+
+    fn count(inventory: &PlayerInventory, item: &str) -> u32 {
+        inventory.counts().get(&id(item)).copied().unwrap_or_default()
+    }
+
+    /// Recipe names of the queue with the recipe name of their parent.
+    fn queue(inventory: &PlayerInventory) -> Vec<(String, Option<String>, Option<Item>)> {
+        let crafts = &inventory.active_recipes.0;
+        crafts.iter().map(|craft| {
+            let parent = craft.parent.and_then(|parent| crafts.iter().find(|c| c.id == parent));
+            (craft.active.recipe.name.clone(), parent.map(|parent| parent.active.recipe.name.clone()), craft.deliver)
+        }).collect()
+    }
+
+    #[test]
+    fn intermediates_are_crafted_recursively() {
+        let mut inventory = inventory(&[("iron_ingot", 4)]);
+        assert!(inventory.start_recipe(recipe("science_pack")));
+        assert_eq!(count(&inventory, "iron_ingot"), 0);
+
+        let plate = Some(Item::new(id("iron_plate"), 1));
+        let science_pack = Some("science_pack".to_string());
+        assert_eq!(queue(&inventory), [
+            ("iron_plate".to_string(), Some("glass_green".to_string()), plate),
+            ("glass_green".to_string(), science_pack.clone(), Some(Item::new(id("glass_green"), 1))),
+            ("iron_plate".to_string(), science_pack, plate),
+            ("science_pack".to_string(), None, None),
+        ]);
+    }
+
+    #[test]
+    fn nothing_is_started_when_an_ingredient_is_missing() {
+        let mut inventory = inventory(&[("iron_ingot", 3)]);
+        assert!(!inventory.start_recipe(recipe("science_pack")));
+        assert_eq!(count(&inventory, "iron_ingot"), 3);
+        assert!(inventory.active_recipes.0.is_empty());
+    }
+
+    #[test]
+    fn ingredients_in_the_inventory_are_reserved_first() {
+        let mut inventory = inventory(&[("iron_plate", 1), ("iron_ingot", 2)]);
+        assert!(inventory.start_recipe(recipe("science_pack")));
+        assert_eq!((count(&inventory, "iron_plate"), count(&inventory, "iron_ingot")), (0, 0));
+        let names: Vec<String> = queue(&inventory).into_iter().map(|(name, ..)| name).collect();
+        assert_eq!(names, ["iron_plate", "glass_green", "science_pack"]);
+        assert_eq!(inventory.active_recipes.0[2].reserved, [Item::new(id("iron_plate"), 1)]);
+    }
+
+    #[test]
+    fn long_chains_are_refused() {
+        let mut inventory = inventory(&[("iron_ingot", 128)]);
+        let mut plates = recipe("tank").clone();
+        plates.ingredients = vec![Item::new(id("iron_plate"), MAX_CRAFTS as u32 - 1)];
+        assert!(inventory.start_recipe(&plates));
+        assert_eq!(inventory.active_recipes.0.len(), MAX_CRAFTS);
+
+        plates.ingredients = vec![Item::new(id("iron_plate"), MAX_CRAFTS as u32)];
+        assert!(!inventory.start_recipe(&plates));
+        assert_eq!(inventory.active_recipes.0.len(), MAX_CRAFTS);
+    }
+
+    #[test]
+    fn cancel_refunds_the_whole_chain() {
+        let mut inventory = inventory(&[("iron_ingot", 4)]);
+        assert!(inventory.start_recipe(recipe("science_pack")));
+        assert!(inventory.cancel_active_recipe(0));
+        assert!(inventory.active_recipes.0.is_empty());
+        assert_eq!(count(&inventory, "iron_ingot"), 4);
+
+        // The plates are finished, the glass is not.
+        assert!(inventory.start_recipe(recipe("science_pack")));
+        (0..7).for_each(|_| clock::advance());
+        inventory.update_recipe();
+        let names: Vec<String> = queue(&inventory).into_iter().map(|(name, ..)| name).collect();
+        assert_eq!(names, ["glass_green", "science_pack"]);
+        assert!(inventory.cancel_active_recipe(1));
+        assert!(inventory.active_recipes.0.is_empty());
+        assert_eq!((count(&inventory, "iron_plate"), count(&inventory, "iron_ingot")), (2, 0));
+    }
+
+    #[test]
+    fn chain_links_are_saved() {
+        let mut inventory = inventory(&[("iron_ingot", 4)]);
+        assert!(inventory.start_recipe(recipe("science_pack")));
+        let mut loaded = PlayerInventory::decode_bytes(&inventory.encode_bytes());
+        assert_eq!(queue(&loaded), queue(&inventory));
+        let reserved = |inventory: &PlayerInventory| inventory.active_recipes.0.iter().map(|craft| craft.reserved.clone()).collect::<Vec<_>>();
+        assert_eq!(reserved(&loaded), reserved(&inventory));
+
+        assert!(loaded.cancel_active_recipe(0));
+        assert_eq!(count(&loaded, "iron_ingot"), 4);
+    }
+
+    #[test]
+    fn inventory_saved_before_links_is_loaded() {
+        let mut inventory = inventory(&[("iron_ingot", 4), ("rock", 3)]);
+        assert!(inventory.start_recipe(recipe("science_pack")));
+        let bytes = inventory.encode_bytes();
+        let storage_end = 8 + u32::from_bytes(&bytes[0..4]) as usize + u32::from_bytes(&bytes[4..8]) as usize;
+
+        let loaded = PlayerInventory::decode_bytes(&bytes[..storage_end]);
+        assert_eq!(count(&loaded, "rock"), 3);
+        assert_eq!(loaded.active_recipes.0.len(), 4);
+        assert!(loaded.active_recipes.0.iter().all(|craft| craft.parent.is_none() && craft.reserved == craft.active.recipe.ingredients));
+    }
+}
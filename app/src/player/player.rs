@@ -40,7 +40,8 @@ impl Player {
             .lock().unwrap()
             .storage()[self.active_slot].0
             .map(|item| item.id()) else {return};
-        ITEMS()[item_id as usize].on_right_click(world, self, xyz, dir, content);
+        let Some(item_type) = ITEMS().get(item_id) else {return};
+        item_type.on_right_click(world, self, xyz, dir, content);
     }
 
     pub fn set_open_storage(&mut self, storage: Weak<Mutex<dyn PlayerUnlockable>>) {
@@ -0,0 +1,132 @@
+use std::{ffi::{c_char, CStr}, fmt::Display};
+
+use crate::Registrator;
+
+/// Bump it whenever the layout of [`PluginDescriptor`], [`Registrator`] or the plugin entry points change.
+pub const PLUGIN_API_VERSION: u32 = 3;
+
+/// Identifies the exact build of `app` a plugin was compiled against.
+/// `Registrator` holds Rust types, so the plugin must match it exactly.
+pub const PLUGIN_BUILD_ID: &str = concat!(
+    env!("CARGO_PKG_VERSION"), " ", env!("MANUFACTORY_RUSTC_VERSION"), "\0");
+
+/// Name of the exported `u32` static holding the [`PLUGIN_API_VERSION`] of the plugin.
+pub const API_VERSION_SYMBOL: &[u8] = b"MANUFACTORY_PLUGIN_API_VERSION\0";
+/// Name of the exported `extern "C" fn() -> *const PluginDescriptor`.
+pub const DESCRIPTOR_SYMBOL: &[u8] = b"manufactory_plugin_descriptor\0";
+
+/// Its address tells apart the copies of `app`, a plugin must use the one of the game.
+#[doc(hidden)]
+pub static APP_INSTANCE: u8 = 0;
+
+pub type PluginInit = unsafe extern "C" fn(registrator: *mut Registrator);
+pub type PluginDescriptorFn = unsafe extern "C" fn() -> *const PluginDescriptor;
+
+#[repr(C)]
+pub struct PluginDescriptor {
+    pub api_version: u32,
+    /// Null terminated [`PLUGIN_BUILD_ID`] of the plugin
+    pub build_id: *const c_char,
+    /// Null terminated plugin name
+    pub name: *const c_char,
+    /// Null terminated plugin version
+    pub version: *const c_char,
+    /// [`APP_INSTANCE`] of the `app` the plugin is linked to
+    pub app: *const u8,
+    /// Called only after the api version and the build id were checked
+    pub init: PluginInit,
+}
+
+// Descriptors only point to static null terminated strings.
+unsafe impl Sync for PluginDescriptor {}
+
+impl PluginDescriptor {
+    /// # Safety
+    /// The descriptor strings must be valid null terminated strings.
+    pub unsafe fn build_id(&self) -> &str {CStr::from_ptr(self.build_id).to_str().unwrap_or("")}
+    /// # Safety
+    /// The descriptor strings must be valid null terminated strings.
+    pub unsafe fn name(&self) -> &str {CStr::from_ptr(self.name).to_str().unwrap_or("")}
+    /// # Safety
+    /// The descriptor strings must be valid null terminated strings.
+    pub unsafe fn version(&self) -> &str {CStr::from_ptr(self.version).to_str().unwrap_or("")}
+
+    /// Checks that the plugin can safely be initialized by this build.
+    ///
+    /// # Safety
+    /// The descriptor strings must be valid null terminated strings.
+    pub unsafe fn check(&self) -> Result<(), PluginError> {
+        if self.api_version != PLUGIN_API_VERSION {
+            return Err(PluginError::ApiVersion { expected: PLUGIN_API_VERSION, found: self.api_version });
+        }
+        let expected = PLUGIN_BUILD_ID.trim_end_matches('\0');
+        if self.build_id() != expected {
+            return Err(PluginError::Build { expected: expected.to_string(), found: self.build_id().to_string() });
+        }
+        if !std::ptr::eq(self.app, &APP_INSTANCE) {return Err(PluginError::SeparateApp)};
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum PluginError {
+    /// The library does not export the plugin entry points
+    NotAPlugin,
+    ApiVersion { expected: u32, found: u32 },
+    /// The plugin was built against another version of the game or by another compiler
+    Build { expected: String, found: String },
+    /// The plugin or the game was built without `-C prefer-dynamic`, so they don't share the globals
+    SeparateApp,
+}
+
+impl Display for PluginError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PluginError::NotAPlugin => write!(f, "the library is not a manufactory plugin"),
+            PluginError::ApiVersion { expected, found } =>
+                write!(f, "plugin api version {} is not supported, expected {}", found, expected),
+            PluginError::Build { expected, found } =>
+                write!(f, "plugin was built for \"{}\", expected \"{}\"", found, expected),
+            PluginError::SeparateApp =>
+                write!(f, "plugin links its own copy of the game library, build the game and the plugin with testmod/install.sh"),
+        }
+    }
+}
+
+impl std::error::Error for PluginError {}
+
+/// Exports the plugin entry points.
+///
+/// The mod has to be a `dylib` built with `-C prefer-dynamic` like the game, see `testmod/install.sh`,
+/// so it uses the `app` library of the game instead of its own copy. The registries like `ITEMS()` are filled after the `init` of every mod,
+/// only the callbacks registered there may use them.
+/// ```rust, ignore
+/// declare_plugin!("trashcan", "0.0.1", init);
+///
+/// fn init(registrator: &mut Registrator) {}
+/// ```
+#[macro_export]
+macro_rules! declare_plugin {
+    ( $name:expr, $version:expr, $init:path ) => {
+        #[no_mangle]
+        pub static MANUFACTORY_PLUGIN_API_VERSION: u32 = $crate::plugin::PLUGIN_API_VERSION;
+
+        #[no_mangle]
+        pub extern "C" fn manufactory_plugin_descriptor() -> *const $crate::plugin::PluginDescriptor {
+            // Named so that it can't shadow the user's `$init`.
+            unsafe extern "C" fn __manufactory_plugin_init(registrator: *mut $crate::Registrator) {
+                $init(&mut *registrator)
+            }
+
+            static DESCRIPTOR: $crate::plugin::PluginDescriptor = $crate::plugin::PluginDescriptor {
+                api_version: $crate::plugin::PLUGIN_API_VERSION,
+                build_id: $crate::plugin::PLUGIN_BUILD_ID.as_ptr() as *const ::std::ffi::c_char,
+                name: concat!($name, "\0").as_ptr() as *const ::std::ffi::c_char,
+                version: concat!($version, "\0").as_ptr() as *const ::std::ffi::c_char,
+                app: &$crate::plugin::APP_INSTANCE,
+                init: __manufactory_plugin_init,
+            };
+            &DESCRIPTOR
+        }
+    };
+}
\ No newline at end of file
@@ -0,0 +1,311 @@
+use std::{collections::{HashMap, HashSet}, time::Duration};
+
+use crate::coords::{chunk_coord::ChunkCoord, global_coord::GlobalCoord};
+
+/// Poles this close to each other are connected with a wire, in blocks
+pub const WIRE_REACH: i32 = 8;
+/// Machines with a block this close to a pole on every axis are connected to its network
+pub const SUPPLY_RADIUS: i32 = 3;
+
+/// Power balance of one network over the last tick. Power is in watts.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NetworkStats {
+    /// What the generators could give
+    pub supply: f32,
+    /// What the machines asked for
+    pub demand: f32,
+    /// Part of the demand that is met, the machines work this much slower
+    pub satisfaction: f32,
+    /// Part of the supply that is used, the generators burn fuel this much slower
+    pub load: f32,
+}
+
+impl NetworkStats {
+    pub fn new(supply: f32, demand: f32) -> Self {
+        let satisfaction = if demand > 0.0 {(supply / demand).min(1.0)} else if supply > 0.0 {1.0} else {0.0};
+        let load = if supply > 0.0 {(demand / supply).min(1.0)} else {0.0};
+        Self { supply, demand, satisfaction, load }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Network {
+    poles: HashSet<GlobalCoord>,
+    /// Reported since the last tick
+    supply: f32,
+    demand: f32,
+    stats: NetworkStats,
+}
+
+/// Poles joined into networks and the power balance of every network.
+///
+/// Nothing here knows about loaded chunks, so it can be simulated on its own. Poles add themselves on their
+/// first update and are removed when their block is replaced or their chunk is unloaded, machines report
+/// their supply and demand every tick and get the balance of the previous tick back.
+#[derive(Debug, Default)]
+pub struct PowerGrid {
+    networks: HashMap<u32, Network>,
+    poles: HashMap<GlobalCoord, u32>,
+    /// Network of every machine asked about, by its first block. Forgotten when the poles change.
+    connections: HashMap<GlobalCoord, Option<u32>>,
+    next_id: u32,
+    /// Machines without a network work at full speed
+    optional: bool,
+}
+
+impl PowerGrid {
+    pub fn new() -> Self {Self::default()}
+
+    /// Grid of a world from before power, its machines kept working without poles.
+    pub fn optional() -> Self {Self { optional: true, ..Self::default() }}
+
+    pub fn has_pole(&self, coord: GlobalCoord) -> bool {
+        self.poles.contains_key(&coord)
+    }
+
+    /// Network of the pole.
+    pub fn pole_network(&self, coord: GlobalCoord) -> Option<u32> {
+        self.poles.get(&coord).copied()
+    }
+
+    pub fn networks(&self) -> impl Iterator<Item = (u32, NetworkStats)> + '_ {
+        self.networks.iter().map(|(id, network)| (*id, network.stats))
+    }
+
+    pub fn stats(&self, network: u32) -> Option<NetworkStats> {
+        self.networks.get(&network).map(|network| network.stats)
+    }
+
+    fn new_network(&mut self) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.networks.insert(id, Network::default());
+        id
+    }
+
+    /// Wires the pole to every pole in reach, the networks it touches are merged into one.
+    pub fn add_pole(&mut self, coord: GlobalCoord) {
+        if self.has_pole(coord) {return};
+        let mut ids: Vec<u32> = self.poles.iter()
+            .filter(|(pole, _)| is_wired(coord, **pole))
+            .map(|(_, id)| *id)
+            .collect();
+        ids.sort();
+        ids.dedup();
+
+        let id = match ids.first() {
+            Some(id) => *id,
+            None => self.new_network(),
+        };
+        for other in ids.iter().skip(1) {
+            let Some(merged) = self.networks.remove(other) else {continue};
+            merged.poles.iter().for_each(|pole| {self.poles.insert(*pole, id);});
+            let network = self.networks.get_mut(&id).unwrap();
+            network.poles.extend(merged.poles);
+            network.supply += merged.supply;
+            network.demand += merged.demand;
+        }
+        self.networks.get_mut(&id).unwrap().poles.insert(coord);
+        self.poles.insert(coord, id);
+        self.connections.clear();
+    }
+
+    /// Unwires the pole, its network is split if the pole was the only link between its parts.
+    /// The largest part keeps the network id.
+    pub fn remove_pole(&mut self, coord: GlobalCoord) -> bool {
+        let Some(id) = self.poles.remove(&coord) else {return false};
+        let network = self.networks.remove(&id).unwrap();
+        self.connections.clear();
+
+        let mut rest = network.poles;
+        rest.remove(&coord);
+        let mut parts: Vec<HashSet<GlobalCoord>> = vec![];
+        while let Some(&start) = rest.iter().next() {
+            rest.remove(&start);
+            let mut part = HashSet::from([start]);
+            let mut stack = vec![start];
+            while let Some(pole) = stack.pop() {
+                let wired: Vec<GlobalCoord> = rest.iter().filter(|other| is_wired(pole, **other)).copied().collect();
+                for other in wired {
+                    rest.remove(&other);
+                    part.insert(other);
+                    stack.push(other);
+                }
+            }
+            parts.push(part);
+        }
+
+        parts.sort_by_key(|part| std::cmp::Reverse(part.len()));
+        for (index, poles) in parts.into_iter().enumerate() {
+            let part_id = if index == 0 {
+                self.networks.insert(id, Network::default());
+                id
+            } else {
+                self.new_network()
+            };
+            poles.iter().for_each(|pole| {self.poles.insert(*pole, part_id);});
+            let part = self.networks.get_mut(&part_id).unwrap();
+            part.poles = poles;
+            part.stats = network.stats;
+        }
+        true
+    }
+
+    /// Forgets the poles of an unloaded chunk, they add themselves again when it is loaded.
+    pub fn remove_chunk(&mut self, chunk: ChunkCoord) {
+        let poles: Vec<GlobalCoord> = self.poles.keys().filter(|pole| ChunkCoord::from(**pole) == chunk).copied().collect();
+        poles.into_iter().for_each(|pole| {self.remove_pole(pole);});
+    }
+
+    /// Network of a machine made of the blocks, `None` if no pole supplies any of them.
+    /// A machine between several networks takes power from the oldest one.
+    pub fn network(&mut self, blocks: &[GlobalCoord]) -> Option<u32> {
+        let key = *blocks.first()?;
+        if let Some(id) = self.connections.get(&key) {return *id};
+        let id = self.poles.iter()
+            .filter(|(pole, _)| blocks.iter().any(|block| is_supplied(*block, **pole)))
+            .map(|(_, id)| *id)
+            .min();
+        self.connections.insert(key, id);
+        id
+    }
+
+    /// Asks for `power` this tick. Returns the satisfaction of the last tick,
+    /// without a network 0, or 1 if power is [optional](PowerGrid::optional).
+    pub fn demand(&mut self, blocks: &[GlobalCoord], power: f32) -> f32 {
+        let Some(id) = self.network(blocks) else {return if self.optional {1.0} else {0.0}};
+        let network = self.networks.get_mut(&id).unwrap();
+        network.demand += power;
+        network.stats.satisfaction
+    }
+
+    /// Offers `power` this tick. Returns the load of the last tick, 0 without a network.
+    pub fn supply(&mut self, blocks: &[GlobalCoord], power: f32) -> f32 {
+        let Some(id) = self.network(blocks) else {return 0.0};
+        let network = self.networks.get_mut(&id).unwrap();
+        network.supply += power;
+        network.stats.load
+    }
+
+    /// Balances every network with what was reported since the last tick.
+    pub fn tick(&mut self) {
+        self.networks.values_mut().for_each(|network| {
+            network.stats = NetworkStats::new(network.supply, network.demand);
+            network.supply = 0.0;
+            network.demand = 0.0;
+        });
+    }
+}
+
+fn is_wired(a: GlobalCoord, b: GlobalCoord) -> bool {
+    let (x, y, z) = (a.x - b.x, a.y - b.y, a.z - b.z);
+    x*x + y*y + z*z <= WIRE_REACH*WIRE_REACH
+}
+
+fn is_supplied(block: GlobalCoord, pole: GlobalCoord) -> bool {
+    (block.x - pole.x).abs() <= SUPPLY_RADIUS &&
+    (block.y - pole.y).abs() <= SUPPLY_RADIUS &&
+    (block.z - pole.z).abs() <= SUPPLY_RADIUS
+}
+
+/// Blocks of a live voxel, the multiblock coordinates are empty for a single block.
+pub fn footprint<'a>(coord: &'a GlobalCoord, multiblock: &'a [GlobalCoord]) -> &'a [GlobalCoord] {
+    if multiblock.is_empty() {std::slice::from_ref(coord)} else {multiblock}
+}
+
+/// Part of `elapsed` a machine did not work for, it is added to its timers.
+pub fn unpowered(elapsed: Duration, satisfaction: f32) -> Duration {
+    elapsed.mul_f32(1.0 - satisfaction.clamp(0.0, 1.0))
+}
+
+/// Watts as shown to the player.
+pub fn power_text(watts: f32) -> String {
+    if watts >= 1_000_000.0 {return format!("{:.1} MW", watts / 1_000_000.0)};
+    format!("{:.1} kW", watts / 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::voxels::chunk::CHUNK_SIZE;
+    use super::*;
+
+    fn at(x: i32) -> GlobalCoord {GlobalCoord::new(x, 0, 0)}
+
+    #[test]
+    fn poles_in_reach_share_a_network() {
+        let mut grid = PowerGrid::new();
+        grid.add_pole(at(0));
+        grid.add_pole(at(WIRE_REACH));
+        grid.add_pole(at(3 * WIRE_REACH));
+        assert_eq!(grid.pole_network(at(0)), grid.pole_network(at(WIRE_REACH)));
+        assert_ne!(grid.pole_network(at(0)), grid.pole_network(at(3 * WIRE_REACH)));
+        assert_eq!(grid.networks().count(), 2);
+    }
+
+    #[test]
+    fn bridge_pole_merges_and_splits_networks() {
+        let mut grid = PowerGrid::new();
+        for x in [-4, 0, 16] {grid.add_pole(at(x))};
+        assert_eq!(grid.networks().count(), 2);
+
+        grid.add_pole(at(8));
+        assert_eq!(grid.networks().count(), 1);
+        let merged = grid.pole_network(at(8)).unwrap();
+        assert!([-4, 0, 16].iter().all(|x| grid.pole_network(at(*x)) == Some(merged)));
+
+        assert!(grid.remove_pole(at(8)));
+        assert!(!grid.remove_pole(at(8)));
+        assert_eq!(grid.networks().count(), 2);
+        // The larger part keeps the id.
+        assert_eq!(grid.pole_network(at(-4)), Some(merged));
+        assert_eq!(grid.pole_network(at(0)), Some(merged));
+        assert_ne!(grid.pole_network(at(16)), Some(merged));
+    }
+
+    #[test]
+    fn undersupply_slows_the_machines() {
+        let mut grid = PowerGrid::new();
+        grid.add_pole(at(0));
+        assert_eq!(grid.demand(&[at(1)], 100.0), 0.0);
+        assert_eq!(grid.supply(&[at(2)], 50.0), 0.0);
+        grid.tick();
+        assert_eq!(grid.demand(&[at(1)], 100.0), 0.5);
+        assert_eq!(grid.supply(&[at(2)], 50.0), 1.0);
+        let network = grid.pole_network(at(0)).unwrap();
+        assert_eq!(grid.stats(network), Some(NetworkStats::new(50.0, 100.0)));
+    }
+
+    #[test]
+    fn oversupply_lowers_the_generator_load() {
+        let mut grid = PowerGrid::new();
+        grid.add_pole(at(0));
+        grid.demand(&[at(1)], 100.0);
+        grid.supply(&[at(-1), at(-2)], 400.0);
+        grid.tick();
+        assert_eq!(grid.demand(&[at(1)], 100.0), 1.0);
+        assert_eq!(grid.supply(&[at(-1), at(-2)], 400.0), 0.25);
+    }
+
+    #[test]
+    fn machines_without_a_network() {
+        let mut grid = PowerGrid::new();
+        grid.add_pole(at(0));
+        let far = [at(SUPPLY_RADIUS + 1)];
+        assert_eq!(grid.network(&far), None);
+        assert_eq!(grid.demand(&far, 100.0), 0.0);
+        assert_eq!(grid.supply(&far, 100.0), 0.0);
+        assert_eq!(PowerGrid::optional().demand(&far, 100.0), 1.0);
+    }
+
+    #[test]
+    fn unloaded_chunk_drops_its_poles() {
+        let mut grid = PowerGrid::new();
+        let far = at(4 * CHUNK_SIZE as i32);
+        grid.add_pole(at(0));
+        grid.add_pole(far);
+        grid.remove_chunk(far.into());
+        assert!(grid.has_pole(at(0)));
+        assert!(!grid.has_pole(far));
+        assert_eq!(grid.networks().count(), 1);
+    }
+}
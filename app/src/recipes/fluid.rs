@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+
+/// Boxes with less fluid than this are emptied, so that rounding errors don't keep the fluid in them
+const EMPTY: f32 = 0.01;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Fluid {
+    #[serde(with = "crate::content::manifest::fluid_id")]
+    id: u32,
+    pub amount: f32,
+}
+
+impl Fluid {
+    pub fn new(id: u32, amount: f32) -> Self {Self { id, amount }}
+
+    pub fn id(&self) -> u32 {self.id}
+}
+
+/// Which way the fluid of a box may move to its neighbours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Flow {
+    /// Only filled from the outside, like the ingredients of a machine
+    Input,
+    /// Only emptied to the outside, like the results of a machine
+    Output,
+    /// Levelled with the neighbours, like pipes and tanks
+    Both,
+}
+
+/// One fluid at a time, up to the capacity.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FluidBox {
+    pub fluid: Option<Fluid>,
+    pub capacity: f32,
+    pub flow: Flow,
+    /// Only this fluid is accepted when set, machines set it from their recipe
+    #[serde(skip)]
+    pub filter: Option<u32>,
+}
+
+impl FluidBox {
+    pub fn new(capacity: f32, flow: Flow) -> Self {
+        Self { fluid: None, capacity, flow, filter: None }
+    }
+
+    pub fn fluid_id(&self) -> Option<u32> {
+        self.fluid.map(|fluid| fluid.id)
+    }
+
+    pub fn amount(&self) -> f32 {
+        self.fluid.map_or(0.0, |fluid| fluid.amount)
+    }
+
+    /// Filled part of the capacity, between 0 and 1
+    pub fn level(&self) -> f32 {
+        self.amount() / self.capacity
+    }
+
+    pub fn contains(&self, fluid_id: u32) -> f32 {
+        self.fluid.filter(|fluid| fluid.id == fluid_id).map_or(0.0, |fluid| fluid.amount)
+    }
+
+    pub fn accepts(&self, fluid_id: u32) -> bool {
+        self.filter.is_none_or(|filter| filter == fluid_id) && self.fluid_id().is_none_or(|id| id == fluid_id)
+    }
+
+    /// Room left for the fluid, 0 if the box holds another one.
+    pub fn space(&self, fluid_id: u32) -> f32 {
+        if !self.accepts(fluid_id) {return 0.0};
+        (self.capacity - self.amount()).max(0.0)
+    }
+
+    /// Adds as much of the fluid as fits, returns the added amount.
+    pub fn fill(&mut self, fluid: &Fluid) -> f32 {
+        let amount = fluid.amount.min(self.space(fluid.id));
+        if amount <= 0.0 {return 0.0};
+        self.fluid = Some(Fluid::new(fluid.id, self.amount() + amount));
+        amount
+    }
+
+    /// Takes up to the amount of the fluid, returns the taken amount.
+    pub fn drain(&mut self, fluid: &Fluid) -> f32 {
+        let amount = fluid.amount.min(self.contains(fluid.id));
+        if amount <= 0.0 {return 0.0};
+        let left = self.amount() - amount;
+        self.fluid = (left >= EMPTY).then_some(Fluid::new(fluid.id, left));
+        amount
+    }
+
+    pub fn clear(&mut self) -> Option<Fluid> {
+        self.fluid.take()
+    }
+}
\ No newline at end of file
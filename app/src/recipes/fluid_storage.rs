@@ -0,0 +1,12 @@
+use super::fluid::FluidBox;
+
+/// Live voxels that hold fluids. The fluids move between the boxes of neighbouring voxels,
+/// see [`crate::voxels::live_voxels::fluid_flow`].
+pub trait FluidStorage {
+    fn fluid_boxes(&self) -> &[FluidBox];
+    fn mut_fluid_boxes(&mut self) -> &mut [FluidBox];
+
+    /// Whether the neighbour on the side can exchange fluids with this voxel.
+    /// `side` points from this voxel to the neighbour.
+    fn connects(&self, _side: [i32; 3]) -> bool {true}
+}
\ No newline at end of file
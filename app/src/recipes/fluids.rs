@@ -0,0 +1,86 @@
+use std::{collections::HashMap, path::{Path, PathBuf}, sync::OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::content_loader::{default_content, error::{ContentError, ContentErrors}, indices::{sort_unique, GamePath}};
+
+fn white() -> [u8; 3] {[255, 255, 255]}
+
+/// `fluids/<name>.json`, the fluid is named by the file.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FluidFile {
+    /// Color of the fluid bars
+    #[serde(default = "white")]
+    pub color: [u8; 3],
+}
+
+#[derive(Debug)]
+pub struct FluidType {
+    pub id: u32,
+    pub name: String,
+    pub color: [u8; 3],
+}
+
+#[derive(Debug, Default)]
+pub struct Fluids {
+    pub all: Vec<FluidType>,
+    pub indices: HashMap<String, u32>,
+}
+
+impl Fluids {
+    /// `files` are `(name, path, file)`.
+    pub fn new(files: Vec<(String, PathBuf, FluidFile)>, errors: &mut ContentErrors) -> Result<Self, ContentError> {
+        let files = sort_unique(files, "fluid", |(name, path, _)| (name.as_str(), path.as_path()), errors)?;
+        let indices = files.iter().enumerate().map(|(id, (name, ..))| (name.clone(), id as u32)).collect();
+        let all = files.into_iter().enumerate()
+            .map(|(id, (name, _, file))| FluidType { id: id as u32, name, color: file.color })
+            .collect();
+        Ok(Self { all, indices })
+    }
+
+    pub fn get(&self, id: u32) -> Option<&FluidType> {
+        self.all.get(id as usize)
+    }
+
+    pub fn id(&self, name: &str) -> Option<u32> {
+        self.indices.get(name).copied()
+    }
+
+    pub fn color(&self, id: u32) -> [u8; 3] {
+        self.get(id).map_or(white(), |fluid| fluid.color)
+    }
+}
+
+pub fn load_fluid_file(path: &Path) -> Result<FluidFile, ContentError> {
+    let data = std::fs::read(path).map_err(|err| ContentError::io(path, err))?;
+    serde_json::from_slice(&data).map_err(|err| ContentError::json(path, err))
+}
+
+pub fn load_fluids(content: &[GamePath<PathBuf>], errors: &mut ContentErrors) -> Result<Fluids, ContentError> {
+    let mut files = Vec::new();
+    for fluids in content.iter().map(|c| c.join("fluids/")) {
+        for file in fluids.files() {
+            let Some((name, path)) = errors.check(file)? else {continue};
+            let Some(fluid) = errors.check(load_fluid_file(&path))? else {continue};
+            files.push((name, path, fluid));
+        }
+    }
+    Fluids::new(files, errors)
+}
+
+static FLUIDS_CONTAINER: OnceLock<Fluids> = OnceLock::new();
+
+/// Loads the fluids of the base game and of the active content packs.
+/// Must be called before the recipes are loaded.
+pub fn init_fluids(content: &[GamePath<PathBuf>], errors: &mut ContentErrors) -> Result<(), ContentError> {
+    FLUIDS_CONTAINER.set(load_fluids(content, errors)?).expect("Fluids are already initialized");
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+pub fn FLUIDS() -> &'static Fluids {
+    FLUIDS_CONTAINER.get_or_init(|| load_fluids(&default_content(), &mut ContentErrors::fail_fast()).unwrap_or_else(|err| {
+        eprintln!("Failed to load fluids: {}", err);
+        Fluids::default()
+    }))
+}
\ No newline at end of file
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-const STACK_SIZE: u32 = 100;
+use super::items::ITEMS;
+
+pub const STACK_SIZE: u32 = 100;
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct PossibleItem(pub Option<Item>);
@@ -18,8 +20,9 @@ impl PossibleItem {
         if let Some(item_src) = &mut self.0 {
             item_src.try_add(item)
         } else {
-            self.0 = Some(Item::new(item.id, std::cmp::min(item.count, STACK_SIZE)));
-            if item.count > STACK_SIZE {return Some(Item::new(item.id, item.count - STACK_SIZE))};
+            let stack_size = item.stack_size();
+            self.0 = Some(Item::new(item.id, std::cmp::min(item.count, stack_size)));
+            if item.count > stack_size {return Some(Item::new(item.id, item.count - stack_size))};
             None
         }
     }
@@ -45,11 +48,11 @@ impl PossibleItem {
     pub fn available_space(&self, item_id: u32) -> u32 {
         self.0.as_ref()
             .map(|item| item.available_space(item_id))
-            .unwrap_or(STACK_SIZE)
+            .unwrap_or(ITEMS().stack_size(item_id))
     }
 
-    pub fn free_space(&self, _item_id: u32) -> u32 {
-        self.0.as_ref().map_or(STACK_SIZE, |_| 0)
+    pub fn free_space(&self, item_id: u32) -> u32 {
+        self.0.as_ref().map_or(ITEMS().stack_size(item_id), |_| 0)
     }
 
     pub fn residual_space(&self, item_id: u32) -> u32 {
@@ -81,8 +84,9 @@ impl PossibleItem {
 }
 
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Item {
+    #[serde(with = "crate::content::manifest::item_id")]
     id: u32,
     pub count: u32,
 }
@@ -98,7 +102,7 @@ impl Item {
     pub fn try_add(&mut self, item: &Self) -> Option<Item> {
         if self.id != item.id {return Some(Item::from(item))};
         let sum = self.count + item.count;
-        self.count = std::cmp::min(sum, STACK_SIZE);
+        self.count = std::cmp::min(sum, self.stack_size());
         if sum > self.count {return Some(Item::new(self.id, sum - self.count))}
         None
     }
@@ -115,8 +119,7 @@ impl Item {
 
     pub fn available_space(&self, item_id: u32) -> u32 {
         if self.id != item_id {return 0};
-        if self.count < STACK_SIZE {return STACK_SIZE - self.count};
-        0
+        self.stack_size().saturating_sub(self.count)
     }
 
     pub fn contains(&self, item_id: u32) -> u32 {
@@ -139,6 +142,10 @@ impl Item {
     }
 
     pub fn stack_size(&self) -> u32 {
-        STACK_SIZE
+        ITEMS().stack_size(self.id)
+    }
+
+    pub fn icon(&self) -> u32 {
+        ITEMS().icon(self.id)
     }
 }
\ No newline at end of file
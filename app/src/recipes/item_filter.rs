@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{content::tags::Tag, content_loader::indices::resolve};
+
+use super::items::ITEMS;
+
+/// Accepts one item or, written as `#tag`, every item with the tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ItemFilter {
+    Item(u32),
+    Tag(Tag),
+}
+
+impl ItemFilter {
+    /// Returns `None` for unknown items, unknown tags just match nothing.
+    /// Items are looked up in the pack of `prefix` first.
+    pub fn parse(prefix: &str, name: &str) -> Option<Self> {
+        match name.strip_prefix('#') {
+            Some(tag) => Some(Self::Tag(Tag::new(tag))),
+            None => resolve(&ITEMS().indices, prefix, name).copied().map(Self::Item),
+        }
+    }
+
+    #[inline]
+    pub fn matches(&self, id: u32) -> bool {
+        match self {
+            Self::Item(item) => *item == id,
+            Self::Tag(tag) => ITEMS().has_tag(id, *tag),
+        }
+    }
+}
\ No newline at end of file
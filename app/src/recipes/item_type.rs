@@ -1,21 +1,38 @@
+use std::time::Duration;
+
+use crate::content::tags::TagSet;
+
 use super::item_interaction::ItemInteraction;
 
+#[derive(Debug)]
 pub struct ItemType {
     pub id: u32,
+    pub name: String,
+    /// Prefix of the pack the item comes from, its block is looked up in that pack first
+    pub prefix: String,
     pub stack_size: u32,
-    pub block_id: Option<u32>,
+    pub icon: u32,
+    pub block: Option<String>,
+    pub tags: TagSet,
+    pub fuel: Option<Duration>,
 }
 
 impl ItemType {
-    pub fn new(id: u32, stack_size: u32, block_id: Option<u32>) -> Self {Self {
+    pub fn new(id: u32, name: String, stack_size: u32, icon: u32, block: Option<String>, tags: TagSet, fuel: Option<Duration>) -> Self {Self {
         id,
+        name,
+        prefix: String::new(),
         stack_size,
-        block_id
+        icon,
+        block,
+        tags,
+        fuel
     }}
 }
 
 impl ItemInteraction for ItemType {
     fn id(&self) -> u32 {self.id}
-    fn block_id(&self) -> Option<u32> {self.block_id}
+    fn block(&self) -> Option<&str> {self.block.as_deref()}
+    fn prefix(&self) -> &str {&self.prefix}
     fn stack_size(&self) -> u32 {self.stack_size}
 }
\ No newline at end of file
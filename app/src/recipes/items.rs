@@ -1,29 +1,154 @@
-use std::sync::OnceLock;
+use std::{collections::HashMap, path::{Path, PathBuf}, sync::OnceLock, time::Duration};
 
-use super::item_type::ItemType;
+use serde::{Deserialize, Serialize};
+
+use crate::content::tags::{load_tag_members, Tag, TagMember, TagSet};
+use crate::content_loader::{default_content, error::{ContentError, ContentErrors}, indices::{resolve, sort_unique, GamePath}};
+
+use super::{item::{Item, STACK_SIZE}, item_type::ItemType};
+
+fn default_stack_size() -> u32 {STACK_SIZE}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ItemFile {
+    pub id: String,
+    #[serde(default = "default_stack_size")]
+    pub stack_size: u32,
+    #[serde(default)]
+    pub icon: u32,
+    #[serde(default)]
+    pub block: Option<String>,
+    /// Written with or without `#`, tag files in `tags/` can add more
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Seconds a furnace burns on one item, the item is not a fuel without it
+    #[serde(default)]
+    pub fuel: Option<f32>,
+}
+
+#[derive(Debug, Default)]
+pub struct Items {
+    pub all: Vec<ItemType>,
+    pub indices: HashMap<String, u32>,
+    tagged: HashMap<Tag, Vec<u32>>,
+}
+
+impl Items {
+    /// `files` are `(prefix, path, file)`, the ids of pack items are already prefixed.
+    /// Tag file members are looked up like the other content references of their pack.
+    pub fn new(files: Vec<(String, PathBuf, ItemFile)>, tag_members: &[TagMember], errors: &mut ContentErrors) -> Result<Self, ContentError> {
+        // Runtime ids are assigned by string id so they do not depend on the file system order.
+        let files = sort_unique(files, "item", |(_, path, file)| (file.id.as_str(), path.as_path()), errors)?;
+        let mut indices = HashMap::<String, u32>::new();
+        files.iter().enumerate().for_each(|(id, (_, _, file))| {indices.insert(file.id.clone(), id as u32);});
+        let mut tags: Vec<TagSet> = files.iter()
+            .map(|(_, _, file)| file.tags.iter().map(|tag| Tag::new(tag)).collect())
+            .collect();
+        for member in tag_members {
+            let id = resolve(&indices, &member.prefix, &member.name)
+                .ok_or_else(|| ContentError::missing(&member.path, &member.field, "item", &member.name));
+            let Some(&id) = errors.check(id)? else {continue};
+            tags[id as usize].insert(member.tag);
+        }
+        let mut tagged = HashMap::<Tag, Vec<u32>>::new();
+        let mut all = Vec::with_capacity(files.len());
+        for (id, ((prefix, path, file), tags)) in files.into_iter().zip(tags).enumerate() {
+            let fuel = match file.fuel {
+                Some(fuel) if fuel.is_nan() || fuel <= 0.0 => {
+                    errors.report(ContentError::invalid(&path, "fuel", "the fuel value must be positive"))?;
+                    None
+                },
+                fuel => fuel.map(Duration::from_secs_f32),
+            };
+            tags.iter().for_each(|tag| tagged.entry(tag).or_default().push(id as u32));
+            all.push(ItemType { prefix, ..ItemType::new(id as u32, file.id, file.stack_size, file.icon, file.block, tags, fuel) });
+        }
+
+        Ok(Self { all, indices, tagged })
+    }
+
+    #[inline]
+    pub fn has_tag(&self, id: u32, tag: Tag) -> bool {
+        self.get(id).is_some_and(|item_type| item_type.tags.contains(tag))
+    }
+
+    /// Ids of the items with the tag, in id order.
+    pub fn with_tag(&self, tag: Tag) -> &[u32] {
+        self.tagged.get(&tag).map_or(&[], |ids| ids.as_slice())
+    }
+
+    pub fn get(&self, id: u32) -> Option<&ItemType> {
+        self.all.get(id as usize)
+    }
+
+    pub fn id(&self, name: &str) -> Option<u32> {
+        self.indices.get(name).copied()
+    }
+
+    pub fn item(&self, name: &str, count: u32) -> Option<Item> {
+        self.id(name).map(|id| Item::new(id, count))
+    }
+
+    pub fn icon(&self, id: u32) -> u32 {
+        self.get(id).map_or(0, |item_type| item_type.icon)
+    }
+
+    pub fn stack_size(&self, id: u32) -> u32 {
+        self.get(id).map_or(STACK_SIZE, |item_type| item_type.stack_size)
+    }
+
+    /// Burn time of one item in a furnace, `None` if the item is not a fuel.
+    pub fn fuel(&self, id: u32) -> Option<Duration> {
+        self.get(id).and_then(|item_type| item_type.fuel)
+    }
+}
+
+pub fn load_item_file(path: &Path) -> Result<ItemFile, ContentError> {
+    let data = std::fs::read(path).map_err(|err| ContentError::io(path, err))?;
+    serde_json::from_slice(&data).map_err(|err| ContentError::json(path, err))
+}
+
+/// `items/` of the base game and of every content pack as `(prefix, path, file)`, pack items are named `@pack:id`.
+pub fn load_item_files(content: &[GamePath<PathBuf>], errors: &mut ContentErrors) -> Result<Vec<(String, PathBuf, ItemFile)>, ContentError> {
+    let mut files = Vec::new();
+    for items in content.iter().map(|c| c.join("items/")) {
+        for file in items.files() {
+            let Some((_, path)) = errors.check(file)? else {continue};
+            let Some(mut item) = errors.check(load_item_file(&path))? else {continue};
+            item.id = items.qualify(&item.id);
+            files.push((items.prefix().to_string(), path, item));
+        }
+    }
+    Ok(files)
+}
+
+/// Items of the base game and of the content packs, tagged by the tag files of the same content.
+pub fn load_items(content: &[GamePath<PathBuf>], errors: &mut ContentErrors) -> Result<Items, ContentError> {
+    tagged_items(load_item_files(content, errors)?, content, errors)
+}
+
+fn tagged_items(files: Vec<(String, PathBuf, ItemFile)>, content: &[GamePath<PathBuf>], errors: &mut ContentErrors) -> Result<Items, ContentError> {
+    let tag_paths = content.iter().map(|c| c.join("tags/")).collect::<Vec<_>>();
+    let tag_members = load_tag_members(&tag_paths, errors)?;
+    Items::new(files, &tag_members.items, errors)
+}
+
+static ITEMS_CONTAINER: OnceLock<Items> = OnceLock::new();
+
+/// Loads the items of the base game and of the active content packs together with the items registered by mods.
+/// Item tags are taken from the tag files of the same content.
+/// `registered` are `(prefix, path, file)` like the loaded files.
+pub fn init_items(registered: Vec<(String, PathBuf, ItemFile)>, content: &[GamePath<PathBuf>], errors: &mut ContentErrors) -> Result<(), ContentError> {
+    let mut files = load_item_files(content, errors)?;
+    files.extend(registered);
+    ITEMS_CONTAINER.set(tagged_items(files, content, errors)?).expect("Items are already initialized");
+    Ok(())
+}
 
-static ITEMS_CONTAINER: OnceLock<Vec<ItemType>> = OnceLock::new();
 #[allow(non_snake_case)]
-pub fn ITEMS() -> &'static [ItemType] {
-    ITEMS_CONTAINER.get_or_init(|| {
-        vec![
-            ItemType::new(0, 100, Some(5)),
-            ItemType::new(1, 100, None),
-            ItemType::new(2, 100, None),
-            ItemType::new(3, 100, Some(7)),
-
-            ItemType::new(4, 50, Some(15)),
-            ItemType::new(5, 50, Some(17)),
-            ItemType::new(6, 50, Some(16)),
-            ItemType::new(7, 50, Some(14)),
-
-            ItemType::new(8, 100, Some(13)),
-            ItemType::new(9, 100, Some(4)),
-            ItemType::new(10, 50, Some(10)),
-            ItemType::new(11, 50, Some(11)),
-
-            ItemType::new(12, 50, Some(9)),
-            ItemType::new(13, 50, Some(12)),
-        ]
-    })
+pub fn ITEMS() -> &'static Items {
+    ITEMS_CONTAINER.get_or_init(|| load_items(&default_content(), &mut ContentErrors::fail_fast()).unwrap_or_else(|err| {
+        eprintln!("Failed to load items: {}", err);
+        Items::default()
+    }))
 }
\ No newline at end of file
@@ -4,4 +4,11 @@ pub mod recipes;
 pub mod storage;
 pub mod item_interaction;
 pub mod item_type;
-pub mod items;
\ No newline at end of file
+pub mod items;
+pub mod item_filter;
+pub mod ratio;
+pub mod technology;
+pub mod research;
+pub mod fluid;
+pub mod fluids;
+pub mod fluid_storage;
\ No newline at end of file
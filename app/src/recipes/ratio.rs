@@ -0,0 +1,292 @@
+use std::collections::{HashMap, HashSet};
+
+use super::recipe::{CraftStation, Recipe, RecipeCrafter, CRAFTERS};
+use super::recipes::RECIPES;
+
+/// Machines of one recipe in a production line.
+#[derive(Debug, Clone)]
+pub struct RatioStep {
+    /// The item the recipe was chosen for, it can be a byproduct of the recipe
+    pub item: u32,
+    pub recipe: &'static Recipe,
+    pub crafter: RecipeCrafter,
+    /// Fractional, round up to get the machines to build
+    pub machines: f32,
+    /// Crafts per second of all the machines together
+    pub crafts: f32,
+}
+
+/// Something the solver decided on its own, shown to the player next to the plan.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RatioNote {
+    /// The item has several recipes, `chosen` was used. Override it with [`RatioSolver::set_recipe`].
+    /// Recipes that only make the item as a byproduct are not chosen by default,
+    /// `chosen` is `None` when the item is counted as a raw input because of that.
+    Alternatives { item: u32, recipes: Vec<u32>, chosen: Option<u32> },
+    /// The item is an ingredient of a recipe that already makes it further up the chain,
+    /// that part of the demand is counted as a raw input instead of going around the loop.
+    Cycle { item: u32, recipe: u32 },
+}
+
+/// Production line for `rate` items per second of `item`. All rates are per second.
+#[derive(Debug, Clone, Default)]
+pub struct RatioPlan {
+    pub item: u32,
+    pub rate: f32,
+    /// From the target down to the recipes nearest to the raw inputs
+    pub steps: Vec<RatioStep>,
+    /// Crafted items consumed by later steps, the target is not included
+    pub flows: Vec<(u32, f32)>,
+    /// Items no allowed crafter makes
+    pub raw: Vec<(u32, f32)>,
+    /// Other results of the steps, they are not used to cover the demand
+    pub byproducts: Vec<(u32, f32)>,
+    pub notes: Vec<RatioNote>,
+}
+
+/// Walks the recipes backwards from a target item and counts the machines it takes.
+///
+/// Crafters are tried in the given order, the recipe of an item is the first one found
+/// with the item as the main product unless it was chosen with [`RatioSolver::set_recipe`].
+#[derive(Debug)]
+pub struct RatioSolver {
+    stations: Vec<(RecipeCrafter, CraftStation<'static>)>,
+    overrides: HashMap<u32, u32>,
+}
+
+impl RatioSolver {
+    pub fn new(crafters: &[RecipeCrafter]) -> Self {
+        Self::with_recipes(RECIPES().all, crafters)
+    }
+
+    pub fn with_recipes(recipes: &'static [Recipe], crafters: &[RecipeCrafter]) -> Self {
+        Self {
+            stations: crafters.iter().map(|&crafter| (crafter, CraftStation::new(recipes, crafter))).collect(),
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// Every crafter with the machines first, crafting by hand is the last resort.
+    pub fn machines() -> Vec<RecipeCrafter> {
+        let mut crafters: Vec<RecipeCrafter> = CRAFTERS().values()
+            .filter(|crafter| crafter.bits().is_power_of_two())
+            .copied()
+            .collect();
+        crafters.sort_by_key(|crafter| (crafter.contains(RecipeCrafter::PLAYER), crafter.bits()));
+        crafters
+    }
+
+    /// Uses `recipe` for `item`, it is ignored if no allowed crafter has the recipe.
+    pub fn set_recipe(&mut self, item: u32, recipe: u32) {
+        self.overrides.insert(item, recipe);
+    }
+
+    pub fn clear_recipe(&mut self, item: u32) {
+        self.overrides.remove(&item);
+    }
+
+    /// Recipes of the item with the crafter each one is made in, without duplicates.
+    pub fn recipes(&self, item: u32) -> Vec<(&'static Recipe, RecipeCrafter)> {
+        let mut recipes: Vec<(&'static Recipe, RecipeCrafter)> = vec![];
+        for (crafter, station) in &self.stations {
+            for &recipe in station.get_by_result(item).unwrap_or(&[]) {
+                if recipes.iter().all(|(r, _)| r.id != recipe.id) {
+                    recipes.push((recipe, *crafter));
+                }
+            }
+        }
+        recipes
+    }
+
+    fn choose(&self, item: u32, notes: &mut Vec<RatioNote>) -> Option<(&'static Recipe, RecipeCrafter)> {
+        let recipes = self.recipes(item);
+        let chosen = self.overrides.get(&item)
+            .and_then(|id| recipes.iter().find(|(recipe, _)| recipe.id == *id))
+            .or_else(|| recipes.iter().find(|(recipe, _)| recipe.result().id() == item))
+            .copied();
+        if recipes.len() > 1 || (chosen.is_none() && !recipes.is_empty()) {
+            notes.push(RatioNote::Alternatives {
+                item,
+                recipes: recipes.iter().map(|(recipe, _)| recipe.id).collect(),
+                chosen: chosen.map(|(recipe, _)| recipe.id),
+            });
+        }
+        chosen
+    }
+
+    /// Picks the recipe of every item the target needs, depth first.
+    /// `order` gets the items after all of their ingredients.
+    fn visit(
+        &self,
+        item: u32,
+        chosen: &mut HashMap<u32, Option<(&'static Recipe, RecipeCrafter)>>,
+        stack: &mut Vec<u32>,
+        cut: &mut HashSet<(u32, u32)>,
+        order: &mut Vec<u32>,
+        notes: &mut Vec<RatioNote>,
+    ) {
+        if chosen.contains_key(&item) {return};
+        let recipe = self.choose(item, notes);
+        chosen.insert(item, recipe);
+        stack.push(item);
+        if let Some((recipe, _)) = recipe {
+            for ingredient in &recipe.ingredients {
+                if stack.contains(&ingredient.id()) {
+                    cut.insert((recipe.id, ingredient.id()));
+                    notes.push(RatioNote::Cycle { item: ingredient.id(), recipe: recipe.id });
+                    continue;
+                }
+                self.visit(ingredient.id(), chosen, stack, cut, order, notes);
+            }
+        }
+        stack.pop();
+        order.push(item);
+    }
+
+    pub fn solve(&self, item: u32, rate: f32) -> RatioPlan {
+        let mut chosen = HashMap::new();
+        let mut cut = HashSet::new();
+        let mut order = vec![];
+        let mut notes = vec![];
+        self.visit(item, &mut chosen, &mut vec![], &mut cut, &mut order, &mut notes);
+
+        // Every consumer of an item comes before it in reversed order, so its demand is complete when reached.
+        let mut demand = HashMap::<u32, f32>::from([(item, rate)]);
+        let mut raw = HashMap::<u32, f32>::new();
+        let mut byproducts = HashMap::<u32, f32>::new();
+        let mut steps = vec![];
+        let mut flows = vec![];
+        for &current in order.iter().rev() {
+            let need = demand.get(&current).copied().unwrap_or(0.0);
+            let Some((recipe, crafter)) = chosen[&current] else {
+                *raw.entry(current).or_default() += need;
+                continue;
+            };
+            if current != item {flows.push((current, need))};
+            let crafts = need / recipe.expected_count(current);
+            let duration = recipe.duration.as_secs_f32();
+            steps.push(RatioStep { item: current, recipe, crafter, machines: crafts * duration, crafts });
+            for result in recipe.results.iter().filter(|result| result.item.id() != current) {
+                *byproducts.entry(result.item.id()).or_default() += crafts * result.expected();
+            }
+            for ingredient in &recipe.ingredients {
+                let amount = crafts * ingredient.count as f32;
+                if cut.contains(&(recipe.id, ingredient.id())) {
+                    *raw.entry(ingredient.id()).or_default() += amount;
+                } else {
+                    *demand.entry(ingredient.id()).or_default() += amount;
+                }
+            }
+        }
+
+        let sorted = |map: HashMap<u32, f32>| {
+            let mut items: Vec<(u32, f32)> = map.into_iter().collect();
+            items.sort_by_key(|(id, _)| *id);
+            items
+        };
+        RatioPlan { item, rate, steps, flows, raw: sorted(raw), byproducts: sorted(byproducts), notes }
+    }
+}
+
+/// Name of a single crafter flag, as written in the recipe files.
+pub fn crafter_name(crafter: RecipeCrafter) -> String {
+    CRAFTERS().iter()
+        .find(|(_, flag)| flag.bits() == crafter.bits())
+        .map(|(name, _)| name.clone())
+        .unwrap_or_else(|| format!("{:?}", crafter))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::recipes::{item::Item, recipe::{RecipeCategory, RecipeResult}};
+    use super::*;
+
+    const ORE: u32 = 0;
+    const PLATE: u32 = 1;
+    const GEAR: u32 = 2;
+
+    /// `results` are `(item, count, chance)`, the recipe is made in an assembler.
+    fn recipe(id: u32, ingredients: &[(u32, u32)], results: &[(u32, u32, f32)], seconds: f32) -> Recipe {
+        Recipe {
+            index: id as usize,
+            id,
+            name: id.to_string(),
+            duration: Duration::from_secs_f32(seconds),
+            crafter: RecipeCrafter::ASSEMBLER,
+            category: RecipeCategory::ITEM,
+            ingredients: ingredients.iter().map(|&(id, count)| Item::new(id, count)).collect(),
+            results: results.iter().map(|&(id, count, chance)| RecipeResult { item: Item::new(id, count), chance }).collect(),
+            fluid_ingredients: vec![],
+            fluid_results: vec![],
+        }
+    }
+
+    fn solver(recipes: Vec<Recipe>) -> RatioSolver {
+        RatioSolver::with_recipes(Box::leak(recipes.into_boxed_slice()), &[RecipeCrafter::ASSEMBLER])
+    }
+
+    fn machines(plan: &RatioPlan) -> Vec<(u32, f32)> {
+        plan.steps.iter().map(|step| (step.recipe.id, step.machines)).collect()
+    }
+
+    #[test]
+    fn chain_counts_machines_and_raw_inputs() {
+        let solver = solver(vec![
+            recipe(0, &[(PLATE, 2)], &[(GEAR, 1, 1.0)], 0.5),
+            recipe(1, &[(ORE, 1)], &[(PLATE, 1, 1.0)], 1.0),
+        ]);
+        let plan = solver.solve(GEAR, 2.0);
+        assert_eq!(machines(&plan), [(0, 1.0), (1, 4.0)]);
+        assert_eq!(plan.flows, [(PLATE, 4.0)]);
+        assert_eq!(plan.raw, [(ORE, 4.0)]);
+        assert!(plan.byproducts.is_empty() && plan.notes.is_empty());
+    }
+
+    #[test]
+    fn first_recipe_is_chosen_until_overridden() {
+        let mut solver = solver(vec![
+            recipe(0, &[(ORE, 1)], &[(GEAR, 1, 1.0)], 1.0),
+            recipe(1, &[(PLATE, 1)], &[(GEAR, 1, 1.0)], 1.0),
+        ]);
+        let plan = solver.solve(GEAR, 1.0);
+        assert_eq!(plan.raw, [(ORE, 1.0)]);
+        assert_eq!(plan.notes, [RatioNote::Alternatives { item: GEAR, recipes: vec![0, 1], chosen: Some(0) }]);
+
+        solver.set_recipe(GEAR, 1);
+        assert_eq!(solver.solve(GEAR, 1.0).raw, [(PLATE, 1.0)]);
+        solver.clear_recipe(GEAR);
+        assert_eq!(solver.solve(GEAR, 1.0).raw, [(ORE, 1.0)]);
+    }
+
+    #[test]
+    fn chance_results_count_on_average() {
+        let solver = solver(vec![recipe(0, &[(ORE, 1)], &[(PLATE, 2, 0.5), (GEAR, 3, 0.5)], 1.0)]);
+        let plan = solver.solve(PLATE, 3.0);
+        assert_eq!(machines(&plan), [(0, 3.0)]);
+        assert_eq!(plan.raw, [(ORE, 3.0)]);
+        assert_eq!(plan.byproducts, [(GEAR, 4.5)]);
+    }
+
+    #[test]
+    fn byproduct_recipes_are_not_chosen_by_default() {
+        let solver = solver(vec![recipe(0, &[(ORE, 1)], &[(PLATE, 1, 1.0), (GEAR, 1, 1.0)], 1.0)]);
+        let plan = solver.solve(GEAR, 1.0);
+        assert!(plan.steps.is_empty());
+        assert_eq!(plan.raw, [(GEAR, 1.0)]);
+        assert_eq!(plan.notes, [RatioNote::Alternatives { item: GEAR, recipes: vec![0], chosen: None }]);
+    }
+
+    #[test]
+    fn cycles_end_in_raw_inputs() {
+        let solver = solver(vec![
+            recipe(0, &[(GEAR, 1), (ORE, 1)], &[(PLATE, 2, 1.0)], 1.0),
+            recipe(1, &[(PLATE, 1)], &[(GEAR, 1, 1.0)], 1.0),
+        ]);
+        let plan = solver.solve(PLATE, 2.0);
+        assert_eq!(machines(&plan), [(0, 1.0), (1, 1.0)]);
+        assert_eq!(plan.raw, [(ORE, 1.0), (PLATE, 1.0)]);
+        assert_eq!(plan.notes, [RatioNote::Cycle { item: PLATE, recipe: 1 }]);
+    }
+}
@@ -1,14 +1,18 @@
-use std::{time::{Instant, Duration}, collections::HashMap};
+use std::{time::Duration, collections::HashMap, sync::OnceLock};
 
 use bitflags::bitflags;
+use itertools::Itertools;
+use rand::{rngs::StdRng, Rng};
 use serde::{Deserialize, Serialize};
 
-use super::{storage::Storage, item::Item};
+use crate::world::clock::{self, Stopwatch};
+
+use super::{fluid::Fluid, storage::Storage, item::Item, recipes::RECIPES};
 
 
 bitflags! {
     #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-    pub struct RecipeCrafter: u8 {
+    pub struct RecipeCrafter: u32 {
         const PLAYER = 0b1;
         const ASSEMBLER = 0b10;
         const FURNACE = 0b100;
@@ -17,6 +21,32 @@ bitflags! {
     }
 }
 
+pub(crate) fn builtin_crafters() -> HashMap<String, RecipeCrafter> {
+    RecipeCrafter::all().iter_names()
+        .map(|(name, flag)| (name.to_lowercase(), flag))
+        .collect()
+}
+
+static CRAFTERS_CONTAINER: OnceLock<HashMap<String, RecipeCrafter>> = OnceLock::new();
+
+/// Gives every crafter category registered by mods its own flag.
+/// Must be called before the recipes are loaded.
+pub fn init_crafters(names: &[String]) {
+    let mut crafters = builtin_crafters();
+    let first = (u32::BITS - RecipeCrafter::all().bits().leading_zeros()) as usize;
+    names.iter().enumerate().for_each(|(i, name)| {
+        if crafters.contains_key(name) {panic!("Duplicate crafter \"{}\"", name)};
+        if first + i >= u32::BITS as usize {panic!("Too many crafters, \"{}\" can't be registered", name)};
+        crafters.insert(name.clone(), RecipeCrafter::from_bits_retain(1 << (first + i)));
+    });
+    CRAFTERS_CONTAINER.set(crafters).expect("Crafters are already initialized");
+}
+
+#[allow(non_snake_case)]
+pub fn CRAFTERS() -> &'static HashMap<String, RecipeCrafter> {
+    CRAFTERS_CONTAINER.get_or_init(builtin_crafters)
+}
+
 bitflags! {
     #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
     pub struct RecipeCategory: u8 {
@@ -25,18 +55,26 @@ bitflags! {
     }
 }
 
-fn new_instant() -> Instant {Instant::now()}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ActiveRecipe {
-    #[serde(default = "new_instant")]
-    #[serde(skip)]
-    start_time: Instant,
+    start_time: Stopwatch,
     pub recipe: Recipe,
 }
 
+/// Active recipe of the saves from before the recipe files, its start time was not saved.
+#[derive(Debug, Deserialize)]
+pub struct LegacyActiveRecipe {
+    recipe: LegacyRecipe,
+}
+
+impl From<LegacyActiveRecipe> for ActiveRecipe {
+    fn from(legacy: LegacyActiveRecipe) -> Self {
+        Recipe::from(legacy.recipe).start_absolute()
+    }
+}
+
 impl ActiveRecipe {
-    pub fn new(start_time: Instant, recipe: Recipe) -> Self {
+    pub fn new(start_time: Stopwatch, recipe: Recipe) -> Self {
         Self { start_time, recipe }
     }
 
@@ -48,6 +86,20 @@ impl ActiveRecipe {
         false
     }
 
+    pub fn start_time(&self) -> Stopwatch {
+        self.start_time
+    }
+
+    /// Starts the timer again, used while the recipe waits for its ingredients.
+    pub fn restart(&mut self) {
+        self.start_time = Stopwatch::start();
+    }
+
+    /// Moves the start later, so that the time passed without fuel or power does not count.
+    pub fn delay(&mut self, time: Duration) {
+        self.start_time.delay(time);
+    }
+
     pub fn progress(&self) -> f32 {
         self.start_time.elapsed().as_secs_f32() / self.recipe.duration.as_secs_f32() % 1.0
     }
@@ -56,9 +108,10 @@ impl ActiveRecipe {
         self.start_time.elapsed() > self.recipe.duration
     }
 
-    pub fn update(&self, storage: &mut dyn Storage) -> bool {
-        if self.is_finished() && storage.is_space_exist(&self.recipe.result) {
-            storage.add(&self.recipe.result, false);
+    /// Finishes only when every result fits at its full count, the chances are rolled after that.
+    pub fn update(&self, storage: &mut dyn Storage, rng: &mut impl Rng) -> bool {
+        if self.is_finished() && storage.is_spaces_exist(&self.recipe.max_results()) {
+            storage.add_items(&self.recipe.roll_results(rng));
             return true;
         }
         false
@@ -66,29 +119,119 @@ impl ActiveRecipe {
 }
 
 
+/// One output of a recipe, the item is given with the `chance`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RecipeResult {
+    pub item: Item,
+    pub chance: f32,
+}
+
+impl RecipeResult {
+    pub fn roll(&self, rng: &mut impl Rng) -> Option<Item> {
+        (self.chance >= 1.0 || rng.gen_bool(self.chance as f64)).then_some(self.item)
+    }
+
+    /// Average count of the item per craft
+    pub fn expected(&self) -> f32 {
+        self.item.count as f32 * self.chance
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Recipe {
+    #[serde(with = "crate::content::manifest::recipe_index")]
     pub index: usize,
+    #[serde(with = "crate::content::manifest::recipe_id")]
     pub id: u32,
+    pub name: String,
     pub duration: Duration,
     pub crafter: RecipeCrafter,
     pub category: RecipeCategory,
     pub ingredients: Vec<Item>,
-    pub result: Item,
+    /// Never empty, the first result is the main product
+    pub results: Vec<RecipeResult>,
+    /// Only machines with fluid boxes make recipes with fluids
+    pub fluid_ingredients: Vec<Fluid>,
+    pub fluid_results: Vec<Fluid>,
+}
+
+/// Recipe as the saves from before the recipe files wrote it, with one result and a crafter of one byte.
+/// The index and the id are mapped through the manifest like the ones of [`Recipe`].
+#[derive(Debug, Deserialize)]
+pub struct LegacyRecipe {
+    #[serde(with = "crate::content::manifest::recipe_index")]
+    index: usize,
+    #[serde(with = "crate::content::manifest::recipe_id")]
+    id: u32,
+    duration: Duration,
+    crafter: u8,
+    category: u8,
+    ingredients: Vec<Item>,
+    result: Item,
+}
+
+impl From<LegacyRecipe> for Recipe {
+    fn from(legacy: LegacyRecipe) -> Self {
+        let name = RECIPES().all.get(legacy.index).map_or_else(String::new, |recipe| recipe.name.clone());
+        Self {
+            index: legacy.index,
+            id: legacy.id,
+            name,
+            duration: legacy.duration,
+            crafter: RecipeCrafter::from_bits_retain(legacy.crafter.into()),
+            category: RecipeCategory::from_bits_retain(legacy.category),
+            ingredients: legacy.ingredients,
+            results: vec![RecipeResult { item: legacy.result, chance: 1.0 }],
+            fluid_ingredients: vec![],
+            fluid_results: vec![],
+        }
+    }
 }
 
 
 impl Recipe {
+    /// The main product, shown as the icon of the recipe
+    pub fn result(&self) -> &Item {
+        &self.results[0].item
+    }
+
+    /// Every result at its full count, the space a finished craft may need.
+    pub fn max_results(&self) -> Vec<Item> {
+        self.results.iter().map(|result| result.item).collect()
+    }
+
+    /// Random numbers for a craft finished now, `salts` tell apart the crafters that finish on the same tick.
+    pub fn rng(&self, salts: &[u64]) -> StdRng {
+        clock::rng(&[&[self.id as u64], salts].concat())
+    }
+
+    pub fn roll_results(&self, rng: &mut impl Rng) -> Vec<Item> {
+        self.results.iter().filter_map(|result| result.roll(rng)).collect()
+    }
+
+    /// Average count of the item per craft over all the results.
+    pub fn expected_count(&self, item_id: u32) -> f32 {
+        self.results.iter().filter(|result| result.item.id() == item_id).map(RecipeResult::expected).sum()
+    }
+
+    /// Count of the item every craft gives for sure, results with a chance are not counted.
+    pub fn guaranteed_count(&self, item_id: u32) -> u32 {
+        self.results.iter()
+            .filter(|result| result.item.id() == item_id && result.chance >= 1.0)
+            .map(|result| result.item.count)
+            .sum()
+    }
+
     pub fn start(&self, storage: &mut dyn Storage) -> Option<ActiveRecipe> {
         if storage.is_items_exist(&self.ingredients[..]) {
             storage.remove_items(&self.ingredients[..]);
-            return Some(ActiveRecipe { start_time: Instant::now(), recipe: self.clone() });
+            return Some(ActiveRecipe { start_time: Stopwatch::start(), recipe: self.clone() });
         }
         None
     }
 
     pub fn start_absolute(&self) -> ActiveRecipe {
-        ActiveRecipe { start_time: Instant::now(), recipe: self.clone() }
+        ActiveRecipe { start_time: Stopwatch::start(), recipe: self.clone() }
     }
 }
 
@@ -117,10 +260,12 @@ impl<'a> CraftStation<'a> {
                     .or_insert(vec![recipe]);
             });
 
-            result_recipe
-                .entry(recipe.id)
-                .and_modify(|v| v.push(recipe))
-                .or_insert(vec![recipe]);
+            recipe.results.iter().map(|result| result.item.id()).unique().for_each(|id| {
+                result_recipe
+                    .entry(id)
+                    .and_modify(|v| v.push(recipe))
+                    .or_insert(vec![recipe]);
+            });
         });
 
         Self { all, ingredient_recipe, result_recipe }
@@ -136,10 +281,10 @@ impl<'a> CraftStation<'a> {
     pub fn first_by_result(&self, result_id: u32) -> Option<&Recipe> {
         self.get_by_result(result_id).map(|v| v[0])
     }
-    pub fn get_by_ingredient(&self, ingredient_id: u32) -> Option<&[&Recipe]> {
+    pub fn get_by_ingredient(&self, ingredient_id: u32) -> Option<&[&'a Recipe]> {
         self.ingredient_recipe.get(&ingredient_id).map(|v| v.as_slice())
     }
-    pub fn get_by_result(&self, result_id: u32) -> Option<&[&Recipe]> {
+    pub fn get_by_result(&self, result_id: u32) -> Option<&[&'a Recipe]> {
         self.result_recipe.get(&result_id).map(|v| v.as_slice())
     }
 }
@@ -163,3 +308,61 @@ impl Recipes {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::save_load::decode_exact;
+
+    use super::*;
+
+    fn recipe(results: &[(u32, f32)]) -> Recipe {
+        Recipe {
+            index: 0,
+            id: 0,
+            name: "test".to_string(),
+            duration: Duration::from_secs(1),
+            crafter: RecipeCrafter::ASSEMBLER,
+            category: RecipeCategory::ITEM,
+            ingredients: vec![Item::new(0, 1)],
+            results: results.iter().map(|&(id, chance)| RecipeResult { item: Item::new(id, 1), chance }).collect(),
+            fluid_ingredients: vec![],
+            fluid_results: vec![],
+        }
+    }
+
+    #[test]
+    fn baseline_active_recipe_is_decoded() {
+        // Recipe of the baseline: index, id, duration, crafter, category, ingredients and one result.
+        let recipe = (3usize, 3u32, Duration::from_secs(1), 0b11u8, 0b1u8, vec![Item::new(3, 2), Item::new(2, 1)], Item::new(5, 2));
+        let bytes = bincode::serialize(&(recipe,)).unwrap();
+        assert!(decode_exact::<ActiveRecipe>(&bytes).is_err());
+
+        let active = ActiveRecipe::from(decode_exact::<LegacyActiveRecipe>(&bytes).unwrap());
+        assert_eq!((active.recipe.index, active.recipe.id), (3, 3));
+        assert_eq!(active.recipe.duration, Duration::from_secs(1));
+        assert!(active.recipe.crafter.contains(RecipeCrafter::PA));
+        assert_eq!(active.recipe.ingredients, [Item::new(3, 2), Item::new(2, 1)]);
+        assert_eq!(active.recipe.max_results(), [Item::new(5, 2)]);
+        assert!(active.recipe.fluid_ingredients.is_empty() && active.recipe.fluid_results.is_empty());
+    }
+
+    #[test]
+    fn seeded_rolls_are_pinned() {
+        let recipe = recipe(&[(1, 1.0), (2, 0.5), (3, 0.5), (4, 0.5), (5, 0.1)]);
+        let rolled: Vec<Vec<u32>> = (0..4).map(|seed| {
+            recipe.roll_results(&mut StdRng::seed_from_u64(seed)).iter().map(|item| item.id()).collect()
+        }).collect();
+        // The results of a save must not change with the rand version.
+        assert_eq!(rolled, [vec![1, 4], vec![1, 4], vec![1, 2, 3, 4], vec![1, 2, 3, 4]]);
+    }
+
+    #[test]
+    fn certain_results_always_come_out() {
+        let recipe = recipe(&[(1, 1.0), (2, 0.0), (3, 1.0)]);
+        for seed in 0..100 {
+            assert_eq!(recipe.roll_results(&mut StdRng::seed_from_u64(seed)), [Item::new(1, 1), Item::new(3, 1)]);
+        }
+    }
+}
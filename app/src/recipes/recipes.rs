@@ -1,122 +1,257 @@
-use std::{time::Duration, sync::OnceLock};
+use std::{path::{Path, PathBuf}, sync::OnceLock, time::Duration};
 
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::content::tags::Tag;
+use crate::content_loader::{default_content, error::{ContentError, ContentErrors}, indices::{resolve, sort_unique, GamePath}};
+
+use crate::recipes::fluid::Fluid;
+use crate::recipes::fluids::FLUIDS;
 use crate::recipes::item::Item;
-use crate::recipes::recipe::{Recipe, RecipeCrafter, RecipeCategory};
+use crate::recipes::recipe::{Recipe, RecipeCrafter, RecipeCategory, RecipeResult, CRAFTERS};
+
+use crate::voxels::live_voxels::{assembling_machine, furnace};
+
+use super::{items::ITEMS, recipe::Recipes};
+
+fn one() -> u32 {1}
+fn always() -> f32 {1.0}
+fn item_category() -> Vec<String> {vec![String::from("item")]}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ItemStackFile {
+    /// Ingredients can be `#tag` for any item with the tag
+    pub item: String,
+    #[serde(default = "one")]
+    pub count: u32,
+}
 
-use super::recipe::Recipes;
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ResultStackFile {
+    pub item: String,
+    #[serde(default = "one")]
+    pub count: u32,
+    /// Between 0 and 1
+    #[serde(default = "always")]
+    pub chance: f32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FluidStackFile {
+    pub fluid: String,
+    pub amount: f32,
+}
+
+impl From<ItemStackFile> for ResultStackFile {
+    fn from(stack: ItemStackFile) -> Self {
+        Self { item: stack.item, count: stack.count, chance: 1.0 }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RecipeFile {
+    pub id: String,
+    /// Duration in seconds
+    pub duration: f32,
+    pub crafter: Vec<String>,
+    #[serde(default = "item_category")]
+    pub category: Vec<String>,
+    pub ingredients: Vec<ItemStackFile>,
+    /// The main product, put in front of `results`
+    #[serde(default)]
+    pub result: Option<ItemStackFile>,
+    /// Byproducts and chance outputs, or every result when `result` is not set
+    #[serde(default)]
+    pub results: Vec<ResultStackFile>,
+    /// Taken from the fluid boxes of the machine, the player and furnaces can't make such recipes
+    #[serde(default)]
+    pub fluid_ingredients: Vec<FluidStackFile>,
+    #[serde(default)]
+    pub fluid_results: Vec<FluidStackFile>,
+}
+
+impl RecipeFile {
+    /// `result` followed by `results`.
+    pub fn all_results(&self) -> Vec<ResultStackFile> {
+        self.result.iter().cloned().map(ResultStackFile::from).chain(self.results.iter().cloned()).collect()
+    }
+}
+
+/// Items are looked up in the pack of the recipe first.
+fn to_item(prefix: &str, stack: &ItemStackFile, path: &Path, field: String) -> Result<Item, ContentError> {
+    resolve(&ITEMS().indices, prefix, &stack.item).map(|&id| Item::new(id, stack.count))
+        .ok_or_else(|| ContentError::missing(path, format!("{}.item", field), "item", &stack.item))
+}
+
+fn to_result(prefix: &str, stack: &ResultStackFile, path: &Path, field: String) -> Result<RecipeResult, ContentError> {
+    let item = resolve(&ITEMS().indices, prefix, &stack.item).map(|&id| Item::new(id, stack.count))
+        .ok_or_else(|| ContentError::missing(path, format!("{}.item", field), "item", &stack.item))?;
+    if !(0.0..=1.0).contains(&stack.chance) {
+        return Err(ContentError::invalid(path, format!("{}.chance", field), "the chance is not between 0 and 1"));
+    }
+    Ok(RecipeResult { item, chance: stack.chance })
+}
+
+fn to_fluid(prefix: &str, stack: &FluidStackFile, path: &Path, field: String) -> Result<Fluid, ContentError> {
+    let id = resolve(&FLUIDS().indices, prefix, &stack.fluid).copied()
+        .ok_or_else(|| ContentError::missing(path, format!("{}.fluid", field), "fluid", &stack.fluid))?;
+    if stack.amount.is_nan() || stack.amount <= 0.0 {
+        return Err(ContentError::invalid(path, format!("{}.amount", field), "the amount is not positive"));
+    }
+    if stack.amount > assembling_machine::FLUID_CAPACITY {
+        return Err(ContentError::invalid(path, format!("{}.amount", field),
+            format!("the assembler holds {} of a fluid, the recipe needs {}", assembling_machine::FLUID_CAPACITY, stack.amount)));
+    }
+    Ok(Fluid::new(id, stack.amount))
+}
+
+/// `prefix` is the prefix of the pack the recipe comes from, `path` is only used for errors.
+pub fn to_recipe(prefix: &str, recipe_file: RecipeFile, id: u32, path: &Path) -> Result<Recipe, ContentError> {
+    let crafter = recipe_file.crafter.iter().enumerate().try_fold(RecipeCrafter::empty(), |flags, (i, name)| {
+        CRAFTERS().get(&name.to_lowercase()).map(|&crafter| flags | crafter)
+            .ok_or_else(|| ContentError::missing(path, format!("crafter[{}]", i), "crafter", name))
+    })?;
+    let category = recipe_file.category.iter().enumerate().try_fold(RecipeCategory::empty(), |flags, (i, name)| {
+        RecipeCategory::from_name(&name.to_uppercase()).map(|category| flags | category)
+            .ok_or_else(|| ContentError::missing(path, format!("category[{}]", i), "category", name))
+    })?;
+
+    let result_fields = recipe_file.result.iter().map(|_| String::from("result"))
+        .chain((0..recipe_file.results.len()).map(|i| format!("results[{}]", i)));
+    let results = recipe_file.all_results().iter().zip(result_fields)
+        .map(|(r, field)| to_result(prefix, r, path, field))
+        .collect::<Result<Vec<RecipeResult>, ContentError>>()?;
+    if results.is_empty() {return Err(ContentError::invalid(path, "results", "the recipe has no result"))};
+    let fluids = |stacks: &[FluidStackFile], field: &str| stacks.iter().enumerate()
+        .map(|(i, f)| to_fluid(prefix, f, path, format!("{}[{}]", field, i)))
+        .collect::<Result<Vec<Fluid>, ContentError>>();
+    let fluid_ingredients = fluids(&recipe_file.fluid_ingredients, "fluid_ingredients")?;
+    let fluid_results = fluids(&recipe_file.fluid_results, "fluid_results")?;
+    if (!fluid_ingredients.is_empty() || !fluid_results.is_empty()) && crafter.intersects(RecipeCrafter::PLAYER | RecipeCrafter::FURNACE) {
+        return Err(ContentError::invalid(path, "crafter", "recipes with fluids can't be made by the player or in a furnace"));
+    }
+    // Machines can't make recipes that don't fit into their slots.
+    let slots = [
+        (RecipeCrafter::ASSEMBLER, "assembler", "results", results.len(), assembling_machine::RESULT_LENGTH),
+        (RecipeCrafter::FURNACE, "furnace", "results", results.len(), furnace::RESULT_LENGTH),
+        (RecipeCrafter::ASSEMBLER, "assembler", "fluid_ingredients", fluid_ingredients.len(), assembling_machine::FLUID_INGREDIENT_LENGTH),
+        (RecipeCrafter::ASSEMBLER, "assembler", "fluid_results", fluid_results.len(), assembling_machine::FLUID_RESULT_LENGTH),
+    ];
+    if let Some((_, machine, field, count, max)) = slots.into_iter().find(|&(flag, _, _, count, max)| crafter.contains(flag) && count > max) {
+        return Err(ContentError::invalid(path, field,
+            format!("the {} has room for {} {}, the recipe has {}", machine, max, field.replace('_', " "), count)));
+    }
+    let ingredients = recipe_file.ingredients.iter().enumerate()
+        .map(|(i, stack)| to_item(prefix, stack, path, format!("ingredients[{}]", i)))
+        .collect::<Result<Vec<Item>, ContentError>>()?;
+
+    Ok(Recipe {
+        index: id as usize,
+        id,
+        duration: Duration::from_secs_f32(recipe_file.duration),
+        crafter,
+        category,
+        ingredients,
+        results,
+        fluid_ingredients,
+        fluid_results,
+        name: recipe_file.id,
+    })
+}
+
+/// Recipes are written in JSON or, with the `.toml` extension, in TOML.
+pub fn load_recipe_file(path: &Path) -> Result<RecipeFile, ContentError> {
+    let data = std::fs::read(path).map_err(|err| ContentError::io(path, err))?;
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str::<RecipeFile>(&String::from_utf8_lossy(&data))
+            .map_err(|err| ContentError::toml(path, err)),
+        _ => serde_json::from_slice::<RecipeFile>(&data).map_err(|err| ContentError::json(path, err)),
+    }
+}
+
+/// `recipes/` of the base game and of every content pack as `(prefix, path, file)`, pack recipes are named `@pack:id`.
+pub fn load_recipe_files(content: &[GamePath<PathBuf>], errors: &mut ContentErrors) -> Result<Vec<(String, PathBuf, RecipeFile)>, ContentError> {
+    let mut files = Vec::new();
+    for recipes in content.iter().map(|c| c.join("recipes/")) {
+        for file in recipes.files() {
+            let Some((_, path)) = errors.check(file)? else {continue};
+            let Some(mut recipe) = errors.check(load_recipe_file(&path))? else {continue};
+            recipe.id = recipes.qualify(&recipe.id);
+            files.push((recipes.prefix().to_string(), path, recipe));
+        }
+    }
+    Ok(files)
+}
+
+/// Recipes one file with `#tag` ingredients may become, the combinations grow quickly with several tags.
+pub const MAX_TAG_VARIANTS: usize = 256;
+
+/// Recipes with `#tag` ingredients become one recipe for every combination of the items with the tags.
+/// They are named `<recipe>/<item>`, the items of several tag ingredients are joined with `+`.
+/// Fails when there would be more than [`MAX_TAG_VARIANTS`] of them.
+pub fn expand_tags(prefix: String, path: PathBuf, file: RecipeFile) -> Result<Vec<(String, PathBuf, RecipeFile)>, ContentError> {
+    if !file.ingredients.iter().any(|stack| stack.item.starts_with('#')) {return Ok(vec![(prefix, path, file)])};
+    let choices = file.ingredients.iter().enumerate().map(|(i, stack)| match stack.item.strip_prefix('#') {
+        Some(tag) => {
+            let items = ITEMS().with_tag(Tag::new(tag));
+            if items.is_empty() {return Err(ContentError::missing(&path, format!("ingredients[{}].item", i), "item tag", tag))};
+            Ok(items.iter().map(|&id| Some(ITEMS().all[id as usize].name.clone())).collect::<Vec<_>>())
+        },
+        None => Ok(vec![None]),
+    }).collect::<Result<Vec<_>, ContentError>>()?;
+    let variants = choices.iter().map(Vec::len).fold(1usize, usize::saturating_mul);
+    if variants > MAX_TAG_VARIANTS {
+        return Err(ContentError::invalid(&path, "ingredients",
+            format!("the tag ingredients make {} variants of the recipe, at most {} are allowed", variants, MAX_TAG_VARIANTS)));
+    }
+    Ok(choices.into_iter().multi_cartesian_product().map(|chosen| {
+        let mut variant = file.clone();
+        variant.id = format!("{}/{}", file.id, chosen.iter().flatten().join("+"));
+        variant.ingredients.iter_mut().zip(chosen).for_each(|(stack, item)| {
+            if let Some(item) = item {stack.item = item};
+        });
+        (prefix.clone(), path.clone(), variant)
+    }).collect())
+}
+
+/// `recipe_files` are `(prefix, path, file)`.
+pub fn to_recipes(recipe_files: Vec<(String, PathBuf, RecipeFile)>, errors: &mut ContentErrors) -> Result<Vec<Recipe>, ContentError> {
+    let mut expanded = Vec::new();
+    for (prefix, path, file) in recipe_files {
+        expanded.extend(errors.check(expand_tags(prefix, path, file))?.into_iter().flatten());
+    }
+    // Same as items: runtime ids are assigned by string id.
+    let recipe_files = sort_unique(expanded, "recipe", |(_, path, file)| (file.id.as_str(), path.as_path()), errors)?;
+    let mut recipes = Vec::with_capacity(recipe_files.len());
+    for (prefix, path, file) in recipe_files {
+        let id = recipes.len() as u32;
+        if let Some(recipe) = errors.check(to_recipe(&prefix, file, id, &path))? {recipes.push(recipe)};
+    }
+    Ok(recipes)
+}
+
+pub fn load_recipes(content: &[GamePath<PathBuf>], errors: &mut ContentErrors) -> Result<Vec<Recipe>, ContentError> {
+    to_recipes(load_recipe_files(content, errors)?, errors)
+}
 
 static ALL_RECIPES_CONTAINER: OnceLock<Vec<Recipe>> = OnceLock::new();
+
+/// Loads the recipes of the base game and of the active content packs together with the recipes registered by mods.
+/// Must be called after the items are initialized.
+/// `registered` are `(prefix, path, file)` like the loaded files.
+pub fn init_recipes(registered: Vec<(String, PathBuf, RecipeFile)>, content: &[GamePath<PathBuf>], errors: &mut ContentErrors) -> Result<(), ContentError> {
+    let mut files = load_recipe_files(content, errors)?;
+    files.extend(registered);
+    ALL_RECIPES_CONTAINER.set(to_recipes(files, errors)?).expect("Recipes are already initialized");
+    Ok(())
+}
+
 pub fn all_recipe() -> &'static [Recipe] {
-    ALL_RECIPES_CONTAINER.get_or_init(|| vec![
-        Recipe {
-            index: 0,
-            id: 0,
-            duration: Duration::from_secs_f32(0.3),
-            crafter: RecipeCrafter::PA,
-            category: RecipeCategory::ITEM,
-            ingredients: vec![Item::new(1, 2)],
-            result: Item::new(2, 1)
-        },
-        Recipe {
-            index: 1,
-            id: 1,
-            duration: Duration::new(2, 0),
-            crafter: RecipeCrafter::PA,
-            category: RecipeCategory::ITEM,
-            ingredients: vec![Item::new(1, 8)],
-            result: Item::new(4, 1)
-        },
-        Recipe {
-            index: 2,
-            id: 2,
-            duration: Duration::new(1, 0),
-            crafter: RecipeCrafter::FURNACE,
-            category: RecipeCategory::ITEM,
-            ingredients: vec![Item::new(0, 1)],
-            result: Item::new(1, 1)
-        },
-        Recipe {
-            index: 3,
-            id: 3,
-            duration: Duration::new(1, 0),
-            crafter: RecipeCrafter::PA,
-            category: RecipeCategory::ITEM,
-            ingredients: vec![Item::new(3, 2), Item::new(2, 1)],
-            result: Item::new(5, 2)
-        },
-        Recipe {
-            index: 4,
-            id: 4,
-            duration: Duration::new(5, 0),
-            crafter: RecipeCrafter::PA,
-            category: RecipeCategory::ITEM,
-            ingredients: vec![Item::new(2, 10)],
-            result: Item::new(6, 1)
-        },
-        Recipe {
-            index: 5,
-            id: 5,
-            duration: Duration::new(2, 0),
-            crafter: RecipeCrafter::PA,
-            category: RecipeCategory::ITEM,
-            ingredients: vec![Item::new(3, 15)],
-            result: Item::new(7, 1)
-        },
-        Recipe {
-            index: 6,
-            id: 6,
-            duration: Duration::new(1, 0),
-            crafter: RecipeCrafter::PA,
-            category: RecipeCategory::ITEM,
-            ingredients: vec![Item::new(2, 10)],
-            result: Item::new(8, 1)
-        },
-        Recipe {
-            index: 7,
-            id: 7,
-            duration: Duration::new(1, 0),
-            crafter: RecipeCrafter::PA,
-            category: RecipeCategory::ITEM,
-            ingredients: vec![Item::new(2, 5), Item::new(3, 5)],
-            result: Item::new(9, 1)
-        },
-        Recipe {
-            index: 8,
-            id: 8,
-            duration: Duration::new(1, 0),
-            crafter: RecipeCrafter::PA,
-            category: RecipeCategory::ITEM,
-            ingredients: vec![Item::new(2, 1)],
-            result: Item::new(10, 1)
-        },
-        Recipe {
-            index: 9,
-            id: 9,
-            duration: Duration::new(1, 0),
-            crafter: RecipeCrafter::PA,
-            category: RecipeCategory::ITEM,
-            ingredients: vec![Item::new(2, 1)],
-            result: Item::new(11, 1)
-        },
-        Recipe {
-            index: 10,
-            id: 10,
-            duration: Duration::new(3, 0),
-            crafter: RecipeCrafter::PA,
-            category: RecipeCategory::ITEM,
-            ingredients: vec![Item::new(1, 4), Item::new(2, 2)],
-            result: Item::new(12, 1)
-        },
-        Recipe {
-            index: 11,
-            id: 11,
-            duration: Duration::new(1, 0),
-            crafter: RecipeCrafter::PA,
-            category: RecipeCategory::ITEM,
-            ingredients: vec![Item::new(2, 1)],
-            result: Item::new(13, 1)
-        },
-    ])
+    ALL_RECIPES_CONTAINER.get_or_init(|| load_recipes(&default_content(), &mut ContentErrors::fail_fast()).unwrap_or_else(|err| {
+        eprintln!("Failed to load recipes: {}", err);
+        Vec::new()
+    }))
 }
 
 static RECIPES_CONTAINER: OnceLock<Recipes> = OnceLock::new();
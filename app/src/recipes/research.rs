@@ -0,0 +1,224 @@
+use std::{collections::{HashMap, HashSet}, path::{Path, PathBuf}, sync::RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use super::{items::ITEMS, technology::{Technology, TECHNOLOGIES}};
+
+/// `research.json` of a world. Technologies and items are saved by name, unknown ones are dropped.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct ResearchFile {
+    #[serde(default)]
+    researched: Vec<String>,
+    #[serde(default)]
+    current: Option<String>,
+    /// Science items already consumed for the current technology
+    #[serde(default)]
+    progress: HashMap<String, u32>,
+}
+
+/// Technologies researched in a world and the one the labs are working on.
+#[derive(Debug, Default)]
+pub struct Research {
+    researched: HashSet<u32>,
+    current: Option<u32>,
+    /// Consumed count of every item of the current technology cost, in cost order
+    progress: Vec<u32>,
+    unlocked: HashSet<u32>,
+}
+
+impl Research {
+    fn from_file(file: ResearchFile) -> Self {
+        let mut research = Self::default();
+        file.researched.iter().filter_map(|name| TECHNOLOGIES().id(name)).for_each(|id| research.complete(id));
+        if let Some(current) = file.current.as_deref().and_then(|name| TECHNOLOGIES().id(name)) {
+            if research.start(current) {
+                let technology = &TECHNOLOGIES().all[current as usize];
+                for (item, progress) in technology.cost.iter().zip(research.progress.iter_mut()) {
+                    let name = &ITEMS().all[item.id() as usize].name;
+                    *progress = file.progress.get(name).copied().unwrap_or(0).min(item.count);
+                }
+            }
+        }
+        research
+    }
+
+    fn to_file(&self) -> ResearchFile {
+        let name = |id: u32| TECHNOLOGIES().all[id as usize].name.clone();
+        let mut researched: Vec<String> = self.researched.iter().map(|&id| name(id)).collect();
+        researched.sort();
+        let progress = self.current().map(|technology| technology.cost.iter().zip(&self.progress)
+            .map(|(item, &count)| (ITEMS().all[item.id() as usize].name.clone(), count))
+            .collect())
+            .unwrap_or_default();
+        ResearchFile { researched, current: self.current.map(name), progress }
+    }
+
+    fn complete(&mut self, id: u32) {
+        self.researched.insert(id);
+        self.unlocked.extend(&TECHNOLOGIES().all[id as usize].unlocks);
+    }
+
+    pub fn is_researched(&self, id: u32) -> bool {
+        self.researched.contains(&id)
+    }
+
+    /// Not researched yet, with every prerequisite researched.
+    pub fn is_available(&self, id: u32) -> bool {
+        let Some(technology) = TECHNOLOGIES().get(id) else {return false};
+        !self.is_researched(id) && technology.prerequisites.iter().all(|p| self.is_researched(*p))
+    }
+
+    pub fn is_recipe_unlocked(&self, recipe_id: u32) -> bool {
+        !TECHNOLOGIES().is_locked(recipe_id) || self.unlocked.contains(&recipe_id)
+    }
+
+    pub fn current(&self) -> Option<&'static Technology> {
+        self.current.and_then(|id| TECHNOLOGIES().get(id))
+    }
+
+    /// Consumed and total count of every item of the current technology cost.
+    pub fn progress(&self) -> Vec<(u32, u32, u32)> {
+        let Some(technology) = self.current() else {return vec![]};
+        technology.cost.iter().zip(&self.progress).map(|(item, &count)| (item.id(), count, item.count)).collect()
+    }
+
+    /// Switches the labs to the technology, the progress of the previous one is lost.
+    pub fn start(&mut self, id: u32) -> bool {
+        if !self.is_available(id) {return false};
+        self.current = Some(id);
+        self.progress = vec![0; TECHNOLOGIES().all[id as usize].cost.len()];
+        true
+    }
+
+    /// Whether the current technology still needs the item.
+    pub fn needs(&self, item_id: u32) -> bool {
+        self.progress().iter().any(|&(id, count, total)| id == item_id && count < total)
+    }
+
+    /// Counts one science item towards `technology`, the technology is researched with the last one.
+    /// Returns `false` if the technology is no longer researched or does not need the item.
+    pub fn consume(&mut self, technology: u32, item_id: u32) -> bool {
+        let Some(current) = self.current() else {return false};
+        if current.id != technology {return false};
+        let Some(index) = current.cost.iter().enumerate()
+            .position(|(i, item)| item.id() == item_id && self.progress[i] < item.count) else {return false};
+        self.progress[index] += 1;
+        if current.cost.iter().zip(&self.progress).all(|(item, &count)| count >= item.count) {
+            println!("Technology \"{}\" is researched", current.name);
+            self.complete(technology);
+            self.current = None;
+            self.progress.clear();
+        }
+        true
+    }
+}
+
+struct WorldResearch {
+    path: PathBuf,
+    research: Research,
+}
+
+static RESEARCH: RwLock<Option<WorldResearch>> = RwLock::new(None);
+
+/// Loads the research of the world that is being opened.
+pub fn load_research(world: &Path) {
+    let path = world.join("research.json");
+    let file = match std::fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice::<ResearchFile>(&bytes).unwrap_or_else(|err| {
+            eprintln!("Failed to parse research {:?}: {}", path, err);
+            ResearchFile::default()
+        }),
+        Err(_) => ResearchFile::default(),
+    };
+    *RESEARCH.write().unwrap() = Some(WorldResearch { path, research: Research::from_file(file) });
+}
+
+pub fn save_research() {
+    let research = RESEARCH.read().unwrap();
+    let Some(research) = research.as_ref() else {return};
+    if let Err(err) = std::fs::write(&research.path, serde_json::to_vec_pretty(&research.research.to_file()).unwrap()) {
+        eprintln!("Research write error: {}", err);
+    }
+}
+
+/// Forgets the research of the closed world, it is saved by the save thread.
+pub fn unload_research() {
+    *RESEARCH.write().unwrap() = None;
+}
+
+/// Reads the research of the open world, `None` on the main screen.
+pub fn with_research<T>(f: impl FnOnce(&Research) -> T) -> Option<T> {
+    RESEARCH.read().unwrap().as_ref().map(|research| f(&research.research))
+}
+
+pub fn with_research_mut<T>(f: impl FnOnce(&mut Research) -> T) -> Option<T> {
+    RESEARCH.write().unwrap().as_mut().map(|research| f(&mut research.research))
+}
+
+/// Recipes are unlocked while no world is open, so that nothing is hidden by accident.
+pub fn is_recipe_unlocked(recipe_id: u32) -> bool {
+    with_research(|research| research.is_recipe_unlocked(recipe_id)).unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{content_loader::test_content, recipes::recipes::RECIPES};
+
+    use super::*;
+
+    fn technology(name: &str) -> u32 {
+        TECHNOLOGIES().id(name).unwrap()
+    }
+
+    fn recipe(name: &str) -> u32 {
+        RECIPES().all.iter().find(|recipe| recipe.name == name).unwrap().id
+    }
+
+    #[test]
+    fn prerequisites_are_researched_first() {
+        test_content();
+        let mut research = Research::default();
+        assert!(research.is_available(technology("automation")));
+        assert!(!research.is_available(technology("logistics")));
+        assert!(!research.start(technology("logistics")));
+        assert!(research.current().is_none());
+
+        research.complete(technology("automation"));
+        assert!(!research.is_available(technology("automation")));
+        assert!(research.is_available(technology("logistics")));
+        assert!(research.start(technology("logistics")));
+    }
+
+    #[test]
+    fn science_is_consumed_until_the_technology_is_researched() {
+        test_content();
+        let automation = technology("automation");
+        let science = ITEMS().id("science_pack").unwrap();
+        let mut research = Research::default();
+        assert!(!research.consume(automation, science));
+        assert!(research.start(automation));
+        assert!(!research.consume(automation, ITEMS().id("iron_plate").unwrap()));
+        assert!(!research.consume(technology("logistics"), science));
+
+        for _ in 0..9 {assert!(research.consume(automation, science))};
+        assert!(research.needs(science));
+        assert_eq!(research.progress(), [(science, 9, 10)]);
+        assert!(research.consume(automation, science));
+        assert!(research.is_researched(automation));
+        assert!(research.current().is_none() && !research.needs(science));
+        assert!(!research.consume(automation, science));
+    }
+
+    #[test]
+    fn researched_technologies_unlock_their_recipes() {
+        test_content();
+        let mut research = Research::default();
+        assert!(!research.is_recipe_unlocked(recipe("assembling_machine")));
+        assert!(research.is_recipe_unlocked(recipe("box")));
+
+        research.complete(technology("automation"));
+        assert!(research.is_recipe_unlocked(recipe("assembling_machine")));
+        assert!(research.is_recipe_unlocked(recipe("manipulator")));
+        assert!(!research.is_recipe_unlocked(recipe("pipe")));
+    }
+}
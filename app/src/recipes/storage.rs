@@ -1,7 +1,7 @@
-use crate::bytes::{BytesCoder, AsFromBytes};
+use crate::{bytes::{BytesCoder, AsFromBytes}, content::manifest::id_maps, world::clock::Stopwatch};
 
 use super::{item::{PossibleItem, Item}, recipe::{ActiveRecipe, Recipe}};
-use std::{fmt::Debug, time::Instant};
+use std::fmt::Debug;
 
 pub trait Storage {
     fn storage(&self) -> & [PossibleItem];
@@ -39,7 +39,13 @@ pub trait Storage {
     fn is_empty(&self) -> bool {self.storage().iter().all(|item| item.0.is_none())}
 
     fn take_first_existing(&mut self, max_count: u32) -> Option<(Item, usize)> {
+        self.take_first_matching(max_count, &|_| true)
+    }
+
+    /// Same as [`Storage::take_first_existing`], but only takes items whose id passes the filter.
+    fn take_first_matching(&mut self, max_count: u32, filter: &dyn Fn(u32) -> bool) -> Option<(Item, usize)> {
         for (i, possible_item) in self.mut_storage().iter_mut().enumerate() {
+            if !possible_item.0.is_some_and(|item| filter(item.id())) {continue};
             let Some(item) = possible_item.try_take(max_count) else {continue};
             return Some((item, i))
         }
@@ -108,7 +114,7 @@ pub trait Storage {
     fn start_recipe(&mut self, recipe: &Recipe) -> Option<ActiveRecipe> {
         if self.is_items_exist(&recipe.ingredients[..]) {
             self.remove_items(&recipe.ingredients[..]);
-            return Some(ActiveRecipe::new(Instant::now(), recipe.clone()));
+            return Some(ActiveRecipe::new(Stopwatch::start(), recipe.clone()));
         }
         None
     }
@@ -151,7 +157,7 @@ impl<const N: usize> BytesCoder for [PossibleItem; N] {
         let mut bytes = Vec::new();
         self.iter().enumerate().for_each(|(index, item)| {
             let Some(item) = item.0 else {return};
-            bytes.extend(ItemHeader::new(index as u32, item.id(), item.count).as_bytes());
+            bytes.extend(ItemHeader::new(index as u32, id_maps().items.saved(item.id()), item.count).as_bytes());
         });
         bytes.into()
     }
@@ -160,7 +166,7 @@ impl<const N: usize> BytesCoder for [PossibleItem; N] {
         let mut storage: [PossibleItem; N] = [PossibleItem::new_none(); N];
         bytes.chunks(12).for_each(|header_bytes| {
             let header = ItemHeader::from_bytes(header_bytes);
-            storage[header.index as usize] = PossibleItem::new(header.id, header.count);
+            storage[header.index as usize] = PossibleItem::new(id_maps().items.runtime(header.id), header.count);
         });
         storage
     }
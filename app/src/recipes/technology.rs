@@ -0,0 +1,210 @@
+use std::{collections::{HashMap, HashSet}, path::{Path, PathBuf}, sync::OnceLock, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::content_loader::{default_content, error::{ContentError, ContentErrors}, indices::{resolve, sort_unique, GamePath}};
+
+use super::{item::Item, items::ITEMS, recipe::Recipe, recipes::{ItemStackFile, RECIPES}};
+
+fn one_second() -> f32 {1.0}
+
+/// `technologies/<name>.json`, the technology is named by the file.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TechnologyFile {
+    /// Technologies that must be researched first
+    #[serde(default)]
+    pub prerequisites: Vec<String>,
+    /// Science items consumed by labs
+    pub cost: Vec<ItemStackFile>,
+    /// Seconds a lab works on one science item
+    #[serde(default = "one_second")]
+    pub time: f32,
+    /// Recipe ids, the variants of a recipe with `#tag` ingredients are unlocked with it
+    #[serde(default)]
+    pub unlocks: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Technology {
+    pub id: u32,
+    pub name: String,
+    pub prerequisites: Vec<u32>,
+    pub cost: Vec<Item>,
+    pub time: Duration,
+    pub unlocks: Vec<u32>,
+}
+
+#[derive(Debug, Default)]
+pub struct Technologies {
+    pub all: Vec<Technology>,
+    pub indices: HashMap<String, u32>,
+    /// Recipes that some technology unlocks, the rest are known from the start
+    locked: HashSet<u32>,
+    /// Items in the cost of any technology
+    science: HashSet<u32>,
+}
+
+impl Technologies {
+    /// `files` are `(name, prefix, file, path)`.
+    /// A broken technology is still added, without the references that could not be found.
+    /// Of the technologies with the same name only the first is kept.
+    pub fn new(files: Vec<(String, String, TechnologyFile, PathBuf)>, errors: &mut ContentErrors) -> Result<Self, ContentError> {
+        let files = sort_unique(files, "technology", |(name, _, _, path)| (name.as_str(), path.as_path()), errors)?;
+        let indices: HashMap<String, u32> = files.iter().enumerate()
+            .map(|(id, (name, ..))| (name.clone(), id as u32))
+            .collect();
+        let recipes = RECIPES().all;
+
+        let mut all = Vec::with_capacity(files.len());
+        let mut paths = Vec::with_capacity(files.len());
+        for (id, (name, prefix, file, path)) in files.into_iter().enumerate() {
+            if file.cost.is_empty() {errors.report(ContentError::invalid(&path, "cost", "the technology costs nothing"))?};
+            if file.time.is_nan() || file.time <= 0.0 {errors.report(ContentError::invalid(&path, "time", "the time is not positive"))?};
+            let mut prerequisites = Vec::new();
+            for (i, prerequisite) in file.prerequisites.iter().enumerate() {
+                let found = resolve(&indices, &prefix, prerequisite)
+                    .ok_or_else(|| ContentError::missing(&path, format!("prerequisites[{}]", i), "technology", prerequisite));
+                prerequisites.extend(errors.check(found)?.copied());
+            }
+            let mut cost = Vec::new();
+            for (i, stack) in file.cost.iter().enumerate() {
+                let found = resolve(&ITEMS().indices, &prefix, &stack.item).map(|&id| Item::new(id, stack.count))
+                    .ok_or_else(|| ContentError::missing(&path, format!("cost[{}].item", i), "item", &stack.item));
+                cost.extend(errors.check(found)?);
+            }
+            let mut unlocks = Vec::new();
+            for (i, unlock) in file.unlocks.iter().enumerate() {
+                // Recipes of the same pack first, like `resolve`.
+                let in_pack = if prefix.is_empty() || unlock.starts_with('@') {Vec::new()}
+                    else {Self::recipe_ids(recipes, &format!("{}{}", prefix, unlock))};
+                let ids = if in_pack.is_empty() {Self::recipe_ids(recipes, unlock)} else {in_pack};
+                if ids.is_empty() {
+                    errors.report(ContentError::missing(&path, format!("unlocks[{}]", i), "recipe", unlock))?;
+                }
+                unlocks.extend(ids);
+            }
+            let time = Duration::from_secs_f32(file.time.max(0.0));
+            all.push(Technology { id: id as u32, name, prerequisites, cost, time, unlocks });
+            paths.push(path);
+        }
+
+        if let Some(technology) = all.iter().find(|technology| Self::requires(&all, technology.id, technology.id)) {
+            errors.report(ContentError::invalid(&paths[technology.id as usize], "prerequisites", "the technology requires itself"))?;
+        }
+        let locked = all.iter().flat_map(|technology| technology.unlocks.iter().copied()).collect();
+        let science = all.iter().flat_map(|technology| technology.cost.iter().map(|item| item.id())).collect();
+        Ok(Self { all, indices, locked, science })
+    }
+
+    /// The recipe named `name` and its `#tag` variants.
+    fn recipe_ids(recipes: &[Recipe], name: &str) -> Vec<u32> {
+        let variant = format!("{}/", name);
+        recipes.iter()
+            .filter(|recipe| recipe.name == name || recipe.name.starts_with(&variant))
+            .map(|recipe| recipe.id)
+            .collect()
+    }
+
+    /// Whether `id` is among the prerequisites of `of`, directly or through other technologies.
+    fn requires(all: &[Technology], of: u32, id: u32) -> bool {
+        let mut stack = all[of as usize].prerequisites.clone();
+        let mut seen = HashSet::new();
+        while let Some(current) = stack.pop() {
+            if current == id {return true};
+            if seen.insert(current) {stack.extend(&all[current as usize].prerequisites)};
+        }
+        false
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Technology> {
+        self.all.get(id as usize)
+    }
+
+    pub fn id(&self, name: &str) -> Option<u32> {
+        self.indices.get(name).copied()
+    }
+
+    pub fn is_locked(&self, recipe_id: u32) -> bool {
+        self.locked.contains(&recipe_id)
+    }
+
+    pub fn is_science(&self, item_id: u32) -> bool {
+        self.science.contains(&item_id)
+    }
+}
+
+pub fn load_technology_file(path: &Path) -> Result<TechnologyFile, ContentError> {
+    let data = std::fs::read(path).map_err(|err| ContentError::io(path, err))?;
+    serde_json::from_slice(&data).map_err(|err| ContentError::json(path, err))
+}
+
+pub fn load_technologies(content: &[GamePath<PathBuf>], errors: &mut ContentErrors) -> Result<Technologies, ContentError> {
+    let mut files = Vec::new();
+    for technologies in content.iter().map(|c| c.join("technologies/")) {
+        for file in technologies.files() {
+            let Some((name, path)) = errors.check(file)? else {continue};
+            let Some(technology) = errors.check(load_technology_file(&path))? else {continue};
+            files.push((name, technologies.prefix().to_string(), technology, path));
+        }
+    }
+    Technologies::new(files, errors)
+}
+
+static TECHNOLOGIES_CONTAINER: OnceLock<Technologies> = OnceLock::new();
+
+/// Loads the technologies of the base game and of the active content packs.
+/// Must be called after the recipes are initialized.
+pub fn init_technologies(content: &[GamePath<PathBuf>], errors: &mut ContentErrors) -> Result<(), ContentError> {
+    TECHNOLOGIES_CONTAINER.set(load_technologies(content, errors)?).expect("Technologies are already initialized");
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+pub fn TECHNOLOGIES() -> &'static Technologies {
+    TECHNOLOGIES_CONTAINER.get_or_init(|| load_technologies(&default_content(), &mut ContentErrors::fail_fast()).unwrap_or_else(|err| {
+        eprintln!("Failed to load technologies: {}", err);
+        Technologies::default()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::content_loader::test_content;
+
+    use super::*;
+
+    fn file(prerequisites: &[&str]) -> TechnologyFile {
+        TechnologyFile {
+            prerequisites: prerequisites.iter().map(|p| p.to_string()).collect(),
+            cost: vec![ItemStackFile { item: "science_pack".to_string(), count: 1 }],
+            time: 1.0,
+            unlocks: vec![],
+        }
+    }
+
+    fn entry(name: &str, path: &str, file: TechnologyFile) -> (String, String, TechnologyFile, PathBuf) {
+        (name.to_string(), String::new(), file, PathBuf::from(path))
+    }
+
+    #[test]
+    fn duplicate_names_are_rejected() {
+        test_content();
+        let files = || vec![entry("b", "b.json", file(&[])), entry("a", "first/a.json", file(&[])), entry("a", "second/a.json", file(&["b"]))];
+        let err = Technologies::new(files(), &mut ContentErrors::fail_fast()).unwrap_err();
+        assert!(matches!(err, ContentError::Duplicate { kind: "technology", ref name, .. } if name == "a"));
+
+        let mut errors = ContentErrors::collect_all();
+        let technologies = Technologies::new(files(), &mut errors).unwrap();
+        assert_eq!(errors.into_errors().len(), 1);
+        assert_eq!(technologies.all.len(), 2);
+        assert!(technologies.get(technologies.id("a").unwrap()).unwrap().prerequisites.is_empty());
+    }
+
+    #[test]
+    fn prerequisite_cycles_are_reported() {
+        test_content();
+        let files = vec![entry("a", "a.json", file(&["b"])), entry("b", "b.json", file(&["a"]))];
+        let err = Technologies::new(files, &mut ContentErrors::fail_fast()).unwrap_err();
+        assert!(matches!(err, ContentError::Invalid { ref field, .. } if field == "prerequisites"));
+    }
+}
@@ -0,0 +1,99 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use crate::{content::manifest::{init_providers, Providers}, content_loader::{error::{ContentError, ContentErrors}, indices::GamePath}, recipes::{fluids::init_fluids, items::{init_items, ItemFile}, recipe::init_crafters, recipes::{init_recipes, RecipeFile}, technology::init_technologies}, voxels::{block::functions::{init_functions, Function}, live_voxels::{DesiarializeLiveVoxel, LiveVoxelRegistrator, NewLiveVoxel, LIVE_VOXEL_REGISTER}}};
+
+/// Everything mods add to the game during their `init`.
+#[derive(Default)]
+pub struct Registrator {
+    pub c: HashMap<String, NewLiveVoxel>,
+    pub from_bytes: HashMap<String, DesiarializeLiveVoxel>,
+    /// Block functions usable in `on_break` and `on_set` of block files
+    pub functions: HashMap<String, Function>,
+    pub items: Vec<ItemFile>,
+    pub recipes: Vec<RecipeFile>,
+    /// Crafter categories usable in `crafter` of recipe files
+    pub crafters: Vec<String>,
+    /// Content pack of the mod that is being initialized
+    pub pack: Option<String>,
+    pub providers: Providers,
+}
+
+impl Registrator {
+    pub fn new() -> Self {Self::default()}
+
+    /// Entries registered after this call are recorded as provided by the pack.
+    pub fn set_pack(&mut self, pack: &str) {
+        self.pack = Some(pack.to_string());
+    }
+
+    fn provided(providers: &mut HashMap<String, String>, pack: &Option<String>, name: &str) {
+        if let Some(pack) = pack {
+            providers.insert(name.to_string(), pack.clone());
+        }
+    }
+
+    pub fn live_voxel(&mut self, name: &str, new: NewLiveVoxel, from_bytes: DesiarializeLiveVoxel) {
+        Self::provided(&mut self.providers.live_voxels, &self.pack, name);
+        self.c.insert(name.to_string(), new);
+        self.from_bytes.insert(name.to_string(), from_bytes);
+    }
+
+    pub fn function(&mut self, name: &str, function: Function) {
+        self.functions.insert(name.to_string(), function);
+    }
+
+    pub fn item(&mut self, item: ItemFile) {
+        Self::provided(&mut self.providers.items, &self.pack, &item.id);
+        self.items.push(item);
+    }
+
+    pub fn recipe(&mut self, recipe: RecipeFile) {
+        Self::provided(&mut self.providers.recipes, &self.pack, &recipe.id);
+        self.recipes.push(recipe);
+    }
+
+    pub fn crafter(&mut self, name: &str) {
+        self.crafters.push(name.to_string());
+    }
+
+    /// Items registered by mods as `(prefix, path, file)`, the path only names the mod in errors.
+    pub fn item_files(&self) -> Vec<(String, PathBuf, ItemFile)> {
+        self.items.iter().map(|file| (String::new(), mod_path(&self.providers.items, &file.id), file.clone())).collect()
+    }
+
+    /// Recipes registered by mods as `(prefix, path, file)`, the path only names the mod in errors.
+    pub fn recipe_files(&self) -> Vec<(String, PathBuf, RecipeFile)> {
+        self.recipes.iter().map(|file| (String::new(), mod_path(&self.providers.recipes, &file.id), file.clone())).collect()
+    }
+
+    /// Hands everything registered by mods over to the game.
+    /// `content` are the roots of the base game and the active content packs.
+    /// Must be called once, before the content is loaded.
+    pub fn install(self, content: &[GamePath<PathBuf>]) -> Result<(), ContentError> {
+        self.install_with(content, &mut ContentErrors::fail_fast())
+    }
+
+    /// Same as [`Registrator::install`], `errors` decides whether broken entries stop the loading.
+    pub fn install_with(self, content: &[GamePath<PathBuf>], errors: &mut ContentErrors) -> Result<(), ContentError> {
+        let (items, recipes) = (self.item_files(), self.recipe_files());
+        init_functions(self.functions);
+        init_crafters(&self.crafters);
+        init_items(items, content, errors)?;
+        init_fluids(content, errors)?;
+        init_recipes(recipes, content, errors)?;
+        init_technologies(content, errors)?;
+        init_providers(self.providers);
+        unsafe {
+            LIVE_VOXEL_REGISTER = Some(LiveVoxelRegistrator {
+                new: self.c,
+                deserialize: self.from_bytes,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Stands in for the path of content registered by a mod.
+fn mod_path(providers: &HashMap<String, String>, name: &str) -> PathBuf {
+    PathBuf::from(format!("mod {}", providers.get(name).map_or("", String::as_str)))
+}
\ No newline at end of file
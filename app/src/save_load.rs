@@ -27,6 +27,13 @@ const REGION_VOLUME: usize = REGION_SQUARE*WORLD_HEIGHT;
 const REGION_MAGIC_NUMBER: u64 = 0x4474_304E_7AD7_835A;
 const REGION_FORMAT_VERSION: u32 = 2;
 
+/// Same as `bincode::deserialize`, but fails if any bytes are left over,
+/// so the bytes of an older, shorter layout are not taken for the current one.
+pub fn decode_exact<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> bincode::Result<T> {
+    use bincode::Options;
+    bincode::DefaultOptions::new().with_fixint_encoding().reject_trailing_bytes().deserialize(bytes)
+}
+
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum RegionFormatType {
@@ -49,7 +56,7 @@ pub struct RegionCoords(i32, i32);
 impl RegionCoords {
     #[inline]
     pub fn filename(&self) -> String {
-        self.0.to_string() + "_" + &self.1.to_string() + ".bin"
+        format!("{}_{}.bin", self.0, self.1)
     }
 }
 
@@ -0,0 +1,154 @@
+use std::{collections::HashMap, path::{Path, PathBuf}, sync::{Arc, OnceLock}};
+
+use rhai::{module_resolvers::DummyModuleResolver, CallFnOptions, Dynamic, Engine, EvalAltResult, FuncArgs, Scope, AST};
+
+use crate::content_loader::{error::{ContentError, ContentErrors}, indices::{check_duplicate, GamePath}};
+
+use self::world::{register_api, Access, ScriptWorld};
+
+pub mod world;
+
+/// A compiled script from the `scripts/` folder of the base game or of a content pack.
+pub struct Script {
+    pub path: PathBuf,
+    /// Pack prefix used to resolve the block names the script refers to
+    pub prefix: String,
+    ast: AST,
+}
+
+impl std::fmt::Debug for Script {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Script({:?})", self.path)
+    }
+}
+
+impl Script {
+    pub fn compile(path: &Path, prefix: &str) -> Result<Self, ContentError> {
+        let source = std::fs::read_to_string(path).map_err(|err| ContentError::io(path, err))?;
+        let mut ast = ENGINE().compile(source).map_err(|err| ContentError::Script {
+            path: path.to_path_buf(),
+            line: err.position().line(),
+            message: err.err_type().to_string(),
+        })?;
+        ast.set_source(path.to_string_lossy().as_ref());
+        Ok(Self { path: path.to_path_buf(), prefix: prefix.to_string(), ast })
+    }
+
+    pub fn has_fn(&self, name: &str) -> bool {
+        self.ast.iter_functions().any(|f| f.name == name)
+    }
+
+    /// Calls a script function if it is defined.
+    /// `world` is only reachable from the script while the call runs.
+    /// Errors are reported with the file and the line and turn into `None`.
+    pub fn call(&self, name: &str, access: Access, this: Option<&mut Dynamic>, args: impl FuncArgs) -> Option<Dynamic> {
+        if !self.has_fn(name) {return None};
+        let world = ScriptWorld::new(access, &self.prefix);
+        let mut options = CallFnOptions::new().eval_ast(false);
+        if let Some(this) = this {options = options.bind_this_ptr(this)};
+        let mut args_values = vec![Dynamic::from(world.clone())];
+        args.parse(&mut args_values);
+        let result = ENGINE().call_fn_with_options::<Dynamic>(options, &mut Scope::new(), &self.ast, name, args_values);
+        world.close();
+        result.map_err(|err| self.report(name, &err)).ok()
+    }
+
+    fn report(&self, name: &str, err: &EvalAltResult) {
+        // The line of the innermost call is the one that failed.
+        let mut err = err;
+        while let EvalAltResult::ErrorInFunctionCall(_, _, inner, _) = err {
+            err = inner;
+        }
+        match err.position().line() {
+            Some(line) => eprintln!("{:?}:{}: {}: {}", self.path, line, name, err),
+            None => eprintln!("{:?}: {}: {}", self.path, name, err),
+        }
+    }
+}
+
+pub type Scripts = HashMap<String, Arc<Script>>;
+
+/// Compiles every script, scripts are named by the file stem like the other content.
+pub fn load_scripts(paths: &[GamePath<PathBuf>], errors: &mut ContentErrors) -> Result<Scripts, ContentError> {
+    let mut seen = HashMap::<String, PathBuf>::new();
+    let mut scripts = Scripts::new();
+    for path in paths {
+        for file in path.files() {
+            let Some((name, file)) = errors.check(file)? else {continue};
+            if errors.check(check_duplicate(&mut seen, "script", &name, &file))?.is_none() {continue};
+            let Some(script) = errors.check(Script::compile(&file, path.prefix()))? else {continue};
+            scripts.insert(name, Arc::new(script));
+        }
+    }
+    Ok(scripts)
+}
+
+/// Scripts can't import files and are stopped if they run for too long.
+fn new_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_module_resolver(DummyModuleResolver::new());
+    engine.disable_symbol("eval");
+    engine.set_max_operations(100_000);
+    engine.set_max_call_levels(32);
+    engine.set_max_expr_depths(64, 32);
+    engine.set_max_string_size(4096);
+    engine.set_max_array_size(1024);
+    engine.set_max_map_size(256);
+    register_api(&mut engine);
+    engine
+}
+
+static ENGINE_CONTAINER: OnceLock<Engine> = OnceLock::new();
+
+#[allow(non_snake_case)]
+pub fn ENGINE() -> &'static Engine {
+    ENGINE_CONTAINER.get_or_init(new_engine)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{content::Content, content_loader::{indices::Indices, test_content}, voxels::chunks::Chunks};
+
+    use super::*;
+
+    fn write(dir: &Path, name: &str, source: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, source).unwrap();
+        path
+    }
+
+    #[test]
+    fn syntax_errors_are_content_errors() {
+        let root = tempfile::tempdir().unwrap();
+        let scripts = root.path().join("scripts");
+        std::fs::create_dir_all(&scripts).unwrap();
+        let broken = write(&scripts, "broken.rhai", "fn on_use(world) {\n    let x = ;\n}");
+        write(&scripts, "lamp.rhai", "fn on_use(world) { 1 }");
+
+        let err = Script::compile(&broken, "").unwrap_err();
+        assert!(matches!(err, ContentError::Script { ref path, line: Some(2), .. } if *path == broken));
+
+        let mut errors = ContentErrors::collect_all();
+        let loaded = load_scripts(&[GamePath { path: scripts, prefix: None }], &mut errors).unwrap();
+        assert_eq!(loaded.keys().collect::<Vec<_>>(), ["lamp"]);
+        assert_eq!(errors.into_errors().len(), 1);
+    }
+
+    #[test]
+    fn runtime_errors_do_not_panic() {
+        test_content();
+        let root = tempfile::tempdir().unwrap();
+        let (game, world) = (root.path().join("game"), root.path().join("world"));
+        std::fs::create_dir_all(game.join("blocks")).unwrap();
+        std::fs::create_dir_all(&world).unwrap();
+        let indices = Indices { block: HashMap::new(), models: HashMap::new(), animated_models: HashMap::new(), content: vec![] };
+        let chunks = Chunks::new(Arc::new(Content::new(&indices, &world).unwrap()), 1, 1, 1, 0, 0, 0);
+
+        let path = write(root.path(), "script.rhai", "fn fails(world) { throw \"broken\"; }\nfn spins(world) { loop {} }\nfn works(world) { 42 }");
+        let script = Script::compile(&path, "").unwrap();
+        assert!(script.call("fails", Access::Chunks(&chunks), None, ()).is_none());
+        assert!(script.call("spins", Access::Chunks(&chunks), None, ()).is_none());
+        assert!(script.call("missing", Access::Chunks(&chunks), None, ()).is_none());
+        assert_eq!(script.call("works", Access::Chunks(&chunks), None, ()).unwrap().as_int(), Ok(42));
+    }
+}
@@ -0,0 +1,185 @@
+use std::sync::{Arc, Mutex};
+
+use rhai::{Engine, EvalAltResult, FLOAT, INT};
+
+use crate::{content::tags::Tag, content_loader::indices::resolve, coords::global_coord::GlobalCoord, player::inventory::PlayerInventory, recipes::{item::Item, items::ITEMS, storage::Storage}, threads::scheduler, voxels::chunks::Chunks, world::{clock, World}};
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// What a script call can reach.
+pub enum Access<'a> {
+    /// Block callbacks can change blocks and give items to the player
+    World { world: &'a World, inventory: Option<Arc<Mutex<PlayerInventory>>> },
+    /// Live voxel updates only work with the chunks and the storages in them
+    Chunks(&'a Chunks),
+}
+
+#[derive(Clone, Copy)]
+enum Target {
+    World(*const World),
+    Chunks(*const Chunks),
+}
+
+// The pointers are only dereferenced while the call that created them runs.
+unsafe impl Send for Target {}
+
+struct Handle {
+    target: Option<Target>,
+    inventory: Option<Arc<Mutex<PlayerInventory>>>,
+}
+
+/// The `world` argument of the script functions.
+/// It is closed when the call returns, so a copy kept by a script can't reach the world later.
+#[derive(Clone)]
+pub struct ScriptWorld {
+    handle: Arc<Mutex<Handle>>,
+    prefix: Arc<str>,
+}
+
+impl ScriptWorld {
+    pub(super) fn new(access: Access, prefix: &str) -> Self {
+        let (target, inventory) = match access {
+            Access::World { world, inventory } => (Target::World(world), inventory),
+            Access::Chunks(chunks) => (Target::Chunks(chunks), None),
+        };
+        Self { handle: Arc::new(Mutex::new(Handle { target: Some(target), inventory })), prefix: Arc::from(prefix) }
+    }
+
+    pub(super) fn close(&self) {
+        *self.handle.lock().unwrap() = Handle { target: None, inventory: None };
+    }
+
+    fn chunks<T>(&self, f: impl FnOnce(&Chunks) -> T) -> ScriptResult<T> {
+        let handle = self.handle.lock().unwrap();
+        match handle.target {
+            Some(Target::World(world)) => Ok(f(unsafe {&(*world).chunks})),
+            Some(Target::Chunks(chunks)) => Ok(f(unsafe {&*chunks})),
+            None => Err("world is used after the call has returned".into()),
+        }
+    }
+
+    fn world<T>(&self, name: &str, f: impl FnOnce(&World) -> T) -> ScriptResult<T> {
+        let handle = self.handle.lock().unwrap();
+        match handle.target {
+            Some(Target::World(world)) => Ok(f(unsafe {&*world})),
+            Some(Target::Chunks(_)) => Err(format!("{} is only available in block callbacks", name).into()),
+            None => Err("world is used after the call has returned".into()),
+        }
+    }
+
+    fn block_id(&self, chunks: &Chunks, name: &str) -> ScriptResult<u32> {
+        resolve(&chunks.content.block_indexes, &self.prefix, name).copied()
+            .ok_or_else(|| format!("unknown block \"{}\"", name).into())
+    }
+
+    /// Items are looked up in the pack of the script first, like blocks.
+    fn item(&self, name: &str, count: INT) -> ScriptResult<Item> {
+        if count < 0 {return Err(format!("negative item count {}", count).into())};
+        resolve(&ITEMS().indices, &self.prefix, name).map(|&id| Item::new(id, count as u32))
+            .ok_or_else(|| format!("unknown item \"{}\"", name).into())
+    }
+}
+
+fn coord(x: INT, y: INT, z: INT) -> GlobalCoord {
+    GlobalCoord::new(x as i32, y as i32, z as i32)
+}
+
+/// Adds as many items as fit and returns how many were added.
+fn add(storage: &mut dyn Storage, item: &Item) -> INT {
+    let remainder = storage.add(item, false).map_or(0, |r| r.count);
+    (item.count - remainder) as INT
+}
+
+fn count(storage: &dyn Storage, id: u32) -> u32 {
+    storage.storage().iter().map(|possible_item| possible_item.contains(id)).sum()
+}
+
+pub(super) fn register_api(engine: &mut Engine) {
+    engine.register_type_with_name::<ScriptWorld>("World");
+
+    // Item names are looked up in the pack of the script first, like block names.
+    engine.register_fn("item_id", |world: &mut ScriptWorld, name: &str| {
+        resolve(&ITEMS().indices, &world.prefix, name).map_or(-1, |id| *id as INT)
+    });
+    engine.register_fn("item_name", |id: INT| {
+        ITEMS().get(id as u32).map(|item_type| item_type.name.clone()).unwrap_or_default()
+    });
+
+    engine.register_fn("item_has_tag", |world: &mut ScriptWorld, name: &str, tag: &str| {
+        resolve(&ITEMS().indices, &world.prefix, name).is_some_and(|&id| Tag::get(tag).is_some_and(|tag| ITEMS().has_tag(id, tag)))
+    });
+
+    // Block id at the coordinates, -1 if the chunk is not loaded.
+    engine.register_fn("block", |world: &mut ScriptWorld, x: INT, y: INT, z: INT| {
+        world.chunks(|chunks| chunks.voxel_global(coord(x, y, z)).map_or(-1, |voxel| voxel.id as INT))
+    });
+    engine.register_fn("block_id", |world: &mut ScriptWorld, name: &str| {
+        world.chunks(|chunks| resolve(&chunks.content.block_indexes, &world.prefix, name).map_or(-1, |id| *id as INT))
+    });
+    engine.register_fn("block_has_tag", |world: &mut ScriptWorld, x: INT, y: INT, z: INT, tag: &str| {
+        let Some(tag) = Tag::get(tag) else {return Ok(false)};
+        world.chunks(|chunks| chunks.voxel_global(coord(x, y, z))
+            .is_some_and(|voxel| chunks.content.blocks[voxel.id as usize].load().has_tag(tag)))
+    });
+    // Places a block if the place is empty.
+    engine.register_fn("set_block", |world: &mut ScriptWorld, x: INT, y: INT, z: INT, name: &str| -> ScriptResult<bool> {
+        let id = world.chunks(|chunks| world.block_id(chunks, name))??;
+        world.world("set_block", |w| {
+            let xyz = coord(x, y, z);
+            if !w.chunks.is_air_global(xyz) {return false};
+            w.chunks.set_block(xyz, id, None);
+            w.light.on_block_set(&w.chunks, xyz.x, xyz.y, xyz.z, id);
+            true
+        })
+    });
+    engine.register_fn("break_block", |world: &mut ScriptWorld, x: INT, y: INT, z: INT| {
+        world.world("break_block", |w| {
+            let xyz = coord(x, y, z);
+            if w.voxel(&xyz).is_none_or(|voxel| voxel.id == 0) {return false};
+            w.break_voxel(&xyz);
+            true
+        })
+    });
+
+    // Storage of the live voxel at the coordinates, multiblocks are reached from any of their voxels.
+    engine.register_fn("storage_count", |world: &mut ScriptWorld, x: INT, y: INT, z: INT, name: &str| -> ScriptResult<INT> {
+        let item = world.item(name, 0)?;
+        world.chunks(|chunks| {
+            let Some(storage) = chunks.master_live_voxel(coord(x, y, z)).and_then(|lv| lv.storage()) else {return 0};
+            let storage = storage.lock().unwrap();
+            count(&*storage, item.id()) as INT
+        })
+    });
+    engine.register_fn("storage_add", |world: &mut ScriptWorld, x: INT, y: INT, z: INT, name: &str, n: INT| -> ScriptResult<INT> {
+        let item = world.item(name, n)?;
+        world.chunks(|chunks| {
+            let Some(storage) = chunks.master_live_voxel(coord(x, y, z)).and_then(|lv| lv.storage()) else {return 0};
+            let mut storage = storage.lock().unwrap();
+            add(&mut *storage, &item)
+        })
+    });
+    engine.register_fn("storage_remove", |world: &mut ScriptWorld, x: INT, y: INT, z: INT, name: &str, n: INT| -> ScriptResult<INT> {
+        let item = world.item(name, n)?;
+        world.chunks(|chunks| {
+            let Some(storage) = chunks.master_live_voxel(coord(x, y, z)).and_then(|lv| lv.storage()) else {return 0};
+            let mut storage = storage.lock().unwrap();
+            let removed = item.count.min(count(&*storage, item.id()));
+            storage.remove(&Item::new(item.id(), removed));
+            removed as INT
+        })
+    });
+
+    engine.register_fn("give_player", |world: &mut ScriptWorld, name: &str, n: INT| -> ScriptResult<INT> {
+        let item = world.item(name, n)?;
+        let inventory = world.handle.lock().unwrap().inventory.clone();
+        let Some(inventory) = inventory else {return Err("give_player is only available in block callbacks of the player".into())};
+        let remainder = inventory.lock().unwrap().add(&item, true).map_or(0, |r| r.count);
+        Ok((item.count - remainder) as INT)
+    });
+
+    // State of the simulation, only the debug window and the keybinds control it.
+    engine.register_fn("game_tick", || clock::now() as INT);
+    engine.register_fn("ups", || scheduler::stats().ups as FLOAT);
+    engine.register_fn("game_speed", || scheduler::speed() as FLOAT);
+    engine.register_fn("is_game_paused", scheduler::is_paused);
+}
\ No newline at end of file
@@ -1,12 +1,27 @@
 use serde::{Deserialize, Serialize};
 use graphics_engine::setting::GraphicSetting;
 
+use crate::{lang::FALLBACK_LOCALE, world::clock::TICKS_PER_SECOND};
+
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Setting {
     pub is_greedy_meshing: bool,
     pub render_radius: u32,
     pub graphic: GraphicSetting,
+    #[serde(default = "default_language")]
+    pub language: String,
+    /// Simulation updates per second at normal speed
+    #[serde(default = "default_ups")]
+    pub ups: u32,
+}
+
+fn default_language() -> String {
+    FALLBACK_LOCALE.to_string()
+}
+
+fn default_ups() -> u32 {
+    TICKS_PER_SECOND as u32
 }
 
 
@@ -16,6 +31,8 @@ impl Default for Setting {
             is_greedy_meshing: true,
             render_radius: 3,
             graphic: Default::default(),
+            language: default_language(),
+            ups: default_ups(),
         }
     }
 }
\ No newline at end of file
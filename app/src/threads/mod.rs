@@ -8,6 +8,7 @@ pub mod renderer;
 pub mod world_loader;
 pub mod voxel_data_updater;
 pub mod save;
+pub mod scheduler;
 
 
 pub struct Threads {
@@ -1,6 +1,6 @@
 use std::{thread::{self, JoinHandle}, sync::{Arc, Mutex, Condvar}, time::Duration};
 
-use crate::{world::World, unsafe_mutex::UnsafeMutex, save_load::{WorldSaver}, player::player::Player};
+use crate::{recipes::research::save_research, world::{clock::save_clock, World}, unsafe_mutex::UnsafeMutex, save_load::{WorldSaver}, player::player::Player};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum SaveState {
@@ -40,6 +40,8 @@ pub fn spawn(
             let player = unsafe {player.lock_unsafe()}.unwrap();
             let player_saver = unsafe {world_saver.player.lock_unsafe()}.unwrap();
             player_saver.save_player(&player);
+            save_research();
+            save_clock();
 
             if *save_state == SaveState::WorldExit {break};
             *save_state = SaveState::Saved;
@@ -0,0 +1,227 @@
+use std::{sync::{atomic::{AtomicBool, Ordering}, Mutex}, thread, time::{Duration, Instant}};
+
+use crate::world::clock::TICKS_PER_SECOND;
+
+/// Speeds the player can choose, relative to the UPS target
+pub const SPEEDS: [f32; 5] = [0.5, 1.0, 2.0, 4.0, 8.0];
+/// Ticks run at once to catch up after a slow tick, the rest of the lag is dropped
+pub const MAX_CATCH_UP: u32 = 10;
+/// How long the scheduler sleeps at most, so that pausing and speed changes apply quickly
+const POLL: Duration = Duration::from_millis(10);
+/// Stats are measured over this window
+const STATS_WINDOW: Duration = Duration::from_secs(1);
+
+/// Updates per second measured over the last second.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SchedulerStats {
+    pub ups: f32,
+    /// Average time a tick took to simulate
+    pub tick_time: Duration,
+    pub max_tick_time: Duration,
+    /// Ticks that were not run because the simulation lagged too far behind
+    pub dropped: u64,
+}
+
+#[derive(Debug)]
+struct Scheduler {
+    /// Ticks run in a real second at normal speed, the game time of a tick doesn't change
+    ups_target: u32,
+    speed: f32,
+    paused: bool,
+    /// Ticks requested by single steps while paused
+    steps: u32,
+    stats: SchedulerStats,
+}
+
+impl Scheduler {
+    /// Real time between two ticks.
+    fn interval(&self) -> Duration {
+        Duration::from_secs_f32(1.0 / (self.ups_target as f32 * self.speed))
+    }
+
+    fn target(&self) -> f32 {
+        if self.paused {0.0} else {self.ups_target as f32 * self.speed}
+    }
+
+    /// Ticks due at `now`, `next_tick` is moved past them and the ticks that can't be caught up are added to `dropped`.
+    fn due_ticks(&mut self, now: Instant, next_tick: &mut Instant, dropped: &mut u64) -> u32 {
+        if self.paused {
+            // The time spent in pause is not caught up.
+            *next_tick = now + POLL;
+            return std::mem::take(&mut self.steps);
+        }
+        if now < *next_tick {return 0};
+
+        let interval = self.interval();
+        let due = (now - *next_tick).as_nanos() / interval.as_nanos() + 1;
+        let ticks = due.min(MAX_CATCH_UP as u128) as u32;
+        if due > ticks as u128 {
+            *dropped += (due - ticks as u128) as u64;
+            *next_tick = now + interval;
+        } else {
+            *next_tick += interval * ticks;
+        }
+        ticks
+    }
+}
+
+static SCHEDULER: Mutex<Scheduler> = Mutex::new(Scheduler {
+    ups_target: TICKS_PER_SECOND as u32,
+    speed: 1.0,
+    paused: false,
+    steps: 0,
+    stats: SchedulerStats { ups: 0.0, tick_time: Duration::ZERO, max_tick_time: Duration::ZERO, dropped: 0 },
+});
+
+/// Ticks in a real second at normal speed. Below [`TICKS_PER_SECOND`] the game runs slower than the real time.
+pub fn ups_target() -> u32 {
+    SCHEDULER.lock().unwrap().ups_target
+}
+
+pub fn set_ups_target(ups: u32) {
+    SCHEDULER.lock().unwrap().ups_target = ups.max(1);
+}
+
+/// Updates per second the scheduler is aiming for right now, zero while paused.
+pub fn target_ups() -> f32 {
+    SCHEDULER.lock().unwrap().target()
+}
+
+pub fn speed() -> f32 {
+    SCHEDULER.lock().unwrap().speed
+}
+
+/// Clamped to the range of [`SPEEDS`].
+pub fn set_speed(speed: f32) {
+    SCHEDULER.lock().unwrap().speed = speed.clamp(SPEEDS[0], SPEEDS[SPEEDS.len() - 1]);
+}
+
+pub fn is_paused() -> bool {
+    SCHEDULER.lock().unwrap().paused
+}
+
+/// Forgets the steps that were not run yet.
+pub fn set_paused(paused: bool) {
+    let mut scheduler = SCHEDULER.lock().unwrap();
+    scheduler.paused = paused;
+    scheduler.steps = 0;
+}
+
+/// Runs `ticks` more ticks while paused, does nothing while running.
+pub fn step(ticks: u32) {
+    let mut scheduler = SCHEDULER.lock().unwrap();
+    if scheduler.paused {scheduler.steps = scheduler.steps.saturating_add(ticks)};
+}
+
+pub fn stats() -> SchedulerStats {
+    SCHEDULER.lock().unwrap().stats
+}
+
+/// Ticks to run now, `next_tick` is moved past them.
+fn due_ticks(next_tick: &mut Instant, dropped: &mut u64) -> u32 {
+    SCHEDULER.lock().unwrap().due_ticks(Instant::now(), next_tick, dropped)
+}
+
+/// Runs `tick` at the UPS target times the speed on the current thread until `exit` is set.
+/// A lagging simulation runs up to [`MAX_CATCH_UP`] ticks at once, then it runs slower than the real time.
+/// Worlds are always opened unpaused.
+pub fn run(exit: &AtomicBool, mut tick: impl FnMut()) {
+    set_paused(false);
+    let mut next_tick = Instant::now();
+    let mut window_start = Instant::now();
+    let (mut ticks, mut busy, mut max_tick_time, mut dropped) = (0u32, Duration::ZERO, Duration::ZERO, 0u64);
+    while !exit.load(Ordering::Relaxed) {
+        for _ in 0..due_ticks(&mut next_tick, &mut dropped) {
+            let start = Instant::now();
+            tick();
+            let tick_time = start.elapsed();
+            ticks += 1;
+            busy += tick_time;
+            max_tick_time = max_tick_time.max(tick_time);
+        }
+
+        let window = window_start.elapsed();
+        if window >= STATS_WINDOW {
+            SCHEDULER.lock().unwrap().stats = SchedulerStats {
+                ups: ticks as f32 / window.as_secs_f32(),
+                tick_time: if ticks == 0 {Duration::ZERO} else {busy / ticks},
+                max_tick_time,
+                dropped,
+            };
+            window_start = Instant::now();
+            (ticks, busy, max_tick_time, dropped) = (0, Duration::ZERO, Duration::ZERO, 0);
+        }
+
+        thread::sleep(next_tick.saturating_duration_since(Instant::now()).min(POLL));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduler() -> Scheduler {
+        Scheduler { ups_target: TICKS_PER_SECOND as u32, speed: 1.0, paused: false, steps: 0, stats: SchedulerStats::default() }
+    }
+
+    #[test]
+    fn ticks_run_on_time() {
+        let mut scheduler = scheduler();
+        let interval = scheduler.interval();
+        let start = Instant::now();
+        let (mut next_tick, mut dropped) = (start, 0);
+        assert_eq!(scheduler.due_ticks(start, &mut next_tick, &mut dropped), 1);
+        assert_eq!(next_tick, start + interval);
+        assert_eq!(scheduler.due_ticks(start + interval / 2, &mut next_tick, &mut dropped), 0);
+        assert_eq!(scheduler.due_ticks(start + interval, &mut next_tick, &mut dropped), 1);
+
+        scheduler.speed = 2.0;
+        assert_eq!(scheduler.due_ticks(start + interval * 2, &mut next_tick, &mut dropped), 1);
+        assert_eq!(next_tick, start + interval * 2 + scheduler.interval());
+        assert_eq!(dropped, 0);
+    }
+
+    #[test]
+    fn missed_ticks_are_caught_up() {
+        let mut scheduler = scheduler();
+        let interval = scheduler.interval();
+        let start = Instant::now();
+        let (mut next_tick, mut dropped) = (start, 0);
+        assert_eq!(scheduler.due_ticks(start + interval * 4, &mut next_tick, &mut dropped), 5);
+        assert_eq!(next_tick, start + interval * 5);
+        assert_eq!(dropped, 0);
+    }
+
+    #[test]
+    fn lag_past_the_catch_up_is_dropped() {
+        let mut scheduler = scheduler();
+        let interval = scheduler.interval();
+        let start = Instant::now();
+        let (mut next_tick, mut dropped) = (start, 0);
+        let now = start + interval * 24;
+        assert_eq!(scheduler.due_ticks(now, &mut next_tick, &mut dropped), MAX_CATCH_UP);
+        assert_eq!(dropped, 25 - MAX_CATCH_UP as u64);
+        assert_eq!(next_tick, now + interval);
+    }
+
+    #[test]
+    fn pause_runs_only_the_steps() {
+        let mut scheduler = scheduler();
+        scheduler.paused = true;
+        let interval = scheduler.interval();
+        let start = Instant::now();
+        let (mut next_tick, mut dropped) = (start, 0);
+        assert_eq!(scheduler.due_ticks(start + interval * 100, &mut next_tick, &mut dropped), 0);
+        assert_eq!(next_tick, start + interval * 100 + POLL);
+
+        scheduler.steps = 3;
+        assert_eq!(scheduler.due_ticks(start + interval * 101, &mut next_tick, &mut dropped), 3);
+        assert_eq!(scheduler.due_ticks(start + interval * 102, &mut next_tick, &mut dropped), 0);
+
+        // The time spent in pause is not caught up.
+        scheduler.paused = false;
+        let now = next_tick;
+        assert_eq!(scheduler.due_ticks(now, &mut next_tick, &mut dropped), 1);
+        assert_eq!(dropped, 0);
+    }
+}
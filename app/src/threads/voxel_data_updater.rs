@@ -1,24 +1,30 @@
-use std::{sync::{Arc, atomic::{Ordering, AtomicBool}}, thread::{self, JoinHandle}, time::{Instant, Duration}};
+use std::{sync::{Arc, atomic::AtomicBool}, thread::{self, JoinHandle}};
 
-use crate::{world::{World}};
+use crate::world::{clock, World};
 
+use super::scheduler;
+
+/// Updates every live voxel once a tick, see [`scheduler`] for when the ticks run.
+/// The voxels of a chunk are updated in the order of their indices, so that the same world gives the same result.
 pub fn spawn(world: Arc<World>, exit: Arc<AtomicBool>) -> JoinHandle<()> {
     thread::spawn(move || {
-        loop {
-            if exit.load(Ordering::Relaxed) {break};
-            let now = Instant::now();
+        scheduler::run(&exit, || {
             for chunk in unsafe {&*(world.chunks.chunks.get())}.iter() {
                 let Some(chunk) = chunk else {continue};
 
-                if !chunk.live_voxels.0.read().unwrap().is_empty() {
-                    chunk.save(true);
-                }
+                let mut live_voxels = chunk.live_voxels.0.read().unwrap().iter()
+                    .map(|(index, vd)| (*index, vd.clone()))
+                    .collect::<Vec<_>>();
+                if live_voxels.is_empty() {continue};
+                chunk.save(true);
+                live_voxels.sort_unstable_by_key(|(index, _)| *index);
 
-                for vd in chunk.live_voxels.0.read().unwrap().values() {
+                for (_, vd) in live_voxels {
                     vd.update(&world.chunks);
                 }
             }
-            thread::sleep(Duration::from_millis(100u64.saturating_sub(now.elapsed().as_millis() as u64)));
-        }
+            world.chunks.power.lock().unwrap().tick();
+            clock::advance();
+        });
     })
 }
\ No newline at end of file
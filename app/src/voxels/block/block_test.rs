@@ -1,10 +1,13 @@
-use std::{collections::HashMap, fmt::Debug};
+use std::{collections::HashMap, fmt::Debug, path::Path, sync::Arc};
 
 use serde::{Deserialize, Serialize};
 use crate::Indices;
-use crate::{direction::Direction, player::player::Player, recipes::{item::Item}, coords::{coord::Coord, global_coord::GlobalCoord}, world::World};
+use crate::content::tags::{Tag, TagSet};
+use crate::content_loader::{error::ContentError, indices::resolve};
+use crate::scripting::{world::Access, Script, Scripts};
+use crate::{direction::Direction, player::player::Player, recipes::{item::Item, item_filter::ItemFilter, items::ITEMS}, coords::{coord::Coord, global_coord::GlobalCoord}, world::World};
 
-use super::{block_type::BlockType, functions::{Function, FUNCTIONS}};
+use super::{block_type::BlockType, drops::{roll_drops, to_drops, BlockDrop, DropFile}, functions::{Function, FUNCTIONS}};
 
 fn one() -> usize {1}
 
@@ -34,8 +37,12 @@ pub struct BlockFile {
     pub id: String,
     pub block_type: BlockTypeFile,
 
+    /// Item of the block, by default the item that places it
     #[serde(default)]
     pub item_id: Option<String>,
+    /// Items given to the player who breaks the block with `player_add_item`
+    #[serde(default)]
+    pub drops: Vec<DropFile>,
     #[serde(default)]
     pub emission: [u8; 3],
 
@@ -50,56 +57,97 @@ pub struct BlockFile {
     pub is_light_passing: bool,
     #[serde(default)]
     pub live_voxel: Option<String>,
+    /// Written with or without `#`, tag files in `tags/` can add more
     #[serde(default)]
-    pub is_glass: bool,
+    pub tags: Vec<String>,
+    /// Items a manipulator block picks up, an item id or `#tag`
     #[serde(default)]
+    pub item_filter: Option<String>,
+    /// Deprecated, the same as the `glass` tag
+    #[serde(default, skip_serializing)]
+    pub is_glass: bool,
+    /// Deprecated, the same as the `ore` tag
+    #[serde(default, skip_serializing)]
     pub is_ore: bool,
 
     #[serde(default)]
     pub on_break: Vec<String>,
     #[serde(default)]
     pub on_set: Vec<String>,
+    /// Script from the `scripts/` folder with the `on_break`, `on_set` and `update` functions of the block
+    #[serde(default)]
+    pub script: Option<String>,
 }
 
 
-pub fn to_block(block_file: BlockFile, indices: &Indices, co_indices: &HashMap<String, u32>, id: u32) -> Block {
+/// `prefix` is the namespace of the content pack the block comes from, it is empty for the base game.
+/// `path` is only used for errors.
+pub fn to_block(block_file: BlockFile, indices: &Indices, co_indices: &HashMap<String, u32>, scripts: &Scripts, prefix: &str, id: u32, path: &Path) -> Result<Block, ContentError> {
+    let find = |map, field: &str, kind, name: &str| resolve(map, prefix, name).copied()
+        .ok_or_else(|| ContentError::missing(path, field, kind, name));
+
     let block_type = match &block_file.block_type {
         BlockTypeFile::Block { faces } => {
             let faces = match faces {
                 Faces::One(texture) => {
-                    let id = *indices.block.get(texture).unwrap();
+                    let id = find(&indices.block, "block_type.block.faces", "block texture", texture)?;
                     [id, id, id, id, id, id]
                 },
                 Faces::All(textures) => {
-                    [0, 1, 2, 3, 4, 5].map(|i| {
-                        *indices.block.get(&textures[i%textures.len()]).unwrap()
-                    })
+                    if textures.is_empty() {
+                        return Err(ContentError::missing(path, "block_type.block.faces", "block texture", ""));
+                    }
+                    let mut faces = [0; 6];
+                    for (i, face) in faces.iter_mut().enumerate() {
+                        let index = i%textures.len();
+                        *face = find(&indices.block, &format!("block_type.block.faces[{}]", index), "block texture", &textures[index])?;
+                    }
+                    faces
                 },
             };
             BlockType::Block { faces }
         }
         BlockTypeFile::ComplexObject { name } => {
-            BlockType::ComplexObject { id: *co_indices.get(name).unwrap() }
+            BlockType::ComplexObject { id: find(co_indices, "block_type.complex_object.name", "complex object", name)? }
         },
         BlockTypeFile::Model { name } => {
-            BlockType::Model { id: *indices.models.get(name).unwrap() }
+            BlockType::Model { id: find(&indices.models, "block_type.model.name", "model", name)? }
         },
         BlockTypeFile::AnimatedModel { name } => {
-            BlockType::AnimatedModel { id: *indices.animated_models.get(name).unwrap() }
+            BlockType::AnimatedModel { id: find(&indices.animated_models, "block_type.animated_model.name", "animated model", name)? }
         },
         BlockTypeFile::None => BlockType::None,
     };
 
-    let on_block_break = block_file.on_break.into_iter()
-        .map(|name| *FUNCTIONS().get(&name).unwrap()).collect();
+    let functions = |field: &str, names: Vec<String>| names.into_iter().enumerate()
+        .map(|(i, name)| FUNCTIONS().get(&name).copied()
+            .ok_or_else(|| ContentError::missing(path, format!("{}[{}]", field, i), "function", name)))
+        .collect::<Result<Box<[Function]>, ContentError>>();
 
-    let on_block_set = block_file.on_set.into_iter()
-        .map(|name| *FUNCTIONS().get(&name).unwrap()).collect();
+    let on_block_break = functions("on_break", block_file.on_break)?;
+    let on_block_set = functions("on_set", block_file.on_set)?;
+    let item_id = match &block_file.item_id {
+        Some(name) => Some(*resolve(&ITEMS().indices, prefix, name).ok_or_else(|| ContentError::missing(path, "item_id", "item", name))?),
+        None => ITEMS().all.iter()
+            .find(|item| item.prefix == prefix && item.block.as_deref() == Some(&block_file.id))
+            .map(|item| item.id),
+    };
+    let drops = to_drops(block_file.drops, item_id, prefix, path)?;
+    let item_filter = block_file.item_filter.as_deref()
+        .map(|name| ItemFilter::parse(prefix, name).ok_or_else(|| ContentError::missing(path, "item_filter", "item", name)))
+        .transpose()?;
+    let mut tags: TagSet = block_file.tags.iter().map(|tag| Tag::new(tag)).collect();
+    if block_file.is_glass {tags.insert(Tag::GLASS)};
+    if block_file.is_ore {tags.insert(Tag::ORE)};
+    let script = block_file.script.as_deref()
+        .map(|name| resolve(scripts, prefix, name).cloned()
+            .ok_or_else(|| ContentError::missing(path, "script", "script", name)))
+        .transpose()?;
 
-    Block {
+    Ok(    Block {
         base: BlockBase {
             id,
-            item_id: None,
+            item_id,
             emission: block_file.emission,
             block_type,
             width: block_file.width,
@@ -107,12 +155,14 @@ pub fn to_block(block_file: BlockFile, indices: &Indices, co_indices: &HashMap<S
             depth: block_file.depth,
             is_light_passing: block_file.is_light_passing,
             live_voxel: block_file.live_voxel,
-            is_glass: block_file.is_glass,
-            is_ore: block_file.is_ore
+            tags,
+            item_filter,
+            drops,
         },
         on_block_break,
-        on_block_set
-    }
+        on_block_set,
+        script,
+    })
 }
 
 pub fn test_serde_block() {
@@ -120,16 +170,20 @@ pub fn test_serde_block() {
         id: String::from("iron_ore"),
         block_type: BlockTypeFile::Block { faces: Faces::One(String::from("iron_ore")) },
         item_id: None,
+        drops: Vec::new(),
         emission: [0, 0, 0],
         width: 1,
         height: 1,
         depth: 1,
         is_light_passing: false,
         live_voxel: None,
+        tags: Vec::new(),
+        item_filter: None,
         is_glass: false,
         is_ore: false,
         on_break: Vec::new(),
         on_set: Vec::new(),
+        script: None,
     };
 
     std::fs::write("./block.json", serde_json::to_vec_pretty(&b).unwrap()).unwrap();
@@ -148,8 +202,16 @@ pub struct BlockBase {
     
     pub is_light_passing: bool,
     pub live_voxel: Option<String>,
-    pub is_glass: bool,
-    pub is_ore: bool,
+    pub tags: TagSet,
+    pub item_filter: Option<ItemFilter>,
+    pub drops: Box<[BlockDrop]>,
+}
+
+impl BlockBase {
+    /// Items of one break, rolled from the drop table.
+    pub fn roll_drops(&self) -> Vec<Item> {
+        roll_drops(&self.drops)
+    }
 }
 
 pub struct Block {
@@ -157,7 +219,8 @@ pub struct Block {
 
     // pub on_use: Box<[Function]>,
     pub on_block_break: Box<[Function]>,
-    pub on_block_set: Box<[Function]>
+    pub on_block_set: Box<[Function]>,
+    pub script: Option<Arc<Script>>,
 }
 
 impl Debug for Block {
@@ -175,14 +238,29 @@ impl Block {
 
     #[inline]
     pub fn on_block_break(&self, world: &World, player: &mut Player, xyz: &GlobalCoord, dir: &Direction) -> bool {
-        if self.on_block_break.is_empty() {return false;}
+        if self.on_block_break.is_empty() && !self.has_script_fn("on_break") {return false;}
         self.on_block_break.iter().all(|f| f(&self.base, world, player, xyz, dir))
+            && self.call_script("on_break", world, player, xyz)
     }
 
     #[inline]
     pub fn on_block_set(&self, world: &World, player: &mut Player, xyz: &GlobalCoord, dir: &Direction) -> bool {
-        if self.on_block_set.is_empty() {return false;}
+        if self.on_block_set.is_empty() && !self.has_script_fn("on_set") {return false;}
         self.on_block_set.iter().all(|f| f(&self.base, world, player, xyz, dir))
+            && self.call_script("on_set", world, player, xyz)
+    }
+
+    fn has_script_fn(&self, name: &str) -> bool {
+        self.script.as_ref().is_some_and(|script| script.has_fn(name))
+    }
+
+    /// Script callbacks run after the block functions, they fail on errors or when they return `false`.
+    fn call_script(&self, name: &str, world: &World, player: &mut Player, xyz: &GlobalCoord) -> bool {
+        let Some(script) = &self.script else {return true};
+        if !script.has_fn(name) {return true};
+        let access = Access::World { world, inventory: Some(player.inventory()) };
+        script.call(name, access, None, (xyz.x as i64, xyz.y as i64, xyz.z as i64))
+            .is_some_and(|result| result.as_bool().unwrap_or(true))
     }
 
     pub fn id(&self) -> u32 {self.base.id}
@@ -190,7 +268,11 @@ impl Block {
     pub fn is_light_passing(&self) -> bool {self.base.is_light_passing}
     pub fn block_type(&self) -> &BlockType {&self.base.block_type}
     pub fn live_voxel(&self) -> Option<&str> {self.base.live_voxel.as_deref()}
-    pub fn is_glass(&self) -> bool {self.base.is_glass}
+    pub fn is_glass(&self) -> bool {self.base.tags.contains(Tag::GLASS)}
+    #[inline]
+    pub fn has_tag(&self, tag: Tag) -> bool {self.base.tags.contains(tag)}
+    pub fn tags(&self) -> &TagSet {&self.base.tags}
+    pub fn item_filter(&self) -> Option<&ItemFilter> {self.base.item_filter.as_ref()}
     
     pub fn width(&self) -> usize {1}
     pub fn height(&self) -> usize {1}
@@ -201,7 +283,7 @@ impl Block {
     pub fn is_voxel_size(&self) -> bool {false}
 
     pub fn ore(&self) -> Option<Item> {
-        if self.base.is_ore {
+        if self.base.tags.contains(Tag::ORE) {
             if let Some(id) = self.base.item_id {
                 return Some(Item::new(id, 1));
             }
@@ -0,0 +1,128 @@
+use std::path::Path;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{content_loader::{error::ContentError, indices::resolve}, recipes::{item::Item, items::ITEMS}};
+
+fn one() -> DropCountFile {DropCountFile::Fixed(1)}
+fn always() -> f32 {1.0}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum DropCountFile {
+    Fixed(u32),
+    /// Both ends included
+    Range([u32; 2]),
+}
+
+/// One entry of the `drops` of a block file, `"self"` is the item of the block.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum DropFile {
+    Item(String),
+    Stack {
+        item: String,
+        #[serde(default = "one")]
+        count: DropCountFile,
+        #[serde(default = "always")]
+        chance: f32,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockDrop {
+    pub item: u32,
+    pub min: u32,
+    pub max: u32,
+    pub chance: f32,
+}
+
+impl BlockDrop {
+    pub fn roll(&self, rng: &mut impl Rng) -> Option<Item> {
+        if self.chance < 1.0 && !rng.gen_bool(self.chance as f64) {return None};
+        let count = rng.gen_range(self.min..=self.max);
+        (count > 0).then(|| Item::new(self.item, count))
+    }
+}
+
+/// `item_id` is the item of the block, it is needed for `"self"`.
+/// Other items are looked up in the pack of the block first.
+pub fn to_drops(files: Vec<DropFile>, item_id: Option<u32>, prefix: &str, path: &Path) -> Result<Box<[BlockDrop]>, ContentError> {
+    files.into_iter().enumerate().map(|(i, file)| {
+        let field = format!("drops[{}]", i);
+        let (name, count, chance) = match file {
+            DropFile::Item(name) => (name, DropCountFile::Fixed(1), 1.0),
+            DropFile::Stack { item, count, chance } => (item, count, chance),
+        };
+        let item = if name == "self" {item_id} else {resolve(&ITEMS().indices, prefix, &name).copied()};
+        let item = item.ok_or_else(|| ContentError::missing(path, format!("{}.item", field), "item", &name))?;
+        let (min, max) = match count {
+            DropCountFile::Fixed(count) => (count, count),
+            DropCountFile::Range([min, max]) if min <= max => (min, max),
+            DropCountFile::Range(_) => return Err(ContentError::invalid(path, format!("{}.count", field), "the range is reversed")),
+        };
+        if !(0.0..=1.0).contains(&chance) {
+            return Err(ContentError::invalid(path, format!("{}.chance", field), "the chance is not between 0 and 1"));
+        }
+        Ok(BlockDrop { item, min, max, chance })
+    }).collect()
+}
+
+pub fn roll_drops(drops: &[BlockDrop]) -> Vec<Item> {
+    let mut rng = rand::thread_rng();
+    drops.iter().filter_map(|drop| drop.roll(&mut rng)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::content_loader::test_content;
+    use super::*;
+
+    fn stack(item: &str, count: DropCountFile, chance: f32) -> DropFile {
+        DropFile::Stack { item: item.to_string(), count, chance }
+    }
+
+    fn field(result: Result<Box<[BlockDrop]>, ContentError>) -> String {
+        match result.unwrap_err() {
+            ContentError::MissingReference { field, .. } | ContentError::Invalid { field, .. } => field,
+            err => panic!("unexpected error: {}", err),
+        }
+    }
+
+    #[test]
+    fn self_and_named_items_are_resolved() {
+        test_content();
+        let drops = to_drops(vec![DropFile::Item("self".into()), stack("box", DropCountFile::Range([1, 3]), 0.5)], Some(7), "", Path::new("")).unwrap();
+        assert_eq!((drops[0].item, drops[0].min, drops[0].max, drops[0].chance), (7, 1, 1, 1.0));
+        assert_eq!((drops[1].item, drops[1].min, drops[1].max, drops[1].chance), (ITEMS().id("box").unwrap(), 1, 3, 0.5));
+    }
+
+    #[test]
+    fn broken_drops_are_reported_with_their_field() {
+        test_content();
+        let path = Path::new("");
+        assert_eq!(field(to_drops(vec![stack("self", DropCountFile::Range([3, 1]), 1.0)], Some(0), "", path)), "drops[0].count");
+        assert_eq!(field(to_drops(vec![stack("self", DropCountFile::Fixed(1), 1.5)], Some(0), "", path)), "drops[0].chance");
+        assert_eq!(field(to_drops(vec![stack("self", DropCountFile::Fixed(1), f32::NAN)], Some(0), "", path)), "drops[0].chance");
+        assert_eq!(field(to_drops(vec![DropFile::Item("self".into()), DropFile::Item("no_such_item".into())], Some(0), "", path)), "drops[1].item");
+        assert_eq!(field(to_drops(vec![DropFile::Item("self".into())], None, "", path)), "drops[0].item");
+    }
+
+    #[test]
+    fn roll_stays_in_range() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let drop = BlockDrop { item: 1, min: 2, max: 4, chance: 1.0 };
+        for _ in 0..100 {
+            let item = drop.roll(&mut rng).unwrap();
+            assert_eq!(item.id(), 1);
+            assert!((2..=4).contains(&item.count));
+        }
+        let never = BlockDrop { item: 1, min: 1, max: 1, chance: 0.0 };
+        assert!((0..100).all(|_| never.roll(&mut rng).is_none()));
+        let empty = BlockDrop { item: 1, min: 0, max: 0, chance: 1.0 };
+        assert!(empty.roll(&mut rng).is_none());
+    }
+}
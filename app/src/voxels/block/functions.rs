@@ -4,8 +4,27 @@ use crate::{coords::global_coord::GlobalCoord, direction::Direction, player::pla
 
 use super::block_test::BlockBase;
 
-pub fn player_add_item(base: &BlockBase, _world: &World, player: &mut Player, _xyz: &GlobalCoord, _dir: &Direction) -> bool {
-    player.inventory().lock().unwrap().add(&Item::new(base.item_id.expect("Not have item_id"), 1), true);
+/// Gives the drops of the block to the player, it has to come before the function that breaks the block.
+/// Fails if the inventory can't take all of them, so the block stays.
+/// Multiblocks drop the items of their master block from any part.
+pub fn player_add_item(base: &BlockBase, world: &World, player: &mut Player, xyz: &GlobalCoord, _dir: &Direction) -> bool {
+    let master = world.chunks.master_live_voxel(*xyz).map(|live_voxel| live_voxel.id).filter(|id| *id != base.id);
+    let drops = match master {
+        Some(id) => world.chunks.content.blocks[id as usize].load().base.roll_drops(),
+        None => base.roll_drops(),
+    };
+    let inventory = player.inventory();
+    let mut inventory = inventory.lock().unwrap();
+    let mut added = Vec::with_capacity(drops.len());
+    for item in &drops {
+        let overflow = inventory.add(item, true).map_or(0, |overflow| overflow.count);
+        added.push(Item::new(item.id(), item.count - overflow));
+        if overflow > 0 {
+            // The block is not broken while the drops don't fit, nothing is lost.
+            added.iter().filter(|item| item.count > 0).for_each(|item| {inventory.remove(item);});
+            return false;
+        }
+    }
     true
 }
 
@@ -57,18 +76,29 @@ pub fn on_multiblock_set(base: &BlockBase, world: &World, _player: &mut Player,
 
 pub type Function = &'static (dyn Fn(&BlockBase, &World, &mut Player, &GlobalCoord, &Direction) -> bool + Send + Sync);
 
+pub(crate) fn builtin_functions() -> HashMap<String, Function> {
+    let mut fns = HashMap::<String, Function>::new();
+
+    fns.insert(String::from("on_set"), &on_set);
+    fns.insert(String::from("on_break"), &on_break);
+    fns.insert(String::from("on_multiblock_break"), &on_multiblock_break);
+    fns.insert(String::from("on_multiblock_set"), &on_multiblock_set);
+    fns.insert(String::from("player_add_item"), &player_add_item);
+    fns
+}
+
 static FUNCTIONS_CONTAINER: OnceLock<HashMap<String, Function>> = OnceLock::new();
 
+/// Adds the block functions registered by mods to the builtin ones.
+pub fn init_functions(registered: HashMap<String, Function>) {
+    let mut fns = builtin_functions();
+    registered.into_iter().for_each(|(name, function)| {
+        if fns.insert(name.clone(), function).is_some() {panic!("Duplicate block function \"{}\"", name)};
+    });
+    if FUNCTIONS_CONTAINER.set(fns).is_err() {panic!("Block functions are already initialized")};
+}
+
 #[allow(non_snake_case)]
 pub fn FUNCTIONS() -> &'static HashMap<String, Function> {
-    FUNCTIONS_CONTAINER.get_or_init(|| {
-        let mut fns = HashMap::<String, Function>::new();
-
-        fns.insert(String::from("on_set"), &on_set);
-        fns.insert(String::from("on_break"), &on_break);
-        fns.insert(String::from("on_multiblock_break"), &on_multiblock_break);
-        fns.insert(String::from("on_multiblock_set"), &on_multiblock_set);
-        fns.insert(String::from("player_add_item"), &on_multiblock_set);
-        fns
-    })
+    FUNCTIONS_CONTAINER.get_or_init(builtin_functions)
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
 pub mod block_type;
 pub mod block_test;
-pub mod functions;
\ No newline at end of file
+pub mod functions;
+pub mod drops;
\ No newline at end of file
@@ -2,7 +2,7 @@ use std::{sync::{Arc, Mutex, atomic::{AtomicBool, Ordering, AtomicI32}}, cell::U
 
 use itertools::{iproduct, Itertools};
 
-use crate::{content::Content, direction::Direction, light::light_map::Light, vec_none, coords::{global_coord::GlobalCoord, local_coord::LocalCoord, chunk_coord::ChunkCoord}};
+use crate::{content::Content, direction::Direction, light::light_map::Light, power::PowerGrid, vec_none, coords::{global_coord::GlobalCoord, local_coord::LocalCoord, chunk_coord::ChunkCoord}};
 
 use super::{chunk::{Chunk, LiveVoxels, CHUNK_SIZE}, live_voxels::{LiveVoxelBehavior, LiveVoxelContainer}, voxel::Voxel};
 
@@ -16,6 +16,7 @@ pub struct Chunks {
     // I tried to do this using safe code, but it kills performance by about 2 times
     pub chunks: UnsafeCell<Vec<Option<Arc<Chunk>>>>,
     pub chunks_awaiting_deletion: Arc<Mutex<Vec<Arc<Chunk>>>>,
+    pub power: Mutex<PowerGrid>,
 
     pub volume: i32,
     pub width: i32,
@@ -35,10 +36,12 @@ impl Chunks {
         let mut chunks: Vec<Option<Arc<Chunk>>> = vec![];
         for _ in 0..volume { chunks.push(None); }
 
+        let power = if content.without_power {PowerGrid::optional()} else {PowerGrid::new()};
         Chunks {
             content,
             chunks: UnsafeCell::new(chunks),
             chunks_awaiting_deletion: Arc::new(Mutex::new(Vec::new())),
+            power: Mutex::new(power),
             volume,
             width,
             height,
@@ -77,6 +80,13 @@ impl Chunks {
     #[inline] pub fn set_depth_with_offset(&self, value: i32) {
         self.depth_with_offset.store(value, Ordering::Relaxed)}
     
+    /// Marks the loaded chunks that contain any of the blocks so they are meshed again.
+    pub fn modify_with_blocks(&self, ids: &[u32]) {
+        unsafe {&*self.chunks.get()}.iter().flatten()
+            .filter(|chunk| chunk.voxels.0.iter().any(|voxel| ids.contains(&voxel.id())))
+            .for_each(|chunk| chunk.modify(true));
+    }
+
     pub fn translate(&self, ox: i32, oz: i32) -> Vec<(usize, usize)> {
         let mut indices = Vec::<(usize, usize)>::new();
         let chunks = unsafe {&mut *self.chunks.get()};
@@ -98,6 +108,7 @@ impl Chunks {
 
         for chunk in chunks.iter_mut() {
             let Some(chunk) = chunk.take() else {continue};
+            self.power.lock().unwrap().remove_chunk(chunk.xyz);
             if chunk.unsaved() {self.chunks_awaiting_deletion.lock().unwrap().push(chunk)}
         }
 
@@ -128,8 +139,8 @@ impl Chunks {
     pub fn set_block(&self, global: GlobalCoord, id: u32, direction: Option<&Direction>) {
         self.set_voxel(global, id);
         let Some(live_voxels) = self.live_voxels(global) else {return};
-        let block = &self.content.blocks[id as usize].base;
-        let Some(name) = &block.live_voxel else {return};
+        let block = self.content.blocks[id as usize].load();
+        let Some(name) = &block.base.live_voxel else {return};
         let local: LocalCoord = global.into();
         println!("{:?}", name);
         let live_voxel = self.content.live_voxel.new.get(name).unwrap()(direction.unwrap_or(&Direction::new_x()));
@@ -147,6 +158,8 @@ impl Chunks {
         let y_offset = (local.y == (CHUNK_SIZE-1) as u8) as i32 - (local.y == 0) as i32;
         let z_offset = (local.z == (CHUNK_SIZE-1) as u8) as i32 - (local.z == 0) as i32;
         chunk.set_voxel_id(local, id);
+        // Poles add themselves again on their first update.
+        self.power.lock().unwrap().remove_pole(global);
         chunk.modify(true);
         chunk.save(true);
         
@@ -241,7 +254,8 @@ impl Chunks {
         }
         self.set_voxel(coords[0], id);
 
-        let live_voxel_name = self.content.blocks[id as usize].live_voxel().unwrap_or("");
+        let block = self.content.blocks[id as usize].load();
+        let live_voxel_name = block.live_voxel().unwrap_or("");
         let voxels_data = self.live_voxels(coords[0]).unwrap();
         let live_voxel: Box<(dyn LiveVoxelBehavior)> = self.content.live_voxel.new.get(live_voxel_name)
             .map_or(Box::new(()), |f| { f(dir)});
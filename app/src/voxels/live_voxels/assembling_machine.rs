@@ -3,27 +3,56 @@ use graphics_engine::texture::TextureAtlas;
 
 use crate::coords::global_coord::GlobalCoord;
 use crate::direction::Direction;
+use crate::power::{footprint, unpowered};
+use crate::recipes::fluid::{FluidBox, Flow};
+use crate::recipes::fluid_storage::FluidStorage;
 use crate::recipes::item::PossibleItem;
 use crate::voxels::chunks::Chunks;
+use crate::world::clock;
 use crate::{live_voxel_default_deserialize, player_unlockable};
-use crate::recipes::recipe::{ActiveRecipe, Recipe};
+use crate::recipes::{recipe::{ActiveRecipe, LegacyActiveRecipe, Recipe}, research::is_recipe_unlocked};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use std::sync::Weak;
 use crate::{recipes::{item::{Item}, storage::Storage, recipes::RECIPES}, gui::{draw::Draw, my_widgets::{assembling_machine_slot::assembling_machine_slot, recipe::recipe}}, player::inventory::PlayerInventory};
-use crate::gui::my_widgets::container::container;
+use crate::gui::my_widgets::{container::container, fluid_bar::fluid_bar};
 
-use super::{LiveVoxelBehavior, LiveVoxelCreation, PlayerUnlockable};
+use super::{fluid_flow::flow, LiveVoxelBehavior, LiveVoxelCreation, PlayerUnlockable};
 
 const INGREDIENT_LENGTH: usize = 3;
-const RESULT_LENGTH: usize = 1;
+/// Recipes with more results can't be made in the assembling machine
+pub const RESULT_LENGTH: usize = 3;
 const TOTAL_LENGTH: usize = INGREDIENT_LENGTH+RESULT_LENGTH;
+/// Recipes with more fluid ingredients or results can't be made in the assembling machine
+pub const FLUID_INGREDIENT_LENGTH: usize = 2;
+pub const FLUID_RESULT_LENGTH: usize = 2;
+const FLUID_TOTAL_LENGTH: usize = FLUID_INGREDIENT_LENGTH+FLUID_RESULT_LENGTH;
+/// Fluid every box holds, recipes can't use more at once
+pub const FLUID_CAPACITY: f32 = 1000.0;
+
+/// Assembling machine of the saves from before the recipe files, with three ingredient slots,
+/// one result slot and no fluids. The recipe starts again.
+#[derive(Debug, Deserialize)]
+pub struct LegacyAssemblingMachine {
+    storage: [PossibleItem; INGREDIENT_LENGTH + 1],
+    active_recipe: Option<LegacyActiveRecipe>,
+}
+
+impl From<LegacyAssemblingMachine> for Arc<Mutex<AssemblingMachine>> {
+    fn from(legacy: LegacyAssemblingMachine) -> Self {
+        let mut machine = AssemblingMachine { active_recipe: legacy.active_recipe.map(ActiveRecipe::from), ..Default::default() };
+        // The result slot becomes the first of the result slots.
+        machine.storage[0..=INGREDIENT_LENGTH].copy_from_slice(&legacy.storage);
+        Arc::new(Mutex::new(machine))
+    }
+}
 
 impl LiveVoxelCreation for Arc<Mutex<AssemblingMachine>> {
     fn create(_: &Direction) -> Box<dyn LiveVoxelBehavior> {
         Box::new(Arc::new(Mutex::new(AssemblingMachine::default())))
     }
 
-    live_voxel_default_deserialize!(Arc<Mutex<AssemblingMachine>>);
+    live_voxel_default_deserialize!(Arc<Mutex<AssemblingMachine>>, LegacyAssemblingMachine);
 }
 
 impl LiveVoxelBehavior for Arc<Mutex<AssemblingMachine>> {
@@ -33,8 +62,18 @@ impl LiveVoxelBehavior for Arc<Mutex<AssemblingMachine>> {
         Some(self.clone())
     }
 
-    fn update(&self, _: &Chunks, _: GlobalCoord, _: &[GlobalCoord]) {
-        self.lock().unwrap().update();
+    fn fluid_storage(&self) -> Option<Arc<Mutex<dyn FluidStorage>>> {
+        Some(self.clone())
+    }
+
+    fn update(&self, chunks: &Chunks, coord: GlobalCoord, multiblock: &[GlobalCoord]) {
+        let mut machine = self.lock().unwrap();
+        let elapsed = clock::elapsed(&mut machine.last_update);
+        let blocks = footprint(&coord, multiblock);
+        let power = if machine.is_working() {AssemblingMachine::POWER} else {0.0};
+        let satisfaction = chunks.power.lock().unwrap().demand(blocks, power);
+        machine.update(elapsed, satisfaction, coord);
+        flow(chunks, blocks, &mut *machine, elapsed);
     }
 
     fn to_bytes(&self) -> Vec<u8> {
@@ -49,31 +88,81 @@ pub struct AssemblingMachine {
     #[serde(skip)]
     selected_recipe: Option<&'static Recipe>,
     active_recipe: Option<ActiveRecipe>,
+    last_update: Option<u64>,
+    /// Fluid ingredients followed by fluid results
+    fluid_boxes: [FluidBox; FLUID_TOTAL_LENGTH],
 }
 
 impl AssemblingMachine {
+    /// Watts used while crafting
+    pub const POWER: f32 = 75_000.0;
+
+    pub fn is_working(&self) -> bool {
+        self.active_recipe.as_ref().is_some_and(|active_recipe| !active_recipe.is_finished())
+    }
+
     pub fn selected_recipe(&self) -> Option<&'static Recipe> {
         self.selected_recipe
     }
 
     pub fn select_recipe(&mut self, index: usize) -> ([PossibleItem; TOTAL_LENGTH], Vec<Item>) {
-        self.selected_recipe = Some(&RECIPES().all[index]);
+        let recipe = &RECIPES().all[index];
+        self.selected_recipe = Some(recipe);
+        // Fluids can't be given back to the player, they are lost.
+        for (i, fluid_box) in self.fluid_boxes.iter_mut().enumerate() {
+            fluid_box.clear();
+            fluid_box.filter = if i < FLUID_INGREDIENT_LENGTH {
+                recipe.fluid_ingredients.get(i)
+            } else {
+                recipe.fluid_results.get(i - FLUID_INGREDIENT_LENGTH)
+            }.map(|fluid| fluid.id());
+        }
         let mut result = [PossibleItem::new_none(); TOTAL_LENGTH];
         std::mem::swap(&mut result, &mut self.storage);
         let ingredients = self.active_recipe.take().map_or(vec![], |ac| ac.recipe.ingredients);
         (result, ingredients)
     }
 
-    pub fn update(&mut self) {
-        if self.active_recipe.is_none() && self.selected_recipe.is_some() {
-            self.active_recipe = self.start_recipe(self.selected_recipe.unwrap());
+    /// Whether every fluid ingredient is in its box.
+    fn has_fluid_ingredients(&self, recipe: &Recipe) -> bool {
+        recipe.fluid_ingredients.len() <= FLUID_INGREDIENT_LENGTH && recipe.fluid_ingredients.iter()
+            .zip(&self.fluid_boxes[0..FLUID_INGREDIENT_LENGTH])
+            .all(|(fluid, fluid_box)| fluid_box.contains(fluid.id()) >= fluid.amount)
+    }
+
+    /// Crafts slower by the power satisfaction of its network.
+    pub fn update(&mut self, elapsed: Duration, satisfaction: f32, coord: GlobalCoord) {
+        if let Some(active_recipe) = self.active_recipe.as_mut().filter(|active_recipe| !active_recipe.is_finished()) {
+            active_recipe.delay(unpowered(elapsed, satisfaction));
+        }
+
+        if self.active_recipe.is_none() {
+            if let Some(recipe) = self.selected_recipe.filter(|r| is_recipe_unlocked(r.id) && self.has_fluid_ingredients(r)) {
+                self.active_recipe = self.start_recipe(recipe);
+                if self.active_recipe.is_some() {
+                    recipe.fluid_ingredients.iter().zip(&mut self.fluid_boxes[0..FLUID_INGREDIENT_LENGTH])
+                        .for_each(|(fluid, fluid_box)| {fluid_box.drain(fluid);});
+                }
+            }
         }
 
         let Some(active_recipe) = &self.active_recipe else {return};
-        if !active_recipe.is_finished() || !self.storage()[3].is_possible_add(&active_recipe.recipe.result) {return};
-        
-        let add_item = active_recipe.recipe.result;
-        self.mut_storage()[3].try_add_item(&add_item);
+        if !active_recipe.is_finished() {return};
+        let results = &active_recipe.recipe.results;
+        let fluid_results = &active_recipe.recipe.fluid_results;
+        if results.len() > RESULT_LENGTH || fluid_results.len() > FLUID_RESULT_LENGTH {return};
+        let result_slots = &self.storage[INGREDIENT_LENGTH..TOTAL_LENGTH];
+        if !results.iter().zip(result_slots).all(|(result, slot)| slot.is_possible_add(&result.item)) {return};
+        let fluid_result_boxes = &self.fluid_boxes[FLUID_INGREDIENT_LENGTH..FLUID_TOTAL_LENGTH];
+        if !fluid_results.iter().zip(fluid_result_boxes).all(|(fluid, fluid_box)| fluid_box.space(fluid.id()) >= fluid.amount) {return};
+
+        let mut rng = active_recipe.recipe.rng(&clock::block_salts(coord));
+        for (result, slot) in results.iter().zip(&mut self.storage[INGREDIENT_LENGTH..TOTAL_LENGTH]) {
+            if let Some(item) = result.roll(&mut rng) {slot.try_add_item(&item);}
+        }
+        for (fluid, fluid_box) in fluid_results.iter().zip(&mut self.fluid_boxes[FLUID_INGREDIENT_LENGTH..FLUID_TOTAL_LENGTH]) {
+            fluid_box.fill(fluid);
+        }
         self.active_recipe = None;
     }
 }
@@ -85,6 +174,11 @@ impl Default for AssemblingMachine {
             storage: [PossibleItem::new_none(); TOTAL_LENGTH],
             selected_recipe: None,
             active_recipe: None,
+            last_update: None,
+            fluid_boxes: std::array::from_fn(|i| {
+                let flow = if i < FLUID_INGREDIENT_LENGTH {Flow::Input} else {Flow::Output};
+                FluidBox::new(FLUID_CAPACITY, flow)
+            }),
         }
     }
 }
@@ -127,8 +221,9 @@ impl Storage for AssemblingMachine {
         Some(added_item)
     }
 
-    fn take_first_existing(&mut self, max_count: u32) -> Option<(Item, usize)> {
+    fn take_first_matching(&mut self, max_count: u32, filter: &dyn Fn(u32) -> bool) -> Option<(Item, usize)> {
         for (i, possible_item) in self.mut_storage()[INGREDIENT_LENGTH..TOTAL_LENGTH].iter_mut().enumerate() {
+            if !possible_item.0.is_some_and(|item| filter(item.id())) {continue};
             let Some(item) = possible_item.try_take(max_count) else {continue};
             return Some((item, i))
         }
@@ -137,6 +232,17 @@ impl Storage for AssemblingMachine {
 }
 
 
+impl FluidStorage for AssemblingMachine {
+    fn fluid_boxes(&self) -> &[FluidBox] {
+        &self.fluid_boxes
+    }
+
+    fn mut_fluid_boxes(&mut self) -> &mut [FluidBox] {
+        &mut self.fluid_boxes
+    }
+}
+
+
 impl Draw for AssemblingMachine {
     fn draw(&mut self, ui: &mut egui::Ui, atlas: Arc<TextureAtlas>, inventory: Arc<Mutex<PlayerInventory>>) {
         let mut task: Option<usize> = None;
@@ -144,11 +250,18 @@ impl Draw for AssemblingMachine {
         if let Some(selected_recipe) = selected_recipe {
             ui.horizontal(|ui| {
                 for (i, item) in self.storage().iter().enumerate() {
-                    if ui.add(assembling_machine_slot(&atlas, item, i, selected_recipe, i==3)).drag_started() {
+                    let (slot_id, result) = if i < INGREDIENT_LENGTH {(i, false)} else {(i - INGREDIENT_LENGTH, true)};
+                    if ui.add(assembling_machine_slot(&atlas, item, slot_id, selected_recipe, result)).drag_started() {
                         task = Some(i);
                     };
                 }
             });
+            // Only the boxes the recipe uses
+            let ingredients = self.fluid_boxes[0..FLUID_INGREDIENT_LENGTH].iter().take(selected_recipe.fluid_ingredients.len());
+            let results = self.fluid_boxes[FLUID_INGREDIENT_LENGTH..FLUID_TOTAL_LENGTH].iter().take(selected_recipe.fluid_results.len());
+            for fluid_box in ingredients.chain(results) {
+                ui.add(fluid_bar(fluid_box));
+            }
         }
         ui.vertical(|ui| {
             ui.add(container(|ui| {
@@ -158,7 +271,7 @@ impl Draw for AssemblingMachine {
                 };
                 ui.set_style(style);
                 ui.horizontal(|ui| {
-                    for i in RECIPES().assembler.all() {
+                    for i in RECIPES().assembler.all().iter().filter(|r| is_recipe_unlocked(r.id)) {
                         if ui.add(recipe(&atlas, i)).drag_started() {
                             let result = self.select_recipe(i.index);
                             for item in result.0 {
@@ -190,4 +303,27 @@ impl PlayerUnlockable for AssemblingMachine {
     fn get_mut_storage(&mut self) -> Option<&mut dyn Storage> {
         Some(self)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::save_load::decode_exact;
+
+    use super::*;
+
+    #[test]
+    fn baseline_assembling_machine_is_loaded() {
+        // Recipe of the baseline: index, id, duration, crafter, category, ingredients and one result.
+        let recipe = (0usize, 0u32, Duration::from_millis(300), 0b11u8, 0b1u8, vec![Item::new(1, 2)], Item::new(2, 1));
+        let storage = [PossibleItem::new(1, 4), PossibleItem::new_none(), PossibleItem::new_none(), PossibleItem::new(2, 6)];
+        let bytes = bincode::serialize(&(storage, Some((recipe,)))).unwrap();
+        let machine: AssemblingMachine = decode_exact(&<Arc<Mutex<AssemblingMachine>>>::from_bytes(&bytes).to_bytes()).unwrap();
+
+        assert_eq!(machine.storage[0].0, Some(Item::new(1, 4)));
+        assert_eq!(machine.storage[INGREDIENT_LENGTH].0, Some(Item::new(2, 6)));
+        assert!(machine.storage[INGREDIENT_LENGTH + 1..].iter().all(|slot| slot.0.is_none()));
+        let active = machine.active_recipe.unwrap();
+        assert_eq!(active.recipe.ingredients, [Item::new(1, 2)]);
+        assert_eq!(active.recipe.max_results(), [Item::new(2, 1)]);
+    }
+}
@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::recipes::{item::PossibleItem, items::ITEMS};
+
+/// Fuel burning in a machine, new items are taken from its fuel slot.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct Burner {
+    /// Burn time left of the last fuel item
+    left: Duration,
+    /// Burn time of the last fuel item
+    total: Duration,
+}
+
+impl Burner {
+    pub fn is_burning(&self) -> bool {
+        !self.left.is_zero()
+    }
+
+    /// Whether the burner has fuel left or can take some from the slot.
+    pub fn has_fuel(&self, slot: &PossibleItem) -> bool {
+        self.is_burning() || slot.0.is_some_and(|item| ITEMS().fuel(item.id()).is_some())
+    }
+
+    /// Burn time left of the last fuel item, from 1 down to 0.
+    pub fn progress(&self) -> f32 {
+        if self.total.is_zero() {return 0.0};
+        self.left.as_secs_f32() / self.total.as_secs_f32()
+    }
+
+    /// Burns the fuel for `time`, taking new fuel items as needed.
+    /// Returns less than `time` if the fuel ran out.
+    pub fn burn(&mut self, time: Duration, slot: &mut PossibleItem) -> Duration {
+        let mut burned = Duration::ZERO;
+        loop {
+            let step = self.left.min(time - burned);
+            self.left -= step;
+            burned += step;
+            if burned >= time || !self.refuel(slot) {return burned};
+        }
+    }
+
+    /// Takes one item from the fuel slot.
+    pub fn refuel(&mut self, slot: &mut PossibleItem) -> bool {
+        let Some(fuel) = slot.0.and_then(|item| ITEMS().fuel(item.id())) else {return false};
+        slot.try_take(1);
+        self.left = fuel;
+        self.total = fuel;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::content_loader::test_content;
+
+    use super::*;
+
+    #[test]
+    fn fuel_is_burned_item_by_item() {
+        test_content();
+        let fuel = ITEMS().id("box").unwrap();
+        let mut slot = PossibleItem::new(fuel, 2);
+        let mut burner = Burner::default();
+        assert!(burner.has_fuel(&slot) && !burner.is_burning());
+
+        assert_eq!(burner.burn(Duration::from_secs(5), &mut slot), Duration::from_secs(5));
+        assert_eq!(slot.0.unwrap().count, 1);
+        assert_eq!(burner.progress(), 0.75);
+
+        assert_eq!(burner.burn(Duration::from_secs(100), &mut slot), Duration::from_secs(35));
+        assert!(slot.0.is_none());
+        assert!(!burner.has_fuel(&slot));
+    }
+
+    #[test]
+    fn items_without_fuel_value_are_not_burned() {
+        test_content();
+        let mut slot = PossibleItem::new(ITEMS().id("iron_ore").unwrap(), 1);
+        let mut burner = Burner::default();
+        assert!(!burner.has_fuel(&slot));
+        assert_eq!(burner.burn(Duration::from_secs(1), &mut slot), Duration::ZERO);
+        assert_eq!(slot.0.unwrap().count, 1);
+    }
+}
@@ -1,19 +1,15 @@
-use std::time::Instant;
-
-
-
-use crate::{direction::Direction};
+use crate::{direction::Direction, world::clock::Stopwatch};
 
 use super::{LiveVoxelBehavior, LiveVoxelCreation};
 
 #[derive(Debug)]
 pub struct Cowboy {
-    time: Instant,
+    time: Stopwatch,
 }
 
 impl Default for Cowboy {
     #[inline] fn default() -> Self {Self {
-        time: Instant::now()
+        time: Stopwatch::start()
     }}
 }
 
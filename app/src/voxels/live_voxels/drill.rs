@@ -1,39 +1,55 @@
 use std::sync::Mutex;
-use std::time::{Instant, Duration};
+use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 
 
 use crate::live_voxel_default_deserialize;
 
+use crate::power::{footprint, unpowered};
+use crate::world::clock::{self, Stopwatch};
 use crate::{coords::global_coord::GlobalCoord, direction::Direction, voxels::{chunks::Chunks}, recipes::{item::PossibleItem, storage::Storage}};
 use crate::voxels::live_voxels::LiveVoxelBehavior;
 
 use crate::voxels::live_voxels::LiveVoxelCreation;
-fn new_instant() -> Instant {Instant::now()}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Drill {
     dir: [i8; 3],
     storage: [PossibleItem; 1],
-    #[serde(skip)]
-    #[serde(default = "new_instant")] 
-    start: Instant,
+    start: Stopwatch,
+    last_update: Option<u64>,
 }
+/// Drill of the saves from before the world clock, the mining starts again.
+#[derive(Debug, Deserialize)]
+pub struct LegacyDrill {
+    dir: [i8; 3],
+    storage: [PossibleItem; 1],
+}
+
+impl From<LegacyDrill> for Mutex<Drill> {
+    fn from(legacy: LegacyDrill) -> Self {
+        Mutex::new(Drill { dir: legacy.dir, storage: legacy.storage, start: Stopwatch::start(), last_update: None })
+    }
+}
+
 impl Drill {
     pub const DURATION: Duration = Duration::new(4, 0);
+    /// Watts used while mining
+    pub const POWER: f32 = 90_000.0;
 }
 
 impl LiveVoxelCreation for Mutex<Drill> {
     fn create(direction: &Direction) -> Box<dyn LiveVoxelBehavior> {
         Box::new(Mutex::new(Drill {
             storage: [PossibleItem::new_none()],
-            start: Instant::now(),
+            start: Stopwatch::start(),
+            last_update: None,
             dir: direction.simplify_to_one_greatest(true, false, true)
         }))
     }
 
-    live_voxel_default_deserialize!(Mutex<Drill>);
+    live_voxel_default_deserialize!(Mutex<Drill>, LegacyDrill);
 }
 
 impl LiveVoxelBehavior for Mutex<Drill> {
@@ -48,15 +64,20 @@ impl LiveVoxelBehavior for Mutex<Drill> {
             }
         }
 
+        // Mines slower by the power satisfaction of its network.
+        let elapsed = clock::elapsed(&mut drill.last_update);
+        let satisfaction = chunks.power.lock().unwrap().demand(footprint(&xyz, multiblock), Drill::POWER);
+        drill.start.delay(unpowered(elapsed, satisfaction));
+
         if drill.start.elapsed() < Drill::DURATION {return}
-        drill.start = Instant::now();
+        drill.start = Stopwatch::start();
         
         let mut ores = vec![];
         multiblock.iter().for_each(|coord| {
             let ore_coords = GlobalCoord::new(coord.x, coord.y-1, coord.z);
             let voxel = chunks.voxel_global(ore_coords);
             let Some(voxel) = voxel else {return};
-            if let Some(item) = chunks.content.blocks[voxel.id as usize].ore() {
+            if let Some(item) = chunks.content.blocks[voxel.id as usize].load().ore() {
                 ores.push(item);
             }
         });
@@ -88,4 +109,19 @@ impl Storage for Drill {
     fn mut_storage(&mut self) -> &mut [PossibleItem] {
         &mut self.storage
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{recipes::item::Item, save_load::decode_exact};
+
+    use super::*;
+
+    #[test]
+    fn baseline_drill_is_loaded() {
+        let bytes = bincode::serialize(&([1i8, 0, 0], [PossibleItem::new(0, 3)])).unwrap();
+        let drill: Drill = decode_exact(&<Mutex<Drill>>::from_bytes(&bytes).to_bytes()).unwrap();
+        assert_eq!(drill.dir, [1, 0, 0]);
+        assert_eq!(drill.storage[0].0, Some(Item::new(0, 3)));
+    }
+}
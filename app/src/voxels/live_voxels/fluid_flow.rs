@@ -0,0 +1,144 @@
+use std::{sync::{Arc, Mutex}, time::Duration};
+
+use crate::{coords::global_coord::GlobalCoord, recipes::{fluid::{Fluid, FluidBox, Flow}, fluid_storage::FluidStorage}, voxels::chunks::Chunks};
+
+/// Fluid one connection moves in a second
+pub const FLOW_RATE: f32 = 1200.0;
+
+const SIDES: [[i32; 3]; 6] = [[1, 0, 0], [-1, 0, 0], [0, 1, 0], [0, -1, 0], [0, 0, 1], [0, 0, -1]];
+
+/// Fluid storage next to a voxel and the side it is on, pointing away from the voxel
+pub type FluidNeighbour = ([i32; 3], Arc<Mutex<dyn FluidStorage>>);
+
+/// Fluid storages next to the blocks. Machines made of several blocks are found once,
+/// through any of their blocks.
+pub fn neighbours(chunks: &Chunks, blocks: &[GlobalCoord]) -> Vec<FluidNeighbour> {
+    let mut masters = Vec::<GlobalCoord>::new();
+    let mut neighbours = Vec::new();
+    for block in blocks {
+        for side in SIDES {
+            let coord = *block + GlobalCoord::new(side[0], side[1], side[2]);
+            if blocks.contains(&coord) {continue};
+            let Some(live_voxel) = chunks.master_live_voxel(coord) else {continue};
+            if masters.contains(&live_voxel.coord()) {continue};
+            let Some(storage) = live_voxel.fluid_storage() else {continue};
+            masters.push(live_voxel.coord());
+            neighbours.push((side, storage));
+        }
+    }
+    neighbours
+}
+
+/// The neighbour in front of a block, used by the voxels that push fluid one way.
+pub fn neighbour(chunks: &Chunks, coord: GlobalCoord, side: [i32; 3]) -> Option<Arc<Mutex<dyn FluidStorage>>> {
+    let neighbour = chunks.master_live_voxel(coord + GlobalCoord::new(side[0], side[1], side[2]))?;
+    neighbour.fluid_storage().filter(|storage| storage.lock().unwrap().connects(side.map(|c| -c)))
+}
+
+/// Moves the fluids of the storage made of the blocks to its neighbours, up to `FLOW_RATE` a second
+/// through every connection. The neighbours move their fluids back on their own update.
+pub fn flow(chunks: &Chunks, blocks: &[GlobalCoord], storage: &mut dyn FluidStorage, elapsed: Duration) {
+    let rate = FLOW_RATE * elapsed.as_secs_f32();
+    if rate <= 0.0 {return};
+    for (side, neighbour) in neighbours(chunks, blocks) {
+        if !storage.connects(side) {continue};
+        let mut neighbour = neighbour.lock().unwrap();
+        if !neighbour.connects(side.map(|c| -c)) {continue};
+        for source in storage.mut_fluid_boxes() {
+            for target in neighbour.mut_fluid_boxes() {
+                push(source, target, rate);
+            }
+        }
+    }
+}
+
+/// Outputs are emptied and inputs are filled as far as they go, the other boxes only flow down
+/// to the same level. Returns the moved amount.
+pub fn push(source: &mut FluidBox, target: &mut FluidBox, max: f32) -> f32 {
+    if source.flow == Flow::Input || target.flow == Flow::Output {return 0.0};
+    if source.flow == Flow::Both && target.flow == Flow::Both {
+        let level = (source.amount() * target.capacity - target.amount() * source.capacity) / (source.capacity + target.capacity);
+        return transfer(source, target, max.min(level));
+    }
+    transfer(source, target, max)
+}
+
+/// Moves up to `max` of the source fluid to the target whatever their flow is, returns the moved amount.
+pub fn transfer(source: &mut FluidBox, target: &mut FluidBox, max: f32) -> f32 {
+    let Some(fluid) = source.fluid else {return 0.0};
+    let amount = max.min(fluid.amount).min(target.space(fluid.id()));
+    if amount <= 0.0 {return 0.0};
+    target.fill(&Fluid::new(fluid.id(), amount));
+    source.drain(&Fluid::new(fluid.id(), amount))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WATER: u32 = 0;
+    const OIL: u32 = 1;
+
+    fn fluid_box(capacity: f32, flow: Flow, amount: f32) -> FluidBox {
+        let mut fluid_box = FluidBox::new(capacity, flow);
+        fluid_box.fill(&Fluid::new(WATER, amount));
+        fluid_box
+    }
+
+    #[test]
+    fn both_ways_boxes_are_levelled() {
+        let (mut source, mut target) = (fluid_box(100.0, Flow::Both, 80.0), fluid_box(100.0, Flow::Both, 20.0));
+        assert_eq!(push(&mut source, &mut target, 1000.0), 30.0);
+        assert_eq!((source.amount(), target.amount()), (50.0, 50.0));
+        assert_eq!(push(&mut target, &mut source, 1000.0), 0.0);
+
+        // Levelled by the filled part, not by the amount.
+        let (mut source, mut target) = (fluid_box(100.0, Flow::Both, 100.0), fluid_box(300.0, Flow::Both, 0.0));
+        assert_eq!(push(&mut source, &mut target, 1000.0), 75.0);
+        assert_eq!(source.level(), target.level());
+    }
+
+    #[test]
+    fn flow_is_limited_by_the_rate() {
+        let (mut source, mut target) = (fluid_box(100.0, Flow::Both, 80.0), fluid_box(100.0, Flow::Both, 0.0));
+        assert_eq!(push(&mut source, &mut target, 10.0), 10.0);
+        assert_eq!((source.amount(), target.amount()), (70.0, 10.0));
+    }
+
+    #[test]
+    fn inputs_and_outputs_move_one_way() {
+        let (mut input, mut output) = (fluid_box(100.0, Flow::Input, 50.0), fluid_box(100.0, Flow::Output, 50.0));
+        let mut pipe = fluid_box(100.0, Flow::Both, 50.0);
+        assert_eq!(push(&mut input, &mut pipe, 1000.0), 0.0);
+        assert_eq!(push(&mut pipe, &mut output, 1000.0), 0.0);
+
+        // Whatever the levels are.
+        assert_eq!(push(&mut output, &mut pipe, 1000.0), 50.0);
+        assert!(output.fluid.is_none());
+        assert_eq!(push(&mut pipe, &mut input, 1000.0), 50.0);
+        assert_eq!((pipe.amount(), input.amount()), (50.0, 100.0));
+    }
+
+    #[test]
+    fn targets_are_filled_up_to_their_capacity() {
+        let (mut source, mut target) = (fluid_box(100.0, Flow::Output, 100.0), fluid_box(30.0, Flow::Input, 10.0));
+        assert_eq!(push(&mut source, &mut target, 1000.0), 20.0);
+        assert_eq!((source.amount(), target.amount()), (80.0, 30.0));
+        assert_eq!(transfer(&mut source, &mut target, 1000.0), 0.0);
+    }
+
+    #[test]
+    fn different_fluids_are_not_mixed() {
+        let mut source = fluid_box(100.0, Flow::Output, 50.0);
+        let mut target = FluidBox::new(100.0, Flow::Both);
+        target.fill(&Fluid::new(OIL, 10.0));
+        assert_eq!(push(&mut source, &mut target, 1000.0), 0.0);
+        assert_eq!(transfer(&mut source, &mut target, 1000.0), 0.0);
+        assert_eq!((target.contains(OIL), target.contains(WATER)), (10.0, 0.0));
+
+        let mut filtered = FluidBox::new(100.0, Flow::Input);
+        filtered.filter = Some(OIL);
+        assert_eq!(push(&mut source, &mut filtered, 1000.0), 0.0);
+        assert!(filtered.fluid.is_none());
+    }
+}
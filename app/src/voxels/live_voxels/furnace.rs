@@ -3,14 +3,41 @@ use std::sync::{Arc, Mutex, Weak};
 use serde::{Deserialize, Serialize};
 use graphics_engine::texture::TextureAtlas;
 
-use crate::{direction::{Direction}, gui::{draw::Draw, my_widgets::inventory_slot::inventory_slot}, live_voxel_default_deserialize, player::inventory::PlayerInventory, player_unlockable, recipes::{item::{Item, PossibleItem}, recipe::ActiveRecipe, recipes::RECIPES, storage::Storage}, voxels::chunks::Chunks, coords::global_coord::GlobalCoord};
+use crate::{direction::{Direction}, gui::{draw::Draw, my_widgets::inventory_slot::inventory_slot}, live_voxel_default_deserialize, player::inventory::PlayerInventory, player_unlockable, recipes::{item::{Item, PossibleItem}, items::ITEMS, recipe::{ActiveRecipe, LegacyActiveRecipe}, recipes::RECIPES, storage::Storage}, voxels::chunks::Chunks, coords::global_coord::GlobalCoord, world::clock};
 
-use super::{LiveVoxelBehavior, PlayerUnlockable, LiveVoxelCreation};
+use super::{burner::Burner, LiveVoxelBehavior, PlayerUnlockable, LiveVoxelCreation};
+
+/// The main result and one byproduct, furnace recipes can't have more
+pub const RESULT_LENGTH: usize = 2;
+const INPUT: usize = 0;
+const FUEL: usize = 1;
+/// The first result slot, manipulators only take from the results
+const RESULT: usize = 2;
+const TOTAL_LENGTH: usize = RESULT + RESULT_LENGTH;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Furnace {
-    storage: [PossibleItem; 2],
+    storage: [PossibleItem; TOTAL_LENGTH],
     active_recipe: Option<ActiveRecipe>,
+    burner: Burner,
+    last_update: Option<u64>,
+}
+
+/// Furnace of the saves from before fuel, with only the input and the result slot.
+#[derive(Debug, Deserialize)]
+pub struct LegacyFurnace {
+    storage: [PossibleItem; 2],
+    active_recipe: Option<LegacyActiveRecipe>,
+}
+
+impl From<LegacyFurnace> for Arc<Mutex<Furnace>> {
+    fn from(legacy: LegacyFurnace) -> Self {
+        let [input, result] = legacy.storage;
+        let mut furnace = Furnace { active_recipe: legacy.active_recipe.map(ActiveRecipe::from), ..Default::default() };
+        furnace.storage[INPUT] = input;
+        furnace.storage[RESULT] = result;
+        Arc::new(Mutex::new(furnace))
+    }
 }
 
 impl LiveVoxelCreation for Arc<Mutex<Furnace>> {
@@ -18,7 +45,7 @@ impl LiveVoxelCreation for Arc<Mutex<Furnace>> {
         Box::new(Arc::new(Mutex::new(Furnace::default())))
     }
 
-    live_voxel_default_deserialize!(Arc<Mutex<Furnace>>);
+    live_voxel_default_deserialize!(Arc<Mutex<Furnace>>, LegacyFurnace);
 }
 
 impl LiveVoxelBehavior for Arc<Mutex<Furnace>> {
@@ -28,26 +55,54 @@ impl LiveVoxelBehavior for Arc<Mutex<Furnace>> {
         Some(self.clone())
     }
 
-    fn update(&self, _: &Chunks, _: GlobalCoord, _: &[GlobalCoord]) {
-        let mut furnace = self.lock().unwrap();
-        let active_recipe_take = furnace.active_recipe.take();
-        if let Some(active_recipe) = &active_recipe_take {
-            let storage = furnace.mut_storage();
-            if active_recipe.is_finished() && storage[1].is_possible_add(&active_recipe.recipe.result) {
-                storage[1].try_add_item(&active_recipe.recipe.result);
-                furnace.active_recipe = None;
-            } else {
-                furnace.active_recipe = active_recipe_take;
+    fn update(&self, _: &Chunks, coord: GlobalCoord, _: &[GlobalCoord]) {
+        self.lock().unwrap().update(coord);
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+}
+
+impl Furnace {
+    pub fn update(&mut self, coord: GlobalCoord) {
+        let elapsed = clock::elapsed(&mut self.last_update);
+
+        let Some(mut active_recipe) = self.active_recipe.take() else {
+            let Some(item) = &self.storage[INPUT].0 else {return};
+            let Some(recipe) = RECIPES().furnace.first_by_ingredient(item.id()).cloned() else {return};
+            if !self.burner.is_burning() && !self.burner.refuel(&mut self.storage[FUEL]) {return};
+            self.active_recipe = self.start_recipe(&recipe);
+            return;
+        };
+
+        // A finished recipe waits for space in the results without burning fuel.
+        if !active_recipe.is_finished() {
+            let burned = self.burner.burn(elapsed, &mut self.storage[FUEL]);
+            active_recipe.delay(elapsed - burned);
+        }
+
+        let results = &active_recipe.recipe.results;
+        let result_slots = &mut self.storage[RESULT..TOTAL_LENGTH];
+        let is_fits = results.len() <= RESULT_LENGTH
+            && results.iter().zip(result_slots.iter()).all(|(result, slot)| slot.is_possible_add(&result.item));
+        if active_recipe.is_finished() && is_fits {
+            let mut rng = active_recipe.recipe.rng(&clock::block_salts(coord));
+            for (result, slot) in results.iter().zip(result_slots) {
+                if let Some(item) = result.roll(&mut rng) {slot.try_add_item(&item);}
             }
         } else {
-            let Some(item) = &furnace.storage[0].0 else {return};
-            let Some(recipe) = RECIPES().furnace.first_by_ingredient(item.id()).cloned() else {return};
-            furnace.active_recipe = furnace.start_recipe(&recipe);
+            self.active_recipe = Some(active_recipe);
         }
     }
 
-    fn to_bytes(&self) -> Vec<u8> {
-        bincode::serialize(self).unwrap()
+    pub fn is_burning(&self) -> bool {
+        self.burner.is_burning()
+    }
+
+    /// Burn time left of the last fuel item, from 1 down to 0.
+    pub fn burn_progress(&self) -> f32 {
+        self.burner.progress()
     }
 }
 
@@ -60,27 +115,40 @@ impl Storage for Furnace {
         &mut self.storage
     }
 
-    fn take_first_existing(&mut self, max_count: u32) -> Option<(Item, usize)> {
-        self.mut_storage()[1].try_take(max_count).map(|i| (i, 1))
+    fn take_first_matching(&mut self, max_count: u32, filter: &dyn Fn(u32) -> bool) -> Option<(Item, usize)> {
+        for (i, possible_item) in self.storage.iter_mut().enumerate().skip(RESULT) {
+            if !possible_item.0.is_some_and(|item| filter(item.id())) {continue};
+            let Some(item) = possible_item.try_take(max_count) else {continue};
+            return Some((item, i))
+        }
+        None
     }
 
+    /// Fuel goes to the fuel slot and what can be smelted to the input, a fuel that can be smelted
+    /// goes to the input once the fuel slot is full.
     fn add(&mut self, item: &Item, _: bool) -> Option<Item> {
+        let mut remainder = Some(*item);
+        if ITEMS().fuel(item.id()).is_some() {
+            remainder = self.storage[FUEL].try_add_item(item);
+        }
         if RECIPES().furnace.get_by_ingredient(item.id()).is_some() {
-            return self.mut_storage()[0].try_add_item(item);
+            remainder = remainder.and_then(|item| self.storage[INPUT].try_add_item(&item));
         }
-        Some(*item)
+        remainder
     }
 
     fn is_item_exist(&self, item: &Item) -> bool {
-        self.storage[0].contains(item.id()) >= item.count
+        self.storage[INPUT].contains(item.id()) >= item.count
     }
 }
 
 impl Default for Furnace {
     fn default() -> Self {
         Self {
-            storage: [PossibleItem::new_none(); 2],
-            active_recipe: None
+            storage: [PossibleItem::new_none(); TOTAL_LENGTH],
+            active_recipe: None,
+            burner: Burner::default(),
+            last_update: None,
         }
     }
 }
@@ -95,6 +163,7 @@ impl Draw for Furnace {
                 }
             }
         });
+        ui.add(egui::ProgressBar::new(self.burn_progress()));
 
         if let Some(task) = task {
             let Some(item) = self.mut_storage()[task].0.take() else {return};
@@ -112,4 +181,64 @@ impl PlayerUnlockable for Furnace {
     fn get_mut_storage(&mut self) -> Option<&mut dyn Storage> {
         Some(self)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{content_loader::test_content, save_load::decode_exact};
+
+    use super::*;
+
+    fn id(name: &str) -> u32 {
+        ITEMS().id(name).unwrap()
+    }
+
+    #[test]
+    fn baseline_furnace_is_loaded() {
+        test_content();
+        let ingot = RECIPES().all.iter().position(|recipe| recipe.name == "iron_ingot").unwrap();
+        // Recipe of the baseline: index, id, duration, crafter, category, ingredients and one result.
+        let recipe = (ingot, ingot as u32, Duration::from_secs(1), 0b100u8, 0b1u8, vec![Item::new(id("iron_ore"), 1)], Item::new(id("iron_ingot"), 1));
+        let storage = [PossibleItem::new(id("iron_ore"), 3), PossibleItem::new(id("iron_ingot"), 2)];
+        let bytes = bincode::serialize(&(storage, Some((recipe,)))).unwrap();
+        let furnace: Furnace = decode_exact(&<Arc<Mutex<Furnace>>>::from_bytes(&bytes).to_bytes()).unwrap();
+
+        assert_eq!(furnace.storage[INPUT].0, Some(Item::new(id("iron_ore"), 3)));
+        assert_eq!(furnace.storage[FUEL].0, None);
+        assert_eq!(furnace.storage[RESULT].0, Some(Item::new(id("iron_ingot"), 2)));
+        assert!(!furnace.is_burning());
+        let active = furnace.active_recipe.unwrap();
+        assert_eq!(active.recipe.ingredients, [Item::new(id("iron_ore"), 1)]);
+        assert_eq!(active.recipe.max_results(), [Item::new(id("iron_ingot"), 1)]);
+    }
+
+    #[test]
+    fn fuel_and_ore_go_to_their_slots() {
+        test_content();
+        let mut furnace = Furnace::default();
+        assert_eq!(furnace.add(&Item::new(id("box"), 5), false), None);
+        assert_eq!(furnace.add(&Item::new(id("iron_ore"), 3), false), None);
+        assert_eq!(furnace.storage[FUEL].0, Some(Item::new(id("box"), 5)));
+        assert_eq!(furnace.storage[INPUT].0, Some(Item::new(id("iron_ore"), 3)));
+
+        let plate = Item::new(id("iron_plate"), 1);
+        assert_eq!(furnace.add(&plate, false), Some(plate));
+        assert!(furnace.storage[RESULT..].iter().all(|slot| slot.0.is_none()));
+    }
+
+    #[test]
+    fn smelting_starts_only_with_fuel() {
+        test_content();
+        let mut furnace = Furnace::default();
+        furnace.add(&Item::new(id("iron_ore"), 3), false);
+        furnace.update(GlobalCoord::new(0, 0, 0));
+        assert!(furnace.active_recipe.is_none());
+
+        furnace.add(&Item::new(id("box"), 2), false);
+        furnace.update(GlobalCoord::new(0, 0, 0));
+        assert!(furnace.active_recipe.is_some());
+        assert!(furnace.is_burning());
+        assert_eq!(furnace.storage[FUEL].0, Some(Item::new(id("box"), 1)));
+    }
+}
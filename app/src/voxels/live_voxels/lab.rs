@@ -0,0 +1,182 @@
+use std::sync::{Arc, Mutex, Weak};
+
+use serde::{Deserialize, Serialize};
+use graphics_engine::texture::TextureAtlas;
+
+use crate::{coords::global_coord::GlobalCoord, direction::Direction, gui::{draw::Draw, my_widgets::inventory_slot::inventory_slot}, lang::{item_name, technology_name, tr}, live_voxel_default_deserialize, player::inventory::PlayerInventory, player_unlockable, recipes::{item::{Item, PossibleItem}, research::{with_research, with_research_mut}, storage::Storage, technology::TECHNOLOGIES}, voxels::chunks::Chunks, world::clock::Stopwatch};
+
+use super::{LiveVoxelBehavior, LiveVoxelCreation, PlayerUnlockable};
+
+const LENGTH: usize = 4;
+
+/// Science item a lab is working on.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Unit {
+    #[serde(with = "technology_name")]
+    technology: u32,
+    #[serde(with = "crate::content::manifest::item_id")]
+    item: u32,
+    start: Stopwatch,
+}
+
+/// Technologies are saved by name like in `research.json`, an unknown one drops the unit.
+mod technology_name {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::recipes::technology::TECHNOLOGIES;
+
+    pub fn serialize<S: Serializer>(id: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+        TECHNOLOGIES().get(*id).map(|technology| technology.name.as_str()).unwrap_or_default().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+        String::deserialize(deserializer).map(|name| TECHNOLOGIES().id(&name).unwrap_or(u32::MAX))
+    }
+}
+
+/// Consumes science items from its storage for the technology selected in any lab.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Lab {
+    storage: [PossibleItem; LENGTH],
+    /// The item is taken when the unit is finished
+    unit: Option<Unit>,
+}
+
+impl LiveVoxelCreation for Arc<Mutex<Lab>> {
+    fn create(_: &Direction) -> Box<dyn LiveVoxelBehavior> {
+        Box::new(Arc::new(Mutex::new(Lab::default())))
+    }
+
+    live_voxel_default_deserialize!(Arc<Mutex<Lab>>);
+}
+
+impl LiveVoxelBehavior for Arc<Mutex<Lab>> {
+    player_unlockable!();
+
+    fn storage(&self) -> Option<Arc<Mutex<dyn Storage>>> {
+        Some(self.clone())
+    }
+
+    fn update(&self, _: &Chunks, _: GlobalCoord, _: &[GlobalCoord]) {
+        self.lock().unwrap().update();
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+}
+
+impl Lab {
+    pub fn update(&mut self) {
+        let Some(unit) = self.unit else {
+            self.unit = self.next_unit();
+            return;
+        };
+        let Some(technology) = TECHNOLOGIES().get(unit.technology) else {
+            self.unit = None;
+            return;
+        };
+        if unit.start.elapsed() < technology.time {return};
+
+        self.unit = None;
+        let item = Item::new(unit.item, 1);
+        if !self.is_item_exist(&item) {return};
+        if with_research_mut(|research| research.consume(unit.technology, unit.item)) == Some(true) {
+            self.remove(&item);
+        }
+    }
+
+    fn next_unit(&self) -> Option<Unit> {
+        with_research(|research| {
+            let technology = research.current()?;
+            let item = self.storage.iter()
+                .filter_map(|possible_item| possible_item.0)
+                .find(|item| research.needs(item.id()))?;
+            Some(Unit { technology: technology.id, item: item.id(), start: Stopwatch::start() })
+        }).flatten()
+    }
+}
+
+impl Storage for Lab {
+    fn storage(&self) -> &[PossibleItem] {
+        &self.storage
+    }
+
+    fn mut_storage(&mut self) -> &mut [PossibleItem] {
+        &mut self.storage
+    }
+
+    fn add(&mut self, item: &Item, _: bool) -> Option<Item> {
+        if !TECHNOLOGIES().is_science(item.id()) {return Some(*item)};
+        let mut added_item = Item::from(item);
+        for possible_item in self.mut_storage().iter_mut() {
+            let remainder = possible_item.try_add_item(&added_item)?;
+            added_item = remainder;
+        }
+        Some(added_item)
+    }
+}
+
+impl Default for Lab {
+    fn default() -> Self {
+        Self { storage: [PossibleItem::new_none(); LENGTH], unit: None }
+    }
+}
+
+impl Draw for Lab {
+    fn draw(&mut self, ui: &mut egui::Ui, atlas: Arc<TextureAtlas>, inventory: Arc<Mutex<PlayerInventory>>) {
+        let mut task: Option<usize> = None;
+        ui.horizontal(|ui| {
+            for (index, item) in self.storage().iter().enumerate() {
+                if ui.add(inventory_slot(&atlas, item)).drag_started() {
+                    task = Some(index);
+                }
+            }
+        });
+
+        let mut start = None;
+        with_research(|research| {
+            match research.current() {
+                Some(technology) => {
+                    ui.label(format!("{} {}", tr("ui.research.current"), technology_name(technology.id)));
+                    for (item, count, total) in research.progress() {
+                        ui.label(format!("{}: {}/{}", item_name(item), count, total));
+                    }
+                },
+                None => {ui.label(tr("ui.research.none"));},
+            }
+            ui.separator();
+            ui.label(tr("ui.research.available"));
+            for technology in TECHNOLOGIES().all.iter().filter(|t| research.is_available(t.id)) {
+                let cost = technology.cost.iter()
+                    .map(|item| format!("{} x{}", item_name(item.id()), item.count))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let selected = research.current().is_some_and(|current| current.id == technology.id);
+                if ui.selectable_label(selected, format!("{} ({})", technology_name(technology.id), cost)).clicked() {
+                    start = Some(technology.id);
+                }
+            }
+        });
+        if let Some(technology) = start {
+            with_research_mut(|research| research.start(technology));
+            self.unit = None;
+        }
+
+        if let Some(task) = task {
+            let Some(item) = self.mut_storage()[task].0.take() else {return};
+            let remainder = inventory.lock().unwrap().add(&item, true);
+            if let Some(r) = remainder {self.set(&r, task)}
+        }
+    }
+}
+
+impl PlayerUnlockable for Lab {
+    fn get_storage(&self) -> Option<&dyn Storage> {
+        Some(self)
+    }
+
+    fn get_mut_storage(&mut self) -> Option<&mut dyn Storage> {
+        Some(self)
+    }
+}
\ No newline at end of file
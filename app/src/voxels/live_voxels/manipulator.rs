@@ -1,39 +1,77 @@
-use std::{sync::{Mutex}, time::{Duration, Instant}};
+use std::{sync::{Mutex}, time::Duration};
 use serde::{Deserialize, Serialize};
 
-use crate::{direction::Direction, live_voxel_default_deserialize, recipes::item::Item, voxels::chunks::Chunks, coords::global_coord::GlobalCoord};
+use crate::{direction::Direction, live_voxel_default_deserialize, power::unpowered, recipes::item::Item, voxels::chunks::Chunks, coords::global_coord::GlobalCoord, world::clock::{self, Stopwatch}};
 
 use super::{LiveVoxelBehavior, LiveVoxelCreation};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Manipulator {
-    #[serde(skip)]
-    start_time: Option<Instant>,
-    #[serde(skip)]
-    return_time: Option<Instant>,
+    start_time: Option<Stopwatch>,
+    return_time: Option<Stopwatch>,
+    last_update: Option<u64>,
+    #[serde(with = "crate::content::manifest::option_item_id")]
     item_id: Option<u32>,
     direction: [i8; 3],
 }
 
+/// Manipulator of the saves from before the world clock, a carried item is put down on the next update.
+#[derive(Debug, Deserialize)]
+pub struct LegacyManipulator {
+    #[serde(with = "crate::content::manifest::option_item_id")]
+    item_id: Option<u32>,
+    direction: [i8; 3],
+}
+
+impl From<LegacyManipulator> for Mutex<Manipulator> {
+    fn from(legacy: LegacyManipulator) -> Self {
+        Mutex::new(Manipulator { start_time: None, return_time: None, last_update: None, item_id: legacy.item_id, direction: legacy.direction })
+    }
+}
 
 impl Manipulator {
     const SPEED: Duration = Duration::from_millis(300);
+    /// Watts used while moving
+    pub const POWER: f32 = 13_000.0;
 
     pub fn new(direction: &Direction) -> Self {Self {
         start_time: None,
         return_time: None,
+        last_update: None,
         item_id: None,
         direction: direction.simplify_to_one_greatest(true, false, true),
     }}
 
+    pub fn is_moving(&self) -> bool {
+        self.start_time.is_some_and(|st| st.elapsed() < Self::SPEED/2)
+            || self.return_time.is_some_and(|rt| rt.elapsed() < Self::SPEED/2)
+    }
+
+    /// Moves slower by the power satisfaction of its network and doesn't pick up items without power.
     pub fn update(&mut self, coords: GlobalCoord, chunks: &Chunks) {
+        let elapsed = clock::elapsed(&mut self.last_update);
+        let power = if self.is_moving() {Self::POWER} else {0.0};
+        let satisfaction = chunks.power.lock().unwrap().demand(&[coords], power);
+        if self.is_moving() {
+            let delay = unpowered(elapsed, satisfaction);
+            if let Some(start_time) = &mut self.start_time {start_time.delay(delay)};
+            if let Some(return_time) = &mut self.return_time {return_time.delay(delay)};
+        }
+
         let return_time = self.return_time.map_or(true, |rt| rt.elapsed() >= (Self::SPEED/2));
-        if self.item_id.is_none() && self.start_time.is_none() && return_time {
+        if self.item_id.is_none() && self.start_time.is_none() && return_time && satisfaction > 0.0 {
             let src_coords = GlobalCoord::new(coords.x - self.direction[0] as i32, coords.y, coords.z - self.direction[2] as i32);
             let Some(storage) = chunks.master_live_voxel(src_coords).and_then(|lv| lv.storage()) else {return};
-            if let Some(item) = storage.lock().unwrap().take_first_existing(1) {
+            // Manipulator blocks with an `item_filter` only pick up the matching items.
+            let filter = chunks.voxel_global(coords)
+                .and_then(|voxel| chunks.content.blocks[voxel.id as usize].load().item_filter().copied());
+            let taken = match filter {
+                Some(filter) => storage.lock().unwrap().take_first_matching(1, &|id| filter.matches(id)),
+                None => storage.lock().unwrap().take_first_existing(1),
+            };
+            if let Some(item) = taken {
                 self.item_id = Some(item.0.id());
-                self.start_time = Some(Instant::now());
+                self.start_time = Some(Stopwatch::start());
                 self.return_time = None;
             };
         }
@@ -46,7 +84,7 @@ impl Manipulator {
             if result {
                 self.item_id = None;
                 self.start_time = None;
-                self.return_time = Some(Instant::now());
+                self.return_time = Some(Stopwatch::start());
             }
         }
     }
@@ -94,5 +132,20 @@ impl LiveVoxelCreation for Mutex<Manipulator> {
         Box::new(Mutex::new(Manipulator::new(direction)))
     }
     
-    live_voxel_default_deserialize!(Arc<Mutex<AssemblingMachine>>);
-}
\ No newline at end of file
+    live_voxel_default_deserialize!(Mutex<Manipulator>, LegacyManipulator);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::save_load::decode_exact;
+
+    use super::*;
+
+    #[test]
+    fn baseline_manipulator_is_loaded() {
+        let bytes = bincode::serialize(&(Some(7u32), [0i8, 0, -1])).unwrap();
+        let manipulator: Manipulator = decode_exact(&<Mutex<Manipulator>>::from_bytes(&bytes).to_bytes()).unwrap();
+        assert_eq!((manipulator.item_id, manipulator.direction), (Some(7), [0, 0, -1]));
+        assert!(manipulator.start_time.is_none() && manipulator.return_time.is_none());
+    }
+}
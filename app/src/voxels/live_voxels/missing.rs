@@ -0,0 +1,10 @@
+use super::LiveVoxelBehavior;
+
+/// Saved state of a live voxel whose kind is not registered.
+/// It is written back unchanged, so the live voxel comes back with its content pack.
+#[derive(Debug)]
+pub struct MissingLiveVoxel(pub Vec<u8>);
+
+impl LiveVoxelBehavior for MissingLiveVoxel {
+    fn to_bytes(&self) -> Vec<u8> {self.0.clone()}
+}
\ No newline at end of file
@@ -2,12 +2,13 @@ use std::{collections::HashMap, sync::{Arc, Mutex, Weak}};
 
 use serde::{Deserialize, Serialize};
 
-use crate::{bytes::AsFromBytes, content::Content, direction::Direction, gui::draw::Draw, recipes::storage::Storage, coords::global_coord::GlobalCoord};
+use crate::{bytes::AsFromBytes, content::Content, direction::Direction, gui::draw::Draw, recipes::{fluid_storage::FluidStorage, storage::Storage}, coords::global_coord::GlobalCoord};
 use std::fmt::Debug;
-use self::{assembling_machine::AssemblingMachine, cowboy::Cowboy, drill::Drill, furnace::Furnace, manipulator::Manipulator, transport_belt::TransportBelt, voxel_box::VoxelBox};
+use self::{assembling_machine::AssemblingMachine, cowboy::Cowboy, drill::Drill, furnace::Furnace, lab::Lab, manipulator::Manipulator, missing::MissingLiveVoxel, offshore_pump::OffshorePump, pipe::{Pipe, Tank}, power_generator::PowerGenerator, power_pole::PowerPole, pump::Pump, scripted::ScriptedLiveVoxel, transport_belt::TransportBelt, voxel_box::VoxelBox};
 
 use super::{chunks::Chunks};
 pub mod furnace;
+pub mod burner;
 pub mod voxel_box;
 pub mod unit;
 pub mod drill;
@@ -15,6 +16,15 @@ pub mod assembling_machine;
 pub mod manipulator;
 pub mod cowboy;
 pub mod transport_belt;
+pub mod missing;
+pub mod scripted;
+pub mod lab;
+pub mod power_pole;
+pub mod power_generator;
+pub mod fluid_flow;
+pub mod pipe;
+pub mod pump;
+pub mod offshore_pump;
 
 pub trait PlayerUnlockable: Draw {
     fn get_storage(&self) -> Option<&dyn Storage> {None}
@@ -103,6 +113,10 @@ impl LiveVoxelContainer {
         self.live_voxel.storage()
     }
     #[inline] 
+    pub fn fluid_storage(&self) -> Option<Arc<Mutex<dyn FluidStorage>>> {
+        self.live_voxel.fluid_storage()
+    }
+    #[inline] 
     pub fn transport_belt(&self) -> Option<Arc<Mutex<TransportBelt>>> {
         self.live_voxel.transport_belt()
     }
@@ -134,9 +148,10 @@ impl LiveVoxelContainer {
             .map(|i| *bytes.get(i+coord_end).unwrap())) as usize;
         let multiblock = bincode::deserialize(&bytes[coord_end+4..multiblock_end]).unwrap();
 
-        let live_voxel: Box<dyn LiveVoxelBehavior> = if let Some(name) = content.blocks[id as usize].live_voxel() {
+        let live_voxel: Box<dyn LiveVoxelBehavior> = if let Some(name) = content.blocks[id as usize].load().live_voxel() {
             content.live_voxel.deserialize.get(name)
-                .map_or(Box::new(()), |desiarialize| desiarialize(&bytes[multiblock_end..]))
+                .map_or_else(|| Box::new(MissingLiveVoxel(bytes[multiblock_end..].to_vec())) as Box<dyn LiveVoxelBehavior>,
+                    |desiarialize| desiarialize(&bytes[multiblock_end..]))
         } else {
             Box::new(())
         };
@@ -166,16 +181,14 @@ pub static mut LIVE_VOXEL_REGISTER: Option<LiveVoxelRegistrator> = None;
 pub fn register() -> LiveVoxelRegistrator {
     let mut new = HashMap::<String, NewLiveVoxel>::new();
     let mut deserialize = HashMap::<String, DesiarializeLiveVoxel>::new();
-    unsafe {
-        if let Some(l) = LIVE_VOXEL_REGISTER.take() {
-            l.new.into_iter().for_each(|(name, f)| {
-                println!("name: {:?}", name);
-                new.insert(name, f);
-            });
-            l.deserialize.into_iter().for_each(|(name, f)| {
-                deserialize.insert(name, f);
-            });
-        }
+    // Not taken, every world builds its own registrator from the mods loaded at startup.
+    if let Some(l) = unsafe {(*std::ptr::addr_of!(LIVE_VOXEL_REGISTER)).as_ref()} {
+        l.new.iter().for_each(|(name, f)| {
+            new.insert(name.clone(), *f);
+        });
+        l.deserialize.iter().for_each(|(name, f)| {
+            deserialize.insert(name.clone(), *f);
+        });
     }
 
     new.insert(String::from("furnace"), &<Arc<Mutex<Furnace>>>::create);
@@ -199,6 +212,30 @@ pub fn register() -> LiveVoxelRegistrator {
     deserialize.insert(String::from("transport_belt"), &<Arc<Mutex<TransportBelt>>>::from_bytes);
     new.insert(String::from("transport_belt"), &<Arc<Mutex<TransportBelt>>>::create);
 
+    deserialize.insert(String::from("lab"), &<Arc<Mutex<Lab>>>::from_bytes);
+    new.insert(String::from("lab"), &<Arc<Mutex<Lab>>>::create);
+
+    deserialize.insert(String::from("power_pole"), &<Arc<Mutex<PowerPole>>>::from_bytes);
+    new.insert(String::from("power_pole"), &<Arc<Mutex<PowerPole>>>::create);
+
+    deserialize.insert(String::from("power_generator"), &<Arc<Mutex<PowerGenerator>>>::from_bytes);
+    new.insert(String::from("power_generator"), &<Arc<Mutex<PowerGenerator>>>::create);
+
+    deserialize.insert(String::from("pipe"), &<Arc<Mutex<Pipe>>>::from_bytes);
+    new.insert(String::from("pipe"), &<Arc<Mutex<Pipe>>>::create);
+
+    deserialize.insert(String::from("tank"), &Tank::from_bytes);
+    new.insert(String::from("tank"), &Tank::create);
+
+    deserialize.insert(String::from("pump"), &<Arc<Mutex<Pump>>>::from_bytes);
+    new.insert(String::from("pump"), &<Arc<Mutex<Pump>>>::create);
+
+    deserialize.insert(String::from("offshore_pump"), &<Arc<Mutex<OffshorePump>>>::from_bytes);
+    new.insert(String::from("offshore_pump"), &<Arc<Mutex<OffshorePump>>>::create);
+
+    deserialize.insert(String::from("script"), &ScriptedLiveVoxel::from_bytes);
+    new.insert(String::from("script"), &ScriptedLiveVoxel::create);
+
     LiveVoxelRegistrator { 
         new,
         deserialize,
@@ -209,6 +246,7 @@ pub trait LiveVoxelBehavior: Debug {
     fn player_unlockable(&self) -> Option<Weak<Mutex<dyn PlayerUnlockable>>> {None}
     fn rotation_index(&self) -> Option<u32> {None}
     fn storage(&self) -> Option<Arc<Mutex<dyn Storage>>> {None}
+    fn fluid_storage(&self) -> Option<Arc<Mutex<dyn FluidStorage>>> {None}
     #[allow(unused)]
     fn update(&self, chunks: &Chunks, coord: GlobalCoord, multiblock: &[GlobalCoord]) {}
     fn transport_belt(&self) -> Option<Arc<Mutex<TransportBelt>>> {None}
@@ -0,0 +1,78 @@
+use std::sync::{Arc, Mutex, Weak};
+
+use serde::{Deserialize, Serialize};
+use graphics_engine::texture::TextureAtlas;
+
+use crate::{coords::global_coord::GlobalCoord, direction::Direction, gui::{draw::Draw, my_widgets::fluid_bar::fluid_bar}, live_voxel_default_deserialize, player::inventory::PlayerInventory, player_unlockable, recipes::{fluid::{Fluid, FluidBox, Flow}, fluid_storage::FluidStorage, fluids::FLUIDS}, voxels::chunks::Chunks, world::clock};
+
+use super::{fluid_flow::flow, LiveVoxelBehavior, LiveVoxelCreation, PlayerUnlockable};
+
+/// Draws water out of the ground without power and gives it to every neighbour.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OffshorePump {
+    fluid_box: [FluidBox; 1],
+    last_update: Option<u64>,
+}
+
+impl OffshorePump {
+    pub const FLUID: &'static str = "water";
+    pub const CAPACITY: f32 = 200.0;
+    /// Fluid drawn in a second
+    pub const RATE: f32 = 1200.0;
+
+    pub fn update(&mut self, chunks: &Chunks, coord: GlobalCoord) {
+        let elapsed = clock::elapsed(&mut self.last_update);
+        if let Some(id) = FLUIDS().id(Self::FLUID) {
+            self.fluid_box[0].fill(&Fluid::new(id, Self::RATE * elapsed.as_secs_f32()));
+        }
+        flow(chunks, &[coord], self, elapsed);
+    }
+}
+
+impl Default for OffshorePump {
+    fn default() -> Self {
+        Self { fluid_box: [FluidBox::new(Self::CAPACITY, Flow::Output)], last_update: None }
+    }
+}
+
+impl LiveVoxelCreation for Arc<Mutex<OffshorePump>> {
+    fn create(_: &Direction) -> Box<dyn LiveVoxelBehavior> {
+        Box::new(Arc::new(Mutex::new(OffshorePump::default())))
+    }
+
+    live_voxel_default_deserialize!(Arc<Mutex<OffshorePump>>);
+}
+
+impl LiveVoxelBehavior for Arc<Mutex<OffshorePump>> {
+    player_unlockable!();
+
+    fn fluid_storage(&self) -> Option<Arc<Mutex<dyn FluidStorage>>> {
+        Some(self.clone())
+    }
+
+    fn update(&self, chunks: &Chunks, coord: GlobalCoord, _: &[GlobalCoord]) {
+        self.lock().unwrap().update(chunks, coord);
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+}
+
+impl FluidStorage for OffshorePump {
+    fn fluid_boxes(&self) -> &[FluidBox] {
+        &self.fluid_box
+    }
+
+    fn mut_fluid_boxes(&mut self) -> &mut [FluidBox] {
+        &mut self.fluid_box
+    }
+}
+
+impl Draw for OffshorePump {
+    fn draw(&mut self, ui: &mut egui::Ui, _: Arc<TextureAtlas>, _: Arc<Mutex<PlayerInventory>>) {
+        ui.add(fluid_bar(&self.fluid_box[0]));
+    }
+}
+
+impl PlayerUnlockable for OffshorePump {}
\ No newline at end of file
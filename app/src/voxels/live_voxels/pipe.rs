@@ -0,0 +1,103 @@
+use std::sync::{Arc, Mutex, Weak};
+
+use serde::{Deserialize, Serialize};
+use graphics_engine::texture::TextureAtlas;
+
+use crate::{coords::global_coord::GlobalCoord, direction::Direction, gui::{draw::Draw, my_widgets::fluid_bar::fluid_bar}, live_voxel_default_deserialize, player::inventory::PlayerInventory, player_unlockable, power::footprint, recipes::{fluid::{FluidBox, Flow}, fluid_storage::FluidStorage}, voxels::chunks::Chunks, world::clock};
+
+use super::{fluid_flow::flow, LiveVoxelBehavior, LiveVoxelCreation, PlayerUnlockable};
+
+pub const PIPE_CAPACITY: f32 = 100.0;
+pub const TANK_CAPACITY: f32 = 25_000.0;
+
+/// Levels its fluid with every neighbour that holds fluids.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Pipe {
+    fluid_box: [FluidBox; 1],
+    last_update: Option<u64>,
+}
+
+impl Pipe {
+    pub fn new(capacity: f32) -> Self {
+        Self { fluid_box: [FluidBox::new(capacity, Flow::Both)], last_update: None }
+    }
+
+    pub fn update(&mut self, chunks: &Chunks, blocks: &[GlobalCoord]) {
+        let elapsed = clock::elapsed(&mut self.last_update);
+        flow(chunks, blocks, self, elapsed);
+    }
+}
+
+impl LiveVoxelCreation for Arc<Mutex<Pipe>> {
+    fn create(_: &Direction) -> Box<dyn LiveVoxelBehavior> {
+        Box::new(Arc::new(Mutex::new(Pipe::new(PIPE_CAPACITY))))
+    }
+
+    live_voxel_default_deserialize!(Arc<Mutex<Pipe>>);
+}
+
+/// Tanks are pipes that hold more, the capacity is saved with the pipe.
+pub struct Tank;
+
+impl LiveVoxelCreation for Tank {
+    fn create(_: &Direction) -> Box<dyn LiveVoxelBehavior> {
+        Box::new(Arc::new(Mutex::new(Pipe::new(TANK_CAPACITY))))
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Box<dyn LiveVoxelBehavior> {
+        <Arc<Mutex<Pipe>>>::from_bytes(bytes)
+    }
+}
+
+impl LiveVoxelBehavior for Arc<Mutex<Pipe>> {
+    player_unlockable!();
+
+    fn fluid_storage(&self) -> Option<Arc<Mutex<dyn FluidStorage>>> {
+        Some(self.clone())
+    }
+
+    fn update(&self, chunks: &Chunks, coord: GlobalCoord, multiblock: &[GlobalCoord]) {
+        self.lock().unwrap().update(chunks, footprint(&coord, multiblock));
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+}
+
+impl FluidStorage for Pipe {
+    fn fluid_boxes(&self) -> &[FluidBox] {
+        &self.fluid_box
+    }
+
+    fn mut_fluid_boxes(&mut self) -> &mut [FluidBox] {
+        &mut self.fluid_box
+    }
+}
+
+impl Draw for Pipe {
+    fn draw(&mut self, ui: &mut egui::Ui, _: Arc<TextureAtlas>, _: Arc<Mutex<PlayerInventory>>) {
+        ui.add(fluid_bar(&self.fluid_box[0]));
+    }
+}
+
+impl PlayerUnlockable for Pipe {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{recipes::fluid::Fluid, save_load::decode_exact};
+
+    use super::*;
+
+    #[test]
+    fn tank_is_saved_with_its_capacity_and_fluid() {
+        let mut tank = Pipe::new(TANK_CAPACITY);
+        tank.fluid_box[0].fill(&Fluid::new(0, 1234.5));
+        let bytes = Arc::new(Mutex::new(tank)).to_bytes();
+
+        let loaded: Pipe = decode_exact(&Tank::from_bytes(&bytes).to_bytes()).unwrap();
+        assert_eq!(loaded.fluid_box[0].capacity, TANK_CAPACITY);
+        assert_eq!(loaded.fluid_box[0].flow, Flow::Both);
+        assert_eq!(loaded.fluid_box[0].fluid, Some(Fluid::new(0, 1234.5)));
+    }
+}
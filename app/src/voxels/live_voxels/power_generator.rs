@@ -0,0 +1,108 @@
+use std::sync::{Arc, Mutex, Weak};
+
+use serde::{Deserialize, Serialize};
+use graphics_engine::texture::TextureAtlas;
+
+use crate::{coords::global_coord::GlobalCoord, direction::Direction, gui::{draw::Draw, my_widgets::inventory_slot::inventory_slot}, lang::tr, live_voxel_default_deserialize, player::inventory::PlayerInventory, player_unlockable, power::{footprint, power_text}, recipes::{item::{Item, PossibleItem}, items::ITEMS, storage::Storage}, voxels::chunks::Chunks, world::clock};
+
+use super::{burner::Burner, LiveVoxelBehavior, LiveVoxelCreation, PlayerUnlockable};
+
+/// Burns fuel to power the network of the nearest pole, slower when the network needs less.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PowerGenerator {
+    storage: [PossibleItem; 1],
+    burner: Burner,
+    /// Load of the network over the last tick, shown to the player
+    #[serde(skip)]
+    load: f32,
+    last_update: Option<u64>,
+}
+
+impl PowerGenerator {
+    /// Watts given at full load
+    pub const POWER: f32 = 900_000.0;
+
+    pub fn update(&mut self, chunks: &Chunks, blocks: &[GlobalCoord]) {
+        let elapsed = clock::elapsed(&mut self.last_update);
+        let power = if self.burner.has_fuel(&self.storage[0]) {Self::POWER} else {0.0};
+        self.load = chunks.power.lock().unwrap().supply(blocks, power);
+        self.burner.burn(elapsed.mul_f32(self.load), &mut self.storage[0]);
+    }
+}
+
+impl LiveVoxelCreation for Arc<Mutex<PowerGenerator>> {
+    fn create(_: &Direction) -> Box<dyn LiveVoxelBehavior> {
+        Box::new(Arc::new(Mutex::new(PowerGenerator::default())))
+    }
+
+    live_voxel_default_deserialize!(Arc<Mutex<PowerGenerator>>);
+}
+
+impl LiveVoxelBehavior for Arc<Mutex<PowerGenerator>> {
+    player_unlockable!();
+
+    fn storage(&self) -> Option<Arc<Mutex<dyn Storage>>> {
+        Some(self.clone())
+    }
+
+    fn update(&self, chunks: &Chunks, coord: GlobalCoord, multiblock: &[GlobalCoord]) {
+        self.lock().unwrap().update(chunks, footprint(&coord, multiblock));
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+}
+
+impl Storage for PowerGenerator {
+    fn storage(&self) -> &[PossibleItem] {
+        &self.storage
+    }
+
+    fn mut_storage(&mut self) -> &mut [PossibleItem] {
+        &mut self.storage
+    }
+
+    /// Manipulators don't take the fuel back.
+    fn take_first_matching(&mut self, _: u32, _: &dyn Fn(u32) -> bool) -> Option<(Item, usize)> {
+        None
+    }
+
+    fn add(&mut self, item: &Item, _: bool) -> Option<Item> {
+        if ITEMS().fuel(item.id()).is_none() {return Some(*item)};
+        self.storage[0].try_add_item(item)
+    }
+}
+
+impl Default for PowerGenerator {
+    fn default() -> Self {
+        Self { storage: [PossibleItem::new_none()], burner: Burner::default(), load: 0.0, last_update: None }
+    }
+}
+
+impl Draw for PowerGenerator {
+    fn draw(&mut self, ui: &mut egui::Ui, atlas: Arc<TextureAtlas>, inventory: Arc<Mutex<PlayerInventory>>) {
+        let mut task = false;
+        ui.horizontal(|ui| {
+            task = ui.add(inventory_slot(&atlas, &self.storage[0])).drag_started();
+            ui.add(egui::ProgressBar::new(self.burner.progress()));
+        });
+        ui.label(format!("{} {}", tr("ui.power.supply"), power_text(Self::POWER * self.load)));
+
+        if task {
+            let Some(item) = self.storage[0].0.take() else {return};
+            let remainder = inventory.lock().unwrap().add(&item, true);
+            if let Some(r) = remainder {self.set(&r, 0)}
+        }
+    }
+}
+
+impl PlayerUnlockable for PowerGenerator {
+    fn get_storage(&self) -> Option<&dyn Storage> {
+        Some(self)
+    }
+
+    fn get_mut_storage(&mut self) -> Option<&mut dyn Storage> {
+        Some(self)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,54 @@
+use std::sync::{Arc, Mutex, Weak};
+
+use serde::{Deserialize, Serialize};
+use graphics_engine::texture::TextureAtlas;
+
+use crate::{coords::global_coord::GlobalCoord, direction::Direction, gui::draw::Draw, lang::tr, live_voxel_default_deserialize, player::inventory::PlayerInventory, player_unlockable, power::{power_text, NetworkStats}, voxels::chunks::Chunks};
+
+use super::{LiveVoxelBehavior, LiveVoxelCreation, PlayerUnlockable};
+
+/// Wires the poles in reach into one network and connects the machines around it.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PowerPole {
+    /// Balance of the network of the pole, shown to the player
+    #[serde(skip)]
+    stats: Option<NetworkStats>,
+}
+
+impl LiveVoxelCreation for Arc<Mutex<PowerPole>> {
+    fn create(_: &Direction) -> Box<dyn LiveVoxelBehavior> {
+        Box::new(Arc::new(Mutex::new(PowerPole::default())))
+    }
+
+    live_voxel_default_deserialize!(Arc<Mutex<PowerPole>>);
+}
+
+impl LiveVoxelBehavior for Arc<Mutex<PowerPole>> {
+    player_unlockable!();
+
+    /// Placed and loaded poles are wired on their first update.
+    fn update(&self, chunks: &Chunks, coord: GlobalCoord, _: &[GlobalCoord]) {
+        let mut grid = chunks.power.lock().unwrap();
+        grid.add_pole(coord);
+        self.lock().unwrap().stats = grid.pole_network(coord).and_then(|network| grid.stats(network));
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+}
+
+impl Draw for PowerPole {
+    fn draw(&mut self, ui: &mut egui::Ui, _: Arc<TextureAtlas>, _: Arc<Mutex<PlayerInventory>>) {
+        let Some(stats) = self.stats else {
+            ui.label(tr("ui.power.no_network"));
+            return;
+        };
+        ui.label(format!("{} {}", tr("ui.power.supply"), power_text(stats.supply)));
+        ui.label(format!("{} {}", tr("ui.power.demand"), power_text(stats.demand)));
+        ui.label(tr("ui.power.satisfaction"));
+        ui.add(egui::ProgressBar::new(stats.satisfaction).show_percentage());
+    }
+}
+
+impl PlayerUnlockable for PowerPole {}
\ No newline at end of file
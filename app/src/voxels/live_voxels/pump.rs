@@ -0,0 +1,117 @@
+use std::sync::{Arc, Mutex, Weak};
+
+use serde::{Deserialize, Serialize};
+use graphics_engine::texture::TextureAtlas;
+
+use crate::{coords::global_coord::GlobalCoord, direction::Direction, gui::{draw::Draw, my_widgets::fluid_bar::fluid_bar}, live_voxel_default_deserialize, player::inventory::PlayerInventory, player_unlockable, recipes::{fluid::{FluidBox, Flow}, fluid_storage::FluidStorage}, voxels::chunks::Chunks, world::clock};
+
+use super::{fluid_flow::{neighbour, transfer}, LiveVoxelBehavior, LiveVoxelCreation, PlayerUnlockable};
+
+/// Sucks fluid in from behind and pushes it forward whatever the levels are.
+/// Only the block behind it can fill it, so the fluid never flows back.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Pump {
+    fluid_box: [FluidBox; 1],
+    direction: [i8; 3],
+    last_update: Option<u64>,
+}
+
+impl Pump {
+    pub const CAPACITY: f32 = 200.0;
+    /// Fluid pushed in a second with full power
+    pub const RATE: f32 = 1200.0;
+    /// Watts used while pumping
+    pub const POWER: f32 = 30_000.0;
+
+    pub fn new(direction: &Direction) -> Self {
+        Self {
+            fluid_box: [FluidBox::new(Self::CAPACITY, Flow::Input)],
+            direction: direction.simplify_to_one_greatest(true, false, true),
+            last_update: None,
+        }
+    }
+
+    fn front(&self) -> [i32; 3] {
+        self.direction.map(i32::from)
+    }
+
+    /// Pumps slower by the power satisfaction of its network.
+    pub fn update(&mut self, chunks: &Chunks, coord: GlobalCoord) {
+        let elapsed = clock::elapsed(&mut self.last_update);
+        let target = neighbour(chunks, coord, self.front());
+        let power = if target.is_some() && self.fluid_box[0].fluid.is_some() {Self::POWER} else {0.0};
+        let satisfaction = chunks.power.lock().unwrap().demand(&[coord], power);
+        let Some(target) = target else {return};
+
+        let mut max = Self::RATE * elapsed.as_secs_f32() * satisfaction;
+        for target_box in target.lock().unwrap().mut_fluid_boxes().iter_mut().filter(|b| b.flow != Flow::Output) {
+            max -= transfer(&mut self.fluid_box[0], target_box, max);
+        }
+    }
+}
+
+impl LiveVoxelCreation for Arc<Mutex<Pump>> {
+    fn create(direction: &Direction) -> Box<dyn LiveVoxelBehavior> {
+        Box::new(Arc::new(Mutex::new(Pump::new(direction))))
+    }
+
+    live_voxel_default_deserialize!(Arc<Mutex<Pump>>);
+}
+
+impl LiveVoxelBehavior for Arc<Mutex<Pump>> {
+    player_unlockable!();
+
+    fn fluid_storage(&self) -> Option<Arc<Mutex<dyn FluidStorage>>> {
+        Some(self.clone())
+    }
+
+    fn update(&self, chunks: &Chunks, coord: GlobalCoord, _: &[GlobalCoord]) {
+        self.lock().unwrap().update(chunks, coord);
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+}
+
+impl FluidStorage for Pump {
+    fn fluid_boxes(&self) -> &[FluidBox] {
+        &self.fluid_box
+    }
+
+    fn mut_fluid_boxes(&mut self) -> &mut [FluidBox] {
+        &mut self.fluid_box
+    }
+
+    fn connects(&self, side: [i32; 3]) -> bool {
+        side == self.front().map(|c| -c)
+    }
+}
+
+impl Draw for Pump {
+    fn draw(&mut self, ui: &mut egui::Ui, _: Arc<TextureAtlas>, _: Arc<Mutex<PlayerInventory>>) {
+        ui.add(fluid_bar(&self.fluid_box[0]));
+    }
+}
+
+impl PlayerUnlockable for Pump {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{recipes::fluid::Fluid, save_load::decode_exact};
+
+    use super::*;
+
+    #[test]
+    fn pump_is_saved_with_its_direction_and_fluid() {
+        let mut pump = Pump::new(&Direction::new(0.0, 0.0, -1.0));
+        pump.fluid_box[0].fill(&Fluid::new(0, 150.0));
+        let bytes = Arc::new(Mutex::new(pump)).to_bytes();
+
+        let loaded: Pump = decode_exact(&<Arc<Mutex<Pump>>>::from_bytes(&bytes).to_bytes()).unwrap();
+        assert_eq!(loaded.front(), [0, 0, -1]);
+        assert!(loaded.connects([0, 0, 1]) && !loaded.connects([0, 0, -1]));
+        assert_eq!(loaded.fluid_box[0].flow, Flow::Input);
+        assert_eq!(loaded.fluid_box[0].fluid, Some(Fluid::new(0, 150.0)));
+    }
+}
@@ -0,0 +1,67 @@
+use std::sync::{Arc, Mutex, Weak};
+
+use rhai::{Dynamic, Map};
+use serde::{Deserialize, Serialize};
+
+use crate::{coords::global_coord::GlobalCoord, direction::Direction, recipes::storage::Storage, scripting::world::Access, voxels::chunks::Chunks};
+
+use super::{voxel_box::VoxelBox, LiveVoxelBehavior, LiveVoxelCreation, PlayerUnlockable};
+
+/// Live voxel of the blocks with `"live_voxel": "script"`.
+/// Every update calls `update(world, x, y, z)` from the block script with `this` bound to a map that is saved with the world.
+#[derive(Debug)]
+pub struct ScriptedLiveVoxel {
+    storage: Arc<Mutex<VoxelBox>>,
+    state: Mutex<Dynamic>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ScriptedLiveVoxelFile {
+    storage: VoxelBox,
+    /// The script state as json, it can't be stored with bincode directly
+    state: String,
+}
+
+impl LiveVoxelCreation for ScriptedLiveVoxel {
+    fn create(_: &Direction) -> Box<dyn LiveVoxelBehavior> {
+        Box::new(Self { storage: Arc::new(Mutex::new(VoxelBox::new())), state: Mutex::new(Map::new().into()) })
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Box<dyn LiveVoxelBehavior> {
+        let file = bincode::deserialize::<ScriptedLiveVoxelFile>(bytes)
+            .expect("Deserialization error on type: ScriptedLiveVoxel");
+        let state = serde_json::from_str::<Dynamic>(&file.state).unwrap_or_else(|err| {
+            eprintln!("Failed to load the state of a scripted live voxel: {}", err);
+            Map::new().into()
+        });
+        Box::new(Self { storage: Arc::new(Mutex::new(file.storage)), state: Mutex::new(state) })
+    }
+}
+
+impl LiveVoxelBehavior for ScriptedLiveVoxel {
+    fn player_unlockable(&self) -> Option<Weak<Mutex<dyn PlayerUnlockable>>> {
+        self.storage.player_unlockable()
+    }
+
+    fn storage(&self) -> Option<Arc<Mutex<dyn Storage>>> {
+        Some(self.storage.clone())
+    }
+
+    fn update(&self, chunks: &Chunks, coord: GlobalCoord, _: &[GlobalCoord]) {
+        let Some(voxel) = chunks.voxel_global(coord) else {return};
+        // Looked up on every update, so reloaded scripts are picked up.
+        let block = chunks.content.blocks[voxel.id as usize].load();
+        let Some(script) = block.script.as_ref() else {return};
+        let mut state = self.state.lock().unwrap();
+        script.call("update", Access::Chunks(chunks), Some(&mut state), (coord.x as i64, coord.y as i64, coord.z as i64));
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let state = serde_json::to_string(&*self.state.lock().unwrap()).unwrap_or_else(|err| {
+            eprintln!("Failed to save the state of a scripted live voxel: {}", err);
+            String::from("{}")
+        });
+        let storage = self.storage.lock().unwrap().clone();
+        bincode::serialize(&ScriptedLiveVoxelFile { storage, state }).unwrap()
+    }
+}
\ No newline at end of file
@@ -147,13 +147,14 @@ impl Storage for TransportBelt {
         &mut self.storage
     }
 
-    fn take_first_existing(&mut self, max_count: u32) -> Option<(Item, usize)> {
+    fn take_first_matching(&mut self, max_count: u32, filter: &dyn Fn(u32) -> bool) -> Option<(Item, usize)> {
         for (index, (_, possible_item)) in self.item_progress
           .iter()
           .zip(self.storage.iter_mut())
           .enumerate()
           .sorted_by(|(_, (a, _)), (_, (b, _))| (*a - 0.5).abs().total_cmp(&(*b - 0.5).abs()))
         {
+            if !possible_item.0.is_some_and(|item| filter(item.id())) {continue};
             let Some(item) = possible_item.try_take(max_count) else {continue};
             self.item_progress[index] = 0.0;
             return Some((item, index))
@@ -0,0 +1,103 @@
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{coords::global_coord::GlobalCoord, direction::Direction, recipes::{item::PossibleItem, storage::Storage}, voxels::{chunks::Chunks, live_voxels::{missing::MissingLiveVoxel, LiveVoxelBehavior}}};
+
+use super::WasmPlugin;
+
+/// Copy of the storage of a wasm live voxel that the rest of the game works with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WasmStorage {
+    storage: Vec<PossibleItem>,
+}
+
+impl Storage for WasmStorage {
+    fn storage(&self) -> &[PossibleItem] {
+        &self.storage
+    }
+
+    fn mut_storage(&mut self) -> &mut [PossibleItem] {
+        &mut self.storage
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct WasmLiveVoxelFile {
+    storage: Option<WasmStorage>,
+    /// Bytes returned by `to_bytes` of the module
+    data: Vec<u8>,
+}
+
+/// Live voxel that lives in the memory of a wasm plugin and is reached through its handle.
+#[derive(Debug)]
+pub struct WasmLiveVoxel {
+    plugin: Arc<WasmPlugin>,
+    handle: i32,
+    storage: Option<Arc<Mutex<WasmStorage>>>,
+}
+
+impl WasmLiveVoxel {
+    pub fn create(plugin: &Arc<WasmPlugin>, kind: i32, direction: &Direction) -> Box<dyn LiveVoxelBehavior> {
+        let Some(handle) = plugin.create(kind, direction.simplify_to_one_greatest(true, true, true)) else {
+            return Box::new(());
+        };
+        let size = plugin.storage_size(handle);
+        let storage = (size > 0).then(|| {
+            let items = plugin.storage_get(handle, size).unwrap_or_default();
+            let mut storage = vec![PossibleItem::new_none(); size];
+            storage.iter_mut().zip(items).for_each(|(slot, item)| slot.0 = item);
+            Arc::new(Mutex::new(WasmStorage { storage }))
+        });
+        Box::new(Self { plugin: plugin.clone(), handle, storage })
+    }
+
+    /// Live voxels the plugin fails to load are kept as they were saved.
+    pub fn load(plugin: &Arc<WasmPlugin>, kind: i32, bytes: &[u8]) -> Box<dyn LiveVoxelBehavior> {
+        let loaded = bincode::deserialize::<WasmLiveVoxelFile>(bytes).ok()
+            .and_then(|file| plugin.load(kind, &file.data).map(|handle| (handle, file.storage)));
+        let Some((handle, storage)) = loaded else {
+            return Box::new(MissingLiveVoxel(bytes.to_vec()));
+        };
+        if let Some(storage) = &storage {
+            plugin.storage_set(handle, &storage.storage.iter().map(|slot| slot.0).collect::<Vec<_>>());
+        }
+        Box::new(Self { plugin: plugin.clone(), handle, storage: storage.map(|s| Arc::new(Mutex::new(s))) })
+    }
+}
+
+impl LiveVoxelBehavior for WasmLiveVoxel {
+    fn storage(&self) -> Option<Arc<Mutex<dyn Storage>>> {
+        self.storage.clone().map(|storage| storage as Arc<Mutex<dyn Storage>>)
+    }
+
+    fn update(&self, chunks: &Chunks, coord: GlobalCoord, _: &[GlobalCoord]) {
+        let Some(storage) = &self.storage else {
+            self.plugin.update(self.handle, chunks, coord);
+            return;
+        };
+        // Not locked during the update, so the host functions called by the module can't deadlock on it.
+        let snapshot = storage.lock().unwrap().storage.iter().map(|slot| slot.0).collect::<Vec<_>>();
+        self.plugin.storage_set(self.handle, &snapshot);
+        self.plugin.update(self.handle, chunks, coord);
+        let Some(items) = self.plugin.storage_get(self.handle, snapshot.len()) else {return};
+        // Only the slots the module changed, the others may have been changed by manipulators meanwhile.
+        storage.lock().unwrap().storage.iter_mut().zip(items.into_iter().zip(snapshot))
+            .filter(|(_, (item, before))| item != before)
+            .for_each(|(slot, (item, _))| slot.0 = item);
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let file = WasmLiveVoxelFile {
+            storage: self.storage.as_ref().map(|storage| storage.lock().unwrap().clone()),
+            data: self.plugin.to_bytes(self.handle).unwrap_or_default(),
+        };
+        bincode::serialize(&file).unwrap()
+    }
+}
+
+impl Drop for WasmLiveVoxel {
+    fn drop(&mut self) {
+        self.plugin.release(self.handle);
+    }
+}
\ No newline at end of file
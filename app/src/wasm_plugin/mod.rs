@@ -0,0 +1,387 @@
+//! Content pack mods compiled to WebAssembly.
+//!
+//! A pack ships `mod.wasm`. Unlike native plugins it runs in a sandbox: it only sees the functions below,
+//! its memory and running time are limited and a trap stops the call instead of the game.
+//!
+//! Imports from the `manufactory` module:
+//! - `log(ptr: i32, len: i32)`
+//! - `register_live_voxel(ptr: i32, len: i32) -> i32` returns the kind passed to `create` and `from_bytes`,
+//!   only works during `init`
+//! - `item_id(ptr: i32, len: i32) -> i32`, -1 for unknown items, names are looked up in the pack of the mod first
+//! - `block(x: i32, y: i32, z: i32) -> i32`, -1 if the chunk is not loaded
+//! - `storage_add(x: i32, y: i32, z: i32, item: i32, count: i32) -> i32` returns how many were added
+//! - `storage_remove(x: i32, y: i32, z: i32, item: i32, count: i32) -> i32` returns how many were removed
+//!
+//! The world functions only work during `update`, names are UTF-8 strings in the module memory.
+//!
+//! Exports:
+//! - `memory` and `alloc(len: i32) -> i32`
+//! - `init()`
+//! - `create(kind: i32, dx: i32, dy: i32, dz: i32) -> i32` returns the handle of a new live voxel
+//! - `from_bytes(kind: i32, ptr: i32, len: i32) -> i32` returns the handle of a loaded live voxel
+//! - `to_bytes(handle: i32) -> i64` returns `ptr << 32 | len`
+//! - `update(handle: i32, x: i32, y: i32, z: i32)`
+//! - `drop(handle: i32)`, optional
+//! - `storage_size(handle: i32) -> i32`, `storage_get(handle: i32, slot: i32) -> i64` returning `item << 32 | count`
+//!   or -1 for an empty slot and `storage_set(handle: i32, slot: i32, item: i32, count: i32)` with -1 to clear,
+//!   optional. The game keeps a copy of the storage, it is written to the module before `update`,
+//!   the slots the module changed are read back after it.
+
+use std::{fmt::Display, path::Path, sync::{Arc, Mutex}};
+
+use wasmi::{Caller, Config, Engine, Extern, Instance, Linker, Memory, Module, Store, StoreContext, StoreLimits, StoreLimitsBuilder, TypedFunc};
+
+use crate::{content_loader::indices::resolve, coords::global_coord::GlobalCoord, recipes::{item::Item, items::ITEMS, storage::Storage}, voxels::chunks::Chunks, Registrator};
+
+use self::live_voxel::WasmLiveVoxel;
+
+pub mod live_voxel;
+
+/// Instructions a single call may run.
+const FUEL: u64 = 10_000_000;
+const MEMORY_LIMIT: usize = 64 << 20;
+
+#[derive(Debug)]
+pub enum WasmPluginError {
+    Io(std::io::Error),
+    Wasm(wasmi::Error),
+    MissingExport(&'static str),
+}
+
+impl Display for WasmPluginError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WasmPluginError::Io(err) => write!(f, "failed to read the module: {}", err),
+            WasmPluginError::Wasm(err) => write!(f, "{}", err),
+            WasmPluginError::MissingExport(name) => write!(f, "the module does not export \"{}\"", name),
+        }
+    }
+}
+
+impl std::error::Error for WasmPluginError {}
+
+impl From<wasmi::Error> for WasmPluginError {
+    fn from(err: wasmi::Error) -> Self {Self::Wasm(err)}
+}
+
+#[derive(Clone, Copy)]
+struct ChunksPtr(*const Chunks);
+
+// Only set while `update` runs on the thread that owns the chunks reference.
+unsafe impl Send for ChunksPtr {}
+unsafe impl Sync for ChunksPtr {}
+
+struct HostState {
+    pack: String,
+    /// Item names are looked up in the pack of the mod first
+    prefix: String,
+    /// Live voxel kinds registered during `init`
+    kinds: Option<Vec<String>>,
+    chunks: Option<ChunksPtr>,
+    limits: StoreLimits,
+}
+
+impl HostState {
+    fn chunks(&self) -> Option<&Chunks> {
+        self.chunks.map(|ptr| unsafe {&*ptr.0})
+    }
+}
+
+struct Exports {
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    create: TypedFunc<(i32, i32, i32, i32), i32>,
+    from_bytes: TypedFunc<(i32, i32, i32), i32>,
+    to_bytes: TypedFunc<i32, i64>,
+    update: TypedFunc<(i32, i32, i32, i32), ()>,
+    drop: Option<TypedFunc<i32, ()>>,
+    storage_size: Option<TypedFunc<i32, i32>>,
+    storage_get: Option<TypedFunc<(i32, i32), i64>>,
+    storage_set: Option<TypedFunc<(i32, i32, i32, i32), ()>>,
+}
+
+struct Runtime {
+    store: Store<HostState>,
+    exports: Exports,
+}
+
+/// A loaded module, shared by all of its live voxels.
+pub struct WasmPlugin {
+    pack: String,
+    runtime: Mutex<Runtime>,
+}
+
+impl std::fmt::Debug for WasmPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "WasmPlugin({})", self.pack)
+    }
+}
+
+/// Copies only a range inside the memory, the module can't make the game allocate more than it has.
+fn read_memory<'a, T: 'a>(memory: Memory, ctx: impl Into<StoreContext<'a, T>>, ptr: usize, len: usize) -> Option<Vec<u8>> {
+    memory.data(ctx).get(ptr..ptr.checked_add(len)?).map(<[u8]>::to_vec)
+}
+
+fn read_str(caller: &Caller<'_, HostState>, ptr: i32, len: i32) -> Option<String> {
+    let memory = caller.get_export("memory").and_then(Extern::into_memory)?;
+    let bytes = read_memory(memory, caller, usize::try_from(ptr).ok()?, usize::try_from(len).ok()?)?;
+    String::from_utf8(bytes).ok()
+}
+
+fn storage_of(caller: &Caller<'_, HostState>, x: i32, y: i32, z: i32) -> Option<Arc<Mutex<dyn Storage>>> {
+    caller.data().chunks()?.master_live_voxel(GlobalCoord::new(x, y, z))?.storage()
+}
+
+fn item(id: i32, count: i32) -> Option<Item> {
+    let id = u32::try_from(id).ok()?;
+    ITEMS().get(id)?;
+    Some(Item::new(id, u32::try_from(count).ok()?))
+}
+
+fn linker(engine: &Engine) -> Result<Linker<HostState>, wasmi::Error> {
+    let mut linker = Linker::<HostState>::new(engine);
+    linker.func_wrap("manufactory", "log", |caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+        let message = read_str(&caller, ptr, len).unwrap_or_default();
+        println!("[{}] {}", caller.data().pack, message);
+    })?;
+    linker.func_wrap("manufactory", "register_live_voxel", |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> i32 {
+        let Some(name) = read_str(&caller, ptr, len) else {return -1};
+        let Some(kinds) = caller.data_mut().kinds.as_mut() else {return -1};
+        kinds.push(name);
+        (kinds.len() - 1) as i32
+    })?;
+    linker.func_wrap("manufactory", "item_id", |caller: Caller<'_, HostState>, ptr: i32, len: i32| -> i32 {
+        read_str(&caller, ptr, len).and_then(|name| resolve(&ITEMS().indices, &caller.data().prefix, &name).copied())
+            .map_or(-1, |id| id as i32)
+    })?;
+    linker.func_wrap("manufactory", "block", |caller: Caller<'_, HostState>, x: i32, y: i32, z: i32| -> i32 {
+        caller.data().chunks()
+            .and_then(|chunks| chunks.voxel_global(GlobalCoord::new(x, y, z)))
+            .map_or(-1, |voxel| voxel.id as i32)
+    })?;
+    linker.func_wrap("manufactory", "storage_add", |caller: Caller<'_, HostState>, x: i32, y: i32, z: i32, id: i32, count: i32| -> i32 {
+        let (Some(storage), Some(item)) = (storage_of(&caller, x, y, z), item(id, count)) else {return 0};
+        let remainder = storage.lock().unwrap().add(&item, false).map_or(0, |r| r.count);
+        (item.count - remainder) as i32
+    })?;
+    linker.func_wrap("manufactory", "storage_remove", |caller: Caller<'_, HostState>, x: i32, y: i32, z: i32, id: i32, count: i32| -> i32 {
+        let (Some(storage), Some(item)) = (storage_of(&caller, x, y, z), item(id, count)) else {return 0};
+        let mut storage = storage.lock().unwrap();
+        let existing: u32 = storage.storage().iter().map(|possible_item| possible_item.contains(item.id())).sum();
+        let removed = item.count.min(existing);
+        storage.remove(&Item::new(item.id(), removed));
+        removed as i32
+    })?;
+    Ok(linker)
+}
+
+impl WasmPlugin {
+    /// Instantiates the module and runs its `init`.
+    /// Returns the plugin together with the live voxel kinds it registered.
+    fn new(bytes: &[u8], pack: &str) -> Result<(Arc<Self>, Vec<String>), WasmPluginError> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, bytes)?;
+        let state = HostState {
+            pack: pack.to_string(),
+            prefix: format!("@{}:", pack),
+            kinds: None,
+            chunks: None,
+            limits: StoreLimitsBuilder::new().memory_size(MEMORY_LIMIT).build(),
+        };
+        let mut store = Store::new(&engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(FUEL).map_err(wasmi::Error::from)?;
+        let instance = linker(&engine)?.instantiate(&mut store, &module)?.start(&mut store)?;
+
+        let exports = Exports {
+            memory: instance.get_memory(&store, "memory").ok_or(WasmPluginError::MissingExport("memory"))?,
+            alloc: typed(&instance, &store, "alloc")?,
+            create: typed(&instance, &store, "create")?,
+            from_bytes: typed(&instance, &store, "from_bytes")?,
+            to_bytes: typed(&instance, &store, "to_bytes")?,
+            update: typed(&instance, &store, "update")?,
+            drop: typed(&instance, &store, "drop").ok(),
+            storage_size: typed(&instance, &store, "storage_size").ok(),
+            storage_get: typed(&instance, &store, "storage_get").ok(),
+            storage_set: typed(&instance, &store, "storage_set").ok(),
+        };
+        let init: TypedFunc<(), ()> = typed(&instance, &store, "init")?;
+
+        store.data_mut().kinds = Some(Vec::new());
+        init.call(&mut store, ())?;
+        let kinds = store.data_mut().kinds.take().unwrap_or_default();
+
+        let plugin = Self { pack: pack.to_string(), runtime: Mutex::new(Runtime { store, exports }) };
+        Ok((Arc::new(plugin), kinds))
+    }
+
+    /// Runs `f` with fresh fuel and reports a trap with the name of the pack.
+    fn call<T>(&self, name: &str, chunks: Option<&Chunks>, f: impl FnOnce(&mut Runtime) -> Result<T, wasmi::Error>) -> Option<T> {
+        let mut runtime = self.runtime.lock().unwrap();
+        runtime.store.set_fuel(FUEL).ok()?;
+        runtime.store.data_mut().chunks = chunks.map(|chunks| ChunksPtr(chunks));
+        let result = f(&mut runtime);
+        runtime.store.data_mut().chunks = None;
+        result.map_err(|err| eprintln!("The mod \"{}\" failed in \"{}\": {}", self.pack, name, err)).ok()
+    }
+
+    fn create(&self, kind: i32, direction: [i8; 3]) -> Option<i32> {
+        let [dx, dy, dz] = direction.map(i32::from);
+        self.call("create", None, |r| r.exports.create.call(&mut r.store, (kind, dx, dy, dz)))
+    }
+
+    fn load(&self, kind: i32, bytes: &[u8]) -> Option<i32> {
+        self.call("from_bytes", None, |r| {
+            let ptr = r.exports.alloc.call(&mut r.store, bytes.len() as i32)?;
+            r.exports.memory.write(&mut r.store, ptr as usize, bytes).map_err(wasmi::Error::from)?;
+            r.exports.from_bytes.call(&mut r.store, (kind, ptr, bytes.len() as i32))
+        })
+    }
+
+    fn to_bytes(&self, handle: i32) -> Option<Vec<u8>> {
+        self.call("to_bytes", None, |r| {
+            let packed = r.exports.to_bytes.call(&mut r.store, handle)? as u64;
+            read_memory(r.exports.memory, &r.store, (packed >> 32) as usize, (packed & 0xFFFF_FFFF) as usize)
+                .ok_or_else(|| wasmi::Error::new("the returned bytes are outside of the memory"))
+        })
+    }
+
+    fn update(&self, handle: i32, chunks: &Chunks, coord: GlobalCoord) {
+        self.call("update", Some(chunks), |r| r.exports.update.call(&mut r.store, (handle, coord.x, coord.y, coord.z)));
+    }
+
+    fn release(&self, handle: i32) {
+        self.call("drop", None, |r| match r.exports.drop {
+            Some(drop) => drop.call(&mut r.store, handle),
+            None => Ok(()),
+        });
+    }
+
+    fn storage_size(&self, handle: i32) -> usize {
+        self.call("storage_size", None, |r| match r.exports.storage_size {
+            Some(size) => size.call(&mut r.store, handle),
+            None => Ok(0),
+        }).map_or(0, |size| size.max(0) as usize)
+    }
+
+    fn storage_get(&self, handle: i32, len: usize) -> Option<Vec<Option<Item>>> {
+        self.call("storage_get", None, |r| {
+            let Some(get) = r.exports.storage_get else {return Ok(Vec::new())};
+            (0..len as i32).map(|slot| {
+                let packed = get.call(&mut r.store, (handle, slot))?;
+                Ok((packed >= 0).then(|| Item::new((packed >> 32) as u32, (packed & 0xFFFF_FFFF) as u32)))
+            }).collect()
+        })
+    }
+
+    fn storage_set(&self, handle: i32, items: &[Option<Item>]) {
+        self.call("storage_set", None, |r| {
+            let Some(set) = r.exports.storage_set else {return Ok(())};
+            for (slot, item) in items.iter().enumerate() {
+                let (id, count) = item.map_or((-1, 0), |item| (item.id() as i32, item.count as i32));
+                set.call(&mut r.store, (handle, slot as i32, id, count))?;
+            }
+            Ok(())
+        });
+    }
+}
+
+fn typed<Params: wasmi::WasmParams, Results: wasmi::WasmResults>(
+    instance: &Instance,
+    store: &Store<HostState>,
+    name: &'static str
+) -> Result<TypedFunc<Params, Results>, WasmPluginError> {
+    instance.get_typed_func(store, name).map_err(|_| WasmPluginError::MissingExport(name))
+}
+
+/// Loads `mod.wasm` of a content pack and registers its live voxels.
+/// The plugin is kept alive by the registered constructors for the rest of the run.
+pub fn load_wasm_plugin(path: impl AsRef<Path>, pack: &str, registrator: &mut Registrator) -> Result<(), WasmPluginError> {
+    let bytes = std::fs::read(path).map_err(WasmPluginError::Io)?;
+    let (plugin, kinds) = WasmPlugin::new(&bytes, pack)?;
+    for (kind, name) in kinds.iter().enumerate() {
+        let (new_plugin, load_plugin) = (plugin.clone(), plugin.clone());
+        let kind = kind as i32;
+        registrator.live_voxel(
+            name,
+            Box::leak(Box::new(move |direction: &crate::direction::Direction| WasmLiveVoxel::create(&new_plugin, kind, direction))),
+            Box::leak(Box::new(move |bytes: &[u8]| WasmLiveVoxel::load(&load_plugin, kind, bytes))),
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{direction::Direction, voxels::live_voxels::LiveVoxelBehavior};
+    use super::*;
+
+    /// Two storage slots at 1024, `create` with kind 1 grows the memory past the limit,
+    /// `update` never returns and `to_bytes` points past the end of the memory.
+    const MODULE: &str = r#"
+        (module
+            (memory (export "memory") 1)
+            (data (i32.const 1024) "\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff")
+            (func (export "alloc") (param i32) (result i32) (i32.const 2048))
+            (func (export "init"))
+            (func (export "create") (param $kind i32) (param i32 i32 i32) (result i32)
+                (if (result i32) (i32.eq (local.get $kind) (i32.const 1))
+                    (then (memory.grow (i32.const 1024)))
+                    (else (i32.const 7))))
+            (func (export "from_bytes") (param i32 i32 i32) (result i32) (i32.const 7))
+            (func (export "to_bytes") (param i32) (result i64) (i64.const 0x0000_ff00_0010_0000))
+            (func (export "update") (param i32 i32 i32 i32) (loop $forever (br $forever)))
+            (func (export "storage_size") (param i32) (result i32) (i32.const 2))
+            (func (export "storage_get") (param i32) (param $slot i32) (result i64)
+                (i64.load (i32.add (i32.const 1024) (i32.mul (local.get $slot) (i32.const 8)))))
+            (func (export "storage_set") (param i32) (param $slot i32) (param $item i32) (param $count i32)
+                (i64.store (i32.add (i32.const 1024) (i32.mul (local.get $slot) (i32.const 8)))
+                    (if (result i64) (i32.lt_s (local.get $item) (i32.const 0))
+                        (then (i64.const -1))
+                        (else (i64.or
+                            (i64.shl (i64.extend_i32_u (local.get $item)) (i64.const 32))
+                            (i64.extend_i32_u (local.get $count))))))))
+    "#;
+
+    fn plugin() -> Arc<WasmPlugin> {
+        WasmPlugin::new(&wat::parse_str(MODULE).unwrap(), "test").unwrap().0
+    }
+
+    #[test]
+    fn endless_call_runs_out_of_fuel() {
+        let plugin = plugin();
+        let update = plugin.call("update", None, |r| r.exports.update.call(&mut r.store, (7, 0, 0, 0)));
+        assert!(update.is_none());
+        // The next call gets fresh fuel.
+        assert_eq!(plugin.create(0, [0, 0, 0]), Some(7));
+    }
+
+    #[test]
+    fn memory_does_not_grow_past_the_limit() {
+        assert_eq!(plugin().create(1, [0, 0, 0]), Some(-1));
+    }
+
+    #[test]
+    fn bytes_outside_of_the_memory_are_not_read() {
+        assert_eq!(plugin().to_bytes(7), None);
+    }
+
+    #[test]
+    fn storage_is_written_back_to_the_module() {
+        let plugin = plugin();
+        assert_eq!(plugin.storage_get(7, 2), Some(vec![None, None]));
+        let items = [Some(Item::new(3, 5)), None];
+        plugin.storage_set(7, &items);
+        assert_eq!(plugin.storage_get(7, 2), Some(items.to_vec()));
+
+        // A loaded live voxel gives its saved storage to the module.
+        let voxel = WasmLiveVoxel::create(&plugin, 0, &Direction::new_x());
+        let saved = voxel.to_bytes();
+        plugin.storage_set(7, &[None, None]);
+        let loaded = WasmLiveVoxel::load(&plugin, 0, &saved);
+        assert!(loaded.storage().is_some());
+        assert_eq!(plugin.storage_get(7, 2), Some(items.to_vec()));
+    }
+}
@@ -0,0 +1,142 @@
+use std::{path::{Path, PathBuf}, sync::{atomic::{AtomicU64, Ordering}, RwLock}, time::Duration};
+
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::coords::global_coord::GlobalCoord;
+
+/// Ticks simulated in a second of game time
+pub const TICKS_PER_SECOND: u64 = 20;
+/// Game time of one tick
+pub const TICK: Duration = Duration::from_millis(1000 / TICKS_PER_SECOND);
+
+/// Ticks of the open world, advanced only by the voxel data updater.
+static TICKS: AtomicU64 = AtomicU64::new(0);
+
+/// Current tick of the open world.
+pub fn now() -> u64 {
+    TICKS.load(Ordering::Acquire)
+}
+
+/// Ends the tick, called after every live voxel and the power networks were updated.
+pub fn advance() {
+    TICKS.fetch_add(1, Ordering::AcqRel);
+}
+
+/// Game time of `ticks`.
+pub fn duration(ticks: u64) -> Duration {
+    Duration::from_nanos(TICK.as_nanos() as u64 * ticks)
+}
+
+/// Game time passed since the tick.
+pub fn since(tick: u64) -> Duration {
+    duration(now().saturating_sub(tick))
+}
+
+/// Game time since the previous call with the same `last_update`, zero on the first one.
+pub fn elapsed(last_update: &mut Option<u64>) -> Duration {
+    let now = now();
+    last_update.replace(now).map_or(Duration::ZERO, |last| duration(now.saturating_sub(last)))
+}
+
+/// Random numbers that are the same for the same tick and `salts`,
+/// so that chances roll the same way when the world is simulated again.
+pub fn rng(salts: &[u64]) -> StdRng {
+    seeded(now(), salts)
+}
+
+fn seeded(tick: u64, salts: &[u64]) -> StdRng {
+    // Multiplying by an odd number and rotating keep different salts apart.
+    StdRng::seed_from_u64(salts.iter().fold(tick, |seed, salt| (seed ^ salt).wrapping_mul(0x9E37_79B9_7F4A_7C15).rotate_left(31)))
+}
+
+/// Salts of a block, so that the machines finishing on the same tick roll apart.
+pub fn block_salts(coord: GlobalCoord) -> [u64; 3] {
+    [coord.x, coord.y, coord.z].map(|c| c as u64)
+}
+
+/// Measures game time instead of wall-clock time, so it is saved with the machine
+/// and doesn't run while the world is closed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Stopwatch {
+    start: u64,
+    /// Time that doesn't count, for example while the machine had no power
+    delay: Duration,
+}
+
+impl Stopwatch {
+    pub fn start() -> Self {
+        Self { start: now(), delay: Duration::ZERO }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        since(self.start).saturating_sub(self.delay)
+    }
+
+    pub fn delay(&mut self, time: Duration) {
+        self.delay += time;
+    }
+}
+
+/// `clock.json` of a world.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ClockFile {
+    ticks: u64,
+}
+
+static CLOCK_PATH: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Loads the tick of the world that is being opened, new worlds start from zero.
+pub fn load_clock(world: &Path) {
+    let path = world.join("clock.json");
+    let file = match std::fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice::<ClockFile>(&bytes).unwrap_or_else(|err| {
+            eprintln!("Failed to parse clock {:?}: {}", path, err);
+            ClockFile::default()
+        }),
+        Err(_) => ClockFile::default(),
+    };
+    TICKS.store(file.ticks, Ordering::Release);
+    *CLOCK_PATH.write().unwrap() = Some(path);
+}
+
+pub fn save_clock() {
+    let path = CLOCK_PATH.read().unwrap();
+    let Some(path) = path.as_ref() else {return};
+    let file = ClockFile { ticks: now() };
+    if let Err(err) = std::fs::write(path, serde_json::to_vec_pretty(&file).unwrap()) {
+        eprintln!("Clock write error: {}", err);
+    }
+}
+
+/// Forgets the tick of the closed world, it is saved by the save thread.
+pub fn unload_clock() {
+    *CLOCK_PATH.write().unwrap() = None;
+    TICKS.store(0, Ordering::Release);
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+
+    fn roll(tick: u64, salts: &[u64]) -> u64 {
+        seeded(tick, salts).gen()
+    }
+
+    #[test]
+    fn one_seed_always_rolls_the_same() {
+        assert_eq!(roll(40, &[3, 1, 2, 3]), roll(40, &[3, 1, 2, 3]));
+        assert_ne!(roll(40, &[3, 1, 2, 3]), roll(41, &[3, 1, 2, 3]));
+    }
+
+    #[test]
+    fn machines_on_the_same_tick_roll_apart() {
+        let salts = |x| block_salts(GlobalCoord::new(x, 8, -4));
+        assert_ne!(roll(40, &salts(1)), roll(40, &salts(2)));
+        // A result with half a chance drops from some machines, not from all or none.
+        let dropped = (0..32).filter(|&x| seeded(40, &salts(x)).gen_bool(0.5)).count();
+        assert!(dropped > 0 && dropped < 32);
+    }
+}
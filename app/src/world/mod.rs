@@ -4,6 +4,7 @@ use std::{sync::{Arc}, time::Instant};
 use crate::{bytes::BytesCoder, content::Content, coords::global_coord::GlobalCoord, light::light::{LightSolvers, ADD_QUEUE_CAP, REMOVE_QUEUE_CAP}, save_load::{EncodedChunk, WorldRegions}, voxels::{chunk::{Chunk, CHUNK_VOLUME}, chunks::{Chunks, WORLD_HEIGHT}, generator::Generator, voxel::Voxel}};
 
 pub mod sun;
+pub mod clock;
 pub mod loader;
 
 
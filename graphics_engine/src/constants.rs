@@ -2,6 +2,8 @@ pub const BLOCK_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8
 pub const BLOCK_TEXTURE_SIZE: u32 = 32;
 // Maximum mipmap_count is BASE_SIZE.ilog2() + 1 (img size 1px) but it's too small
 pub const BLOCK_MIPMAP_COUNT: usize = BLOCK_TEXTURE_SIZE.ilog2() as usize;
+/// Size of one icon in the item atlas, the atlas has as many rows as fit
+pub const ITEM_ICON_SIZE: u32 = 32;
 pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
 
 pub const IS_LINE_TOPOLOGY: bool = false;
@@ -3,7 +3,7 @@ use itertools::Itertools;
 use wgpu::{util::DeviceExt, TextureFormat, TextureFormatFeatureFlags, Adapter};
 use winit::window::Window;
 use crate::bind_group::block_texture;
-use crate::constants::{BLOCK_MIPMAP_COUNT, BLOCK_TEXTURE_SIZE};
+use crate::constants::{BLOCK_MIPMAP_COUNT, BLOCK_TEXTURE_SIZE, ITEM_ICON_SIZE};
 use crate::models::load_model::load_models;
 use crate::{bind_group, bind_group_layout::Layouts, models::load_animated_model::load_animated_model, pipeline::Pipelines, rev_qumark, shaders::Shaders, texture::Texture};
 use crate::texture::TextureAtlas;
@@ -204,7 +204,7 @@ impl<'a> State<'a> {
         let mut egui = Egui::new(&device, &window, surface_format, sample_count);
         // TODO: Make texture atlas from files not one file.
         
-        let texture_atlas = TextureAtlas::new(egui.renderer(), &device, &queue, atlas_size, atlas, 4);
+        let texture_atlas = TextureAtlas::new(egui.renderer(), &device, &queue, atlas_size, atlas, atlas_size / ITEM_ICON_SIZE);
        
         let shaders = Shaders::new(&device);
         let layouts = Layouts::new(&device);
@@ -275,6 +275,13 @@ impl<'a> State<'a> {
         &self.window
     }
 
+    /// Replaces the block texture array, the textures must keep the same format.
+    pub fn set_block_textures(&mut self, blocks: Vec<Vec<u8>>, blocks_len: u32) {
+        let bb: Vec<&[u8]> = blocks.iter().map(Vec::as_slice).collect();
+        let block_texture = texture::Texture::block_array(&self.device, &self.queue, &bb, blocks_len);
+        self.block_texutre_bg = bind_group::block_texture::get(&self.device, &self.layouts.block_texture, &block_texture);
+    }
+
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         if new_size.width <= 0 || new_size.height <= 0 {return};
 
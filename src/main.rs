@@ -1,9 +1,9 @@
 extern crate app;
-use std::{collections::HashMap, ffi::OsStr};
-use app::{content_loader::ContentLoader, voxels::live_voxels::{LiveVoxelRegistrator, LIVE_VOXEL_REGISTER}, Registrator};
+use std::{ffi::OsStr, path::{Path, PathBuf}};
+use app::{content_loader::{validate::validate_content, ContentDetails, ContentLoader, CONTENT_PACKS_PATH, GAME_PATH}, plugin::{PluginDescriptorFn, PluginError, API_VERSION_SYMBOL, DESCRIPTOR_SYMBOL, PLUGIN_API_VERSION}, wasm_plugin::load_wasm_plugin, Registrator};
 use libloading::Library;
 
-const LIB_FORMAT: &'static str = if cfg!(target_os = "windows") {
+const LIB_FORMAT: &str = if cfg!(target_os = "windows") {
     "dll"
 } else if cfg!(target_os = "linux") {
     "so"
@@ -15,40 +15,97 @@ const LIB_FORMAT: &'static str = if cfg!(target_os = "windows") {
 
 
 pub fn main() {
-    println!("{}", cfg!(target_endian = "little"));
-    // let content_loader = ContentLoader::new("./res/content/");
-    // let mut registrator = Registrator {
-    //     c: HashMap::new(),
-    //     from_bytes: HashMap::new(),
-    // };
-    // let libs: Vec<Library> = content_loader.details().values().filter(|d| d.active())
-    //     .map(|d| {
-    //         let path = d.path().join(&format!("mod.{}", LIB_FORMAT));
-    //         load_library(path, &mut registrator).ok()
-    //     }).flatten().collect();
-
-    // println!("{:?} {}", content_loader.load_indices("./res/game/"), libs.len());
-
-    // unsafe {
-    //     LIVE_VOXEL_REGISTER = Some(
-    //         LiveVoxelRegistrator {
-    //             deserialize: registrator.from_bytes,
-    //             new: registrator.c
-    //         }
-    //     )
-    // }
-    app::run();
+    let content_loader = ContentLoader::new(CONTENT_PACKS_PATH);
+    let mut registrator = Registrator::new();
+    let mods: Vec<Mod> = content_loader.load_order()
+        .filter_map(|d| load_mod(d, &mut registrator))
+        .collect();
+    content_loader.details().values().filter(|d| !d.active() && find_mod(d.path()).is_some())
+        .for_each(|d| println!("The mod \"{}\" will be skipped! Reason: the content pack is not active", d.info().name()));
+    println!("Loaded mods: {}", mods.len());
+
+    // Installs the content of every pack itself, the game is not started after it.
+    if std::env::args().any(|arg| arg == "validate-content") {
+        let problems = validate_content(&content_loader, GAME_PATH, registrator);
+        problems.iter().for_each(|problem| println!("{}", problem));
+        println!("Content problems: {}", problems.len());
+        std::process::exit(if problems.is_empty() {0} else {1});
+    }
+    if let Err(err) = registrator.install(&content_loader.content_paths(GAME_PATH)) {
+        eprintln!("Failed to load content: {}", err);
+        std::process::exit(1);
+    }
+
+    app::run_with(content_loader);
     println!("Exit!");
 
-    // libs.into_iter().for_each(|lib| lib.close().unwrap());
+    mods.into_iter().for_each(|m| if let Mod::Native(lib) = m {lib.close().unwrap()});
+}
+
+enum Mod {
+    Native(Library),
+    /// Kept alive by the live voxels it registered
+    Wasm,
+}
+
+/// Returns any `mod.*` library of the content pack, it may be built for another platform or to wasm.
+fn find_mod(pack: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(pack).ok()?.flatten()
+        .map(|entry| entry.path())
+        .find(|path| path.file_stem().is_some_and(|stem| stem == "mod"))
+}
+
+/// A native library for this platform is preferred over `mod.wasm`.
+fn load_mod(details: &ContentDetails, registrator: &mut Registrator) -> Option<Mod> {
+    let name = details.info().name();
+    let path = details.path().join(format!("mod.{}", LIB_FORMAT));
+    let wasm_path = details.path().join("mod.wasm");
+    if !path.exists() && wasm_path.exists() {
+        registrator.set_pack(name);
+        return match load_wasm_plugin(&wasm_path, name, registrator) {
+            Ok(()) => {
+                println!("The mod \"{}:{}\" was loaded from {:?}", name, details.info().version(), wasm_path);
+                Some(Mod::Wasm)
+            },
+            Err(err) => {
+                eprintln!("The mod \"{}\" will be skipped! Reason: {}", name, err);
+                None
+            },
+        };
+    }
+    if !path.exists() {
+        if let Some(other) = find_mod(details.path()) {
+            println!("The mod \"{}\" will be skipped! Reason: {:?} is not a {} library", name, other, LIB_FORMAT);
+        }
+        return None;
+    }
+    registrator.set_pack(name);
+    match load_library(&path, registrator) {
+        Ok(lib) => {
+            println!("The mod \"{}:{}\" was loaded from {:?}", name, details.info().version(), path);
+            Some(Mod::Native(lib))
+        },
+        Err(err) => {
+            eprintln!("The mod \"{}\" will be skipped! Reason: {}", name, err);
+            None
+        },
+    }
 }
 
 fn load_library(path: impl AsRef<OsStr>, registrator: &mut Registrator) -> Result<Library, Box<dyn std::error::Error>> {
     unsafe {
         let lib = libloading::Library::new(path)?;
-        let init: libloading::Symbol<unsafe extern fn (registrator: &mut Registrator) -> ()> = 
-            lib.get(b"init")?;
-        init(registrator);
+        // Only a plain u32 is read until the plugin is known to match this build.
+        let api_version: libloading::Symbol<*const u32> = lib.get(API_VERSION_SYMBOL)
+            .map_err(|_| PluginError::NotAPlugin)?;
+        if **api_version != PLUGIN_API_VERSION {
+            return Err(PluginError::ApiVersion { expected: PLUGIN_API_VERSION, found: **api_version }.into());
+        }
+        let descriptor: libloading::Symbol<PluginDescriptorFn> = lib.get(DESCRIPTOR_SYMBOL)
+            .map_err(|_| PluginError::NotAPlugin)?;
+        let descriptor = &*descriptor();
+        descriptor.check()?;
+        (descriptor.init)(registrator);
         Ok(lib)
     }
 }
\ No newline at end of file
@@ -0,0 +1,23 @@
+#!/bin/sh
+# Builds the test mod and installs it into the trashcan content pack.
+# Usage: testmod/install.sh [cargo build args], e.g. testmod/install.sh --release
+# The mod is not committed, it has to be built by the same compiler and from the same sources as the game.
+# It links the `app` library the game has already loaded, so it finds it after being copied.
+# The game is built with it into target/dynamic, only a game that links `app` dynamically shares it with the mod.
+set -e
+cd "$(dirname "$0")/.."
+target=target/dynamic
+RUSTFLAGS="$RUSTFLAGS -C prefer-dynamic -C rpath" CARGO_TARGET_DIR=$target cargo build -p manufactory -p testmod "$@"
+
+profile=debug
+for arg in "$@"; do
+    if [ "$arg" = "--release" ]; then profile=release; fi
+done
+
+case "$(uname -s)" in
+    Linux) lib=libtestmod.so; ext=so ;;
+    Darwin) lib=libtestmod.dylib; ext=dylib ;;
+    *) lib=testmod.dll; ext=dll ;;
+esac
+cp "$target/$profile/$lib" "res/content/trashcan/mod.$ext"
+echo "Installed res/content/trashcan/mod.$ext, run the game with $target/$profile/manufactory"
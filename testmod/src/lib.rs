@@ -1,6 +1,6 @@
 use std::sync::{Arc, Mutex, Weak};
 
-use app::{direction::Direction, gui::draw::Draw, player_unlockable, voxels::live_voxels::{DesiarializeLiveVoxel, LiveVoxelBehavior, NewLiveVoxel, PlayerUnlockable}, Registrator};
+use app::{direction::Direction, gui::draw::Draw, player_unlockable, recipes::{items::ItemFile, recipes::{ItemStackFile, RecipeFile}}, voxels::live_voxels::{DesiarializeLiveVoxel, LiveVoxelBehavior, NewLiveVoxel, PlayerUnlockable}, Registrator};
 
 #[no_mangle]
 pub fn super_ultra_test() -> app::coords::global_coord::GlobalCoord {
@@ -8,10 +8,29 @@ pub fn super_ultra_test() -> app::coords::global_coord::GlobalCoord {
     app::coords::global_coord::GlobalCoord::new(1, 2, 3)
 }
 
-#[no_mangle]
+app::declare_plugin!("trashcan", "0.0.1", init);
+
 pub fn init(registrator: &mut Registrator) {
-    registrator.c.insert(String::from("trashcan"), &create);
-    registrator.from_bytes.insert(String::from("trashcan"), &from_bytes);
+    registrator.live_voxel("trashcan", &create, &from_bytes);
+    registrator.item(ItemFile {
+        id: String::from("trashcan"),
+        stack_size: 50,
+        icon: 7,
+        block: Some(String::from("@trashcan:trashcan")),
+        tags: Vec::new(),
+        fuel: None,
+    });
+    registrator.recipe(RecipeFile {
+        id: String::from("trashcan"),
+        duration: 1.0,
+        crafter: vec![String::from("player"), String::from("assembler")],
+        category: vec![String::from("item")],
+        ingredients: vec![ItemStackFile { item: String::from("iron_plate"), count: 5 }],
+        result: Some(ItemStackFile { item: String::from("trashcan"), count: 1 }),
+        results: Vec::new(),
+        fluid_ingredients: Vec::new(),
+        fluid_results: Vec::new(),
+    });
 }
 
 #[no_mangle]